@@ -25,6 +25,7 @@ pub trait NamedNode: NodeBase {
 
 #[derive(Debug, PartialEq, Eq, NodeBase, Wrapper, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
 pub enum Node {
     /// 名前空間
     Ns(Namespace),
@@ -53,6 +54,7 @@ impl From<StatementOrExpression> for Node {
 
 #[derive(Debug, PartialEq, Eq, NodeBase, Wrapper, Serialize, Deserialize)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum StatementOrExpression {
     Statement(Statement),
     Expression(Expression),
@@ -87,6 +89,7 @@ impl NamedNode for Namespace {
 
 #[derive(Debug, PartialEq, Eq, NodeBase, Wrapper, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
 pub enum NamespaceMember {
     Ns(Namespace),
 
@@ -184,6 +187,10 @@ pub struct Return {
 pub struct Each {
     pub loc: Loc,
 
+    /// ラベル
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Utf16String>,
+
     /// each文
     pub var: Expression,
 
@@ -199,6 +206,10 @@ pub struct Each {
 pub struct For {
     pub loc: Loc,
 
+    /// ラベル
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Utf16String>,
+
     #[serde(flatten)]
     pub iter: ForIterator,
 
@@ -209,6 +220,7 @@ pub struct For {
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum ForIterator {
     Range {
         /// イテレータ変数名
@@ -230,6 +242,10 @@ pub enum ForIterator {
 pub struct Loop {
     pub loc: Loc,
 
+    /// ラベル
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Utf16String>,
+
     /// 処理
     pub statements: Vec<StatementOrExpression>,
 }
@@ -237,11 +253,19 @@ pub struct Loop {
 #[derive(Debug, PartialEq, Eq, NodeBase, Serialize, Deserialize)]
 pub struct Break {
     pub loc: Loc,
+
+    /// 終了するループのラベル
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Utf16String>,
 }
 
 #[derive(Debug, PartialEq, Eq, NodeBase, Serialize, Deserialize)]
 pub struct Continue {
     pub loc: Loc,
+
+    /// 次の繰り返しに移るループのラベル
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Utf16String>,
 }
 
 #[derive(Debug, PartialEq, Eq, NodeBase, Serialize, Deserialize)]
@@ -1,9 +1,16 @@
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+};
 
 use crate::position::Position;
 
 pub type Result<T> = core::result::Result<T, Box<dyn AiScriptError>>;
 
+/// 呼び出し履歴の内側と外側からそれぞれ記録する関数呼び出しの最大数。
+/// これを超える中間の関数呼び出しは省略します。
+pub const STACK_TRACE_LIMIT: usize = 10;
+
 pub trait AiScriptError: Debug {
     fn name(&self) -> &'static str;
 
@@ -11,10 +18,53 @@ pub trait AiScriptError: Debug {
 
     fn pos(&self) -> Option<Position>;
 
+    /// 実行時エラーが発生した時点の呼び出し履歴。
+    /// 内側の関数から順に並びます。
+    /// 長い場合は内側と外側の[`STACK_TRACE_LIMIT`]個ずつだけを含みます。
+    fn stack(&self) -> &[StackFrame] {
+        &[]
+    }
+
+    /// 呼び出し履歴から省略した関数呼び出しの数。
+    fn omitted_frames(&self) -> usize {
+        0
+    }
+
+    /// 実行時エラーに発生位置と呼び出し履歴を記録します。
+    /// 既に記録されている場合は何もしません。
+    fn record_trace(&mut self, _pos: Option<Position>, _stack: Vec<StackFrame>) {}
+
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.pos() {
-            Some(pos) => write!(f, "{}: {} ({})", self.name(), self.message(), pos),
-            None => write!(f, "{}: {}", self.name(), self.message()),
+            Some(pos) => write!(f, "{}: {} ({})", self.name(), self.message(), pos)?,
+            None => write!(f, "{}: {}", self.name(), self.message())?,
+        }
+        for (i, frame) in self.stack().iter().enumerate() {
+            if i == STACK_TRACE_LIMIT && self.omitted_frames() > 0 {
+                write!(f, "\n    ... {} more frames", self.omitted_frames())?;
+            }
+            write!(f, "\n    at {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// 呼び出し履歴の1つの関数呼び出し。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackFrame {
+    /// 関数名。無名関数やトップレベルの場合は`None`
+    pub name: Option<String>,
+
+    /// この関数の中で実行していた位置
+    pub pos: Option<Position>,
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.name.as_deref().unwrap_or("<anonymous>");
+        match &self.pos {
+            Some(pos) => write!(f, "{} ({})", name, pos),
+            None => write!(f, "{}", name),
         }
     }
 }
@@ -29,6 +79,15 @@ pub enum AiScriptBasicErrorKind {
 
     /// Interpret-time errors.
     Runtime,
+
+    /// Features not yet supported by this implementation.
+    NotImplemented,
+
+    /// Execution stopped by the host or the step limit.
+    Aborted,
+
+    /// Errors thrown by scripts, e.g. `Core:abort`.
+    User,
 }
 
 impl AiScriptBasicErrorKind {
@@ -37,6 +96,9 @@ impl AiScriptBasicErrorKind {
             AiScriptBasicErrorKind::Syntax => "Syntax",
             AiScriptBasicErrorKind::Namespace => "Namespace",
             AiScriptBasicErrorKind::Runtime => "Runtime",
+            AiScriptBasicErrorKind::NotImplemented => "NotImplemented",
+            AiScriptBasicErrorKind::Aborted => "Aborted",
+            AiScriptBasicErrorKind::User => "User",
         }
     }
 }
@@ -48,6 +110,12 @@ pub struct AiScriptBasicError {
     message: Cow<'static, str>,
 
     pos: Option<Position>,
+
+    /// 呼び出し履歴。記録前は`None`
+    stack: Option<Vec<StackFrame>>,
+
+    /// 呼び出し履歴から省略した関数呼び出しの数
+    omitted_frames: usize,
 }
 
 impl AiScriptBasicError {
@@ -60,6 +128,8 @@ impl AiScriptBasicError {
             kind,
             message: message.into(),
             pos,
+            stack: None,
+            omitted_frames: 0,
         }
     }
 }
@@ -76,6 +146,29 @@ impl AiScriptError for AiScriptBasicError {
     fn pos(&self) -> Option<Position> {
         self.pos.clone()
     }
+
+    fn stack(&self) -> &[StackFrame] {
+        self.stack.as_deref().unwrap_or(&[])
+    }
+
+    fn omitted_frames(&self) -> usize {
+        self.omitted_frames
+    }
+
+    fn record_trace(&mut self, pos: Option<Position>, mut stack: Vec<StackFrame>) {
+        if self.stack.is_some() {
+            return;
+        }
+        if self.pos.is_none() {
+            self.pos = pos;
+        }
+        if stack.len() > STACK_TRACE_LIMIT * 2 {
+            let end = stack.len() - STACK_TRACE_LIMIT;
+            stack.drain(STACK_TRACE_LIMIT..end);
+            self.omitted_frames = end - STACK_TRACE_LIMIT;
+        }
+        self.stack = Some(stack);
+    }
 }
 
 impl Debug for AiScriptBasicError {
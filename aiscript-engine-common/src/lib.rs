@@ -1,9 +1,13 @@
 mod error;
+mod number;
 mod path;
 mod position;
+mod stack;
 mod string;
 
 pub use error::*;
+pub use number::{parse_int, to_radix_string};
 pub use path::NamePath;
 pub use position::Position;
-pub use string::{FromUtf16Str, Utf16Str, Utf16String};
+pub use stack::StackGuard;
+pub use string::{num_to_string, CodePoints, FromUtf16Str, Utf16Str, Utf16String};
@@ -0,0 +1,249 @@
+use crate::{Utf16Str, Utf16String};
+
+const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// JavaScriptの`parseInt(s, radix)`と同じ規則で整数を解釈します。
+/// 解釈できない場合はNaNを返します。
+/// `radix`は2以上36以下である必要があります。
+pub fn parse_int(s: &Utf16Str, radix: u32) -> f64 {
+    debug_assert!((2..=36).contains(&radix));
+    let s = s.trim().as_u16s();
+    let (negative, s) = match s.first() {
+        Some(0x2d) => (true, &s[1..]),
+        Some(0x2b) => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let s = match s {
+        [0x30, 0x58 | 0x78, rest @ ..] if radix == 16 => rest,
+        _ => s,
+    };
+    let digits: Vec<u32> = s
+        .iter()
+        .map_while(|&c| char::from_u32(c as u32)?.to_digit(radix))
+        .collect();
+    if digits.is_empty() {
+        return f64::NAN;
+    }
+    let value = if radix.is_power_of_two() {
+        digits_to_f64_pow2(&digits, radix.trailing_zeros())
+    } else if radix == 10 {
+        let digits: String = digits
+            .iter()
+            .map(|&digit| DIGITS[digit as usize] as char)
+            .collect();
+        digits.parse().unwrap_or(f64::NAN)
+    } else {
+        // 2の累乗と10以外の基数では近似値でよい
+        digits
+            .iter()
+            .fold(0.0, |acc, &digit| acc * radix as f64 + digit as f64)
+    };
+    return if negative { -value } else { value };
+}
+
+/// 2の累乗を基数とする数字の列を、最近接偶数丸めで浮動小数点数に変換します。
+fn digits_to_f64_pow2(digits: &[u32], bits_per_digit: u32) -> f64 {
+    let digits = match digits.iter().position(|&digit| digit != 0) {
+        Some(start) => &digits[start..],
+        None => return 0.0,
+    };
+    // 先頭の64ビット分を仮数に取り込み、残りは指数と端数の有無として扱う
+    let mut mantissa: u64 = 0;
+    let mut exponent: i32 = 0;
+    let mut sticky = false;
+    for &digit in digits {
+        if mantissa.leading_zeros() >= bits_per_digit {
+            mantissa = (mantissa << bits_per_digit) | digit as u64;
+        } else {
+            exponent = exponent.saturating_add(bits_per_digit as i32);
+            sticky |= digit != 0;
+        }
+    }
+    let bit_len = 64 - mantissa.leading_zeros();
+    if bit_len <= 53 && !sticky {
+        return mantissa as f64 * 2f64.powi(exponent);
+    }
+    let shift = bit_len.saturating_sub(53);
+    let mut truncated = mantissa >> shift;
+    let rest = mantissa & ((1u64 << shift) - 1);
+    let half = if shift == 0 { 0 } else { 1u64 << (shift - 1) };
+    let round_up = if shift == 0 {
+        false
+    } else {
+        rest > half || (rest == half && (sticky || truncated & 1 == 1))
+    };
+    if round_up {
+        truncated += 1;
+    }
+    return truncated as f64 * 2f64.powi(exponent.saturating_add(shift as i32));
+}
+
+/// JavaScriptの`Number.prototype.toString(radix)`と同じ文字列に変換します。
+/// `radix`は10以外の2以上36以下である必要があります。
+pub fn to_radix_string(value: f64, radix: u32) -> Utf16String {
+    debug_assert!((2..=36).contains(&radix) && radix != 10);
+    if value.is_nan() {
+        return Utf16String::from("NaN");
+    }
+    if value == f64::INFINITY {
+        return Utf16String::from("Infinity");
+    }
+    if value == f64::NEG_INFINITY {
+        return Utf16String::from("-Infinity");
+    }
+    if value == 0.0 {
+        return Utf16String::from("0");
+    }
+    // V8のDoubleToRadixCStringに準じる
+    let radix_f = radix as f64;
+    let negative = value < 0.0;
+    let value = value.abs();
+    let mut integer = value.floor();
+    let mut fraction = value - integer;
+    // 入力の精度の範囲でのみ小数部の桁を求める
+    let mut delta = 0.5 * (f64::from_bits(value.to_bits() + 1) - value);
+    delta = delta.max(f64::from_bits(1));
+    let mut fraction_digits: Vec<u32> = Vec::new();
+    if fraction >= delta {
+        loop {
+            fraction *= radix_f;
+            delta *= radix_f;
+            let digit = fraction as u32;
+            fraction_digits.push(digit);
+            fraction -= digit as f64;
+            if (fraction > 0.5 || (fraction == 0.5 && digit & 1 == 1)) && fraction + delta > 1.0 {
+                // 繰り上がりを書き込み済みの桁に反映する
+                loop {
+                    match fraction_digits.pop() {
+                        Some(digit) if digit + 1 < radix => {
+                            fraction_digits.push(digit + 1);
+                            break;
+                        }
+                        Some(_) => {}
+                        None => {
+                            integer += 1.0;
+                            break;
+                        }
+                    }
+                }
+                break;
+            }
+            if fraction < delta {
+                break;
+            }
+        }
+    }
+
+    // 表現できない下位の桁は0で埋める
+    let mut integer_digits: Vec<u32> = Vec::new();
+    while exponent(integer / radix_f) > 0 {
+        integer /= radix_f;
+        integer_digits.push(0);
+    }
+    loop {
+        let remainder = integer % radix_f;
+        integer_digits.push(remainder as u32);
+        integer = (integer - remainder) / radix_f;
+        if integer <= 0.0 {
+            break;
+        }
+    }
+
+    let mut result = String::new();
+    if negative {
+        result.push('-');
+    }
+    result.extend(
+        integer_digits
+            .iter()
+            .rev()
+            .map(|&digit| DIGITS[digit as usize] as char),
+    );
+    if !fraction_digits.is_empty() {
+        result.push('.');
+        result.extend(
+            fraction_digits
+                .iter()
+                .map(|&digit| DIGITS[digit as usize] as char),
+        );
+    }
+    return Utf16String::from(result.as_str());
+}
+
+/// V8の`Double::Exponent`と同様に、仮数を整数とみなしたときの指数を返します。
+fn exponent(value: f64) -> i32 {
+    let biased = ((value.to_bits() >> 52) & 0x7ff) as i32;
+    if biased == 0 {
+        return 1 - 1075;
+    }
+    return biased - 1075;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str, radix: u32) -> f64 {
+        parse_int(&Utf16String::from(s), radix)
+    }
+
+    fn radix(value: f64, radix: u32) -> String {
+        to_radix_string(value, radix).to_string()
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(parse("ff", 16), 255.0);
+        assert_eq!(parse("  -0xFFg", 16), -255.0);
+        assert_eq!(parse("+0X10", 16), 16.0);
+        assert_eq!(parse("1fffffffffffff", 16), 9007199254740991.0);
+        // 最近接偶数への丸め
+        assert_eq!(parse("20000000000001", 16), 9007199254740992.0);
+        assert_eq!(parse("20000000000003", 16), 9007199254740996.0);
+        assert_eq!(
+            parse("200000000000010000000001", 16),
+            9007199254740994.0 * 2f64.powi(40)
+        );
+        assert_eq!(parse("ffffffffffffffffffff", 16), 1.2089258196146292e24);
+        assert_eq!(parse(&"f".repeat(300), 16), f64::INFINITY);
+        assert_eq!(parse("000000000000000000000000001", 16), 1.0);
+        assert!(parse("0x", 16).is_nan());
+        assert!(parse("g", 16).is_nan());
+        assert!(parse("", 16).is_nan());
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(parse(" 42px", 10), 42.0);
+        assert_eq!(parse("-0012", 10), -12.0);
+        assert!(parse("0x10", 10) == 0.0);
+        assert!(parse("abc", 10).is_nan());
+    }
+
+    #[test]
+    fn radix_integer() {
+        assert_eq!(radix(255.0, 16), "ff");
+        assert_eq!(radix(-255.0, 16), "-ff");
+        assert_eq!(radix(0.0, 16), "0");
+        assert_eq!(radix(-0.0, 16), "0");
+        assert_eq!(radix(9007199254740991.0, 16), "1fffffffffffff");
+        assert_eq!(radix(1e21, 16), "3635c9adc5dea00000");
+        assert_eq!(radix(f64::MAX, 36).len(), 199);
+        assert_eq!(radix(f64::NAN, 16), "NaN");
+        assert_eq!(radix(f64::NEG_INFINITY, 16), "-Infinity");
+    }
+
+    #[test]
+    fn radix_fraction() {
+        assert_eq!(radix(0.5, 16), "0.8");
+        assert_eq!(radix(0.1, 16), "0.1999999999999a");
+        assert_eq!(radix(-1.5, 2), "-1.1");
+        assert_eq!(radix(1234.5678, 16), "4d2.915b573eab4");
+        assert_eq!(radix(1e-7, 16), "0.000001ad7f29abcaf48");
+        assert_eq!(radix(255.99999999999997, 16), "ff.fffffffffff8");
+        assert_eq!(radix(123.456, 36), "3f.gez4w97ry");
+        assert_eq!(radix(1.0 / 3.0, 3), "0.1");
+        assert_eq!(radix(0.7, 7), "0.4620462046204620461");
+        assert_eq!(radix(5e-324, 2).len(), 1076);
+    }
+}
@@ -1,4 +1,7 @@
-use std::{borrow::Borrow, fmt::Display};
+use std::{
+    borrow::{Borrow, Cow},
+    fmt::Display,
+};
 
 use serde::{Deserialize, Serialize};
 use utf16_literal::utf16;
@@ -15,6 +18,12 @@ pub struct NamePath {
     inner: Utf16String,
 }
 
+impl Default for NamePath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NamePath {
     pub fn new() -> Self {
         NamePath {
@@ -88,6 +97,18 @@ impl From<&Utf16Str> for NamePath {
     }
 }
 
+impl<'a> From<&'a NamePath> for Cow<'a, NamePath> {
+    fn from(value: &'a NamePath) -> Self {
+        Cow::Borrowed(value)
+    }
+}
+
+impl From<NamePath> for Cow<'_, NamePath> {
+    fn from(value: NamePath) -> Self {
+        Cow::Owned(value)
+    }
+}
+
 impl Display for NamePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_utf16_str())
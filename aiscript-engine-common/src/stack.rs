@@ -0,0 +1,86 @@
+use std::cell::Cell;
+
+/// 再帰的な処理に使用できるホストのスタックの大きさ。
+/// 1段の再帰で使用するスタックは処理の内容やビルドの設定によって大きく変わるため、
+/// 深さではなくスタックの使用量で制限します。
+/// 既定のスレッドのスタック(2MiB)でもオーバーフローしない大きさにしています。
+const MAX_STACK_USAGE: usize = 1024 * 1024;
+
+thread_local! {
+    /// 最も外側の[`StackGuard`]を作成した時点のスタックの位置
+    static STACK_BASE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// 現在のスタックの位置を返します。
+#[inline(never)]
+fn stack_pointer() -> usize {
+    let marker = 0_u8;
+    return std::hint::black_box(&marker) as *const u8 as usize;
+}
+
+/// 再帰的な処理の各段階で作成し、ホストのスタックの使用量を確認するためのガード。
+/// スレッドで最も外側のガードを作成した位置を基準として測ります。
+pub struct StackGuard {
+    is_outermost: bool,
+    exceeded: bool,
+}
+
+impl StackGuard {
+    pub fn new() -> Self {
+        let pointer = stack_pointer();
+        let base = STACK_BASE.get();
+        if let Some(base) = base {
+            return StackGuard {
+                is_outermost: false,
+                exceeded: base.abs_diff(pointer) > MAX_STACK_USAGE,
+            };
+        }
+        STACK_BASE.set(Some(pointer));
+        return StackGuard {
+            is_outermost: true,
+            exceeded: false,
+        };
+    }
+
+    /// スタックの使用量が上限を超えているかを返します。
+    pub fn exceeded(&self) -> bool {
+        return self.exceeded;
+    }
+}
+
+impl Default for StackGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for StackGuard {
+    fn drop(&mut self) {
+        if self.is_outermost {
+            STACK_BASE.set(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recurse(depth: usize) -> Option<usize> {
+        let guard = StackGuard::new();
+        if guard.exceeded() {
+            return Some(depth);
+        }
+        let padding = std::hint::black_box([0_u8; 1024]);
+        return recurse(depth + 1).map(|depth| depth + padding[0] as usize);
+    }
+
+    #[test]
+    fn limit_usage() {
+        let depth = recurse(0).unwrap();
+        assert!(depth < MAX_STACK_USAGE / 1024);
+
+        // 最も外側のガードの破棄で基準が解除される
+        assert_eq!(STACK_BASE.get(), None);
+    }
+}
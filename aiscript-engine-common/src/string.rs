@@ -10,6 +10,7 @@ use std::{
 };
 
 use serde::{de::Visitor, Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// 参照として使用できるUTF-16文字列。
 /// サロゲートペアが完全である必要はない。
@@ -44,9 +45,186 @@ impl Utf16Str {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn parse<F: FromUtf16Str>(&self) -> Result<F, F::Err> {
         F::from(self)
     }
+
+    /// `index`番目のコード単位から始まる符号位置を返す。
+    /// ECMAScriptの`String.prototype.codePointAt`と同様に、不完全なサロゲートはそのまま返す。
+    pub fn code_point_at(&self, index: usize) -> Option<u32> {
+        let first = *self.data.get(index)?;
+        if is_high_surrogate(first) {
+            if let Some(&second) = self.data.get(index + 1) {
+                if is_low_surrogate(second) {
+                    return Some(
+                        0x10000 + ((first as u32 - 0xD800) << 10) + (second as u32 - 0xDC00),
+                    );
+                }
+            }
+        }
+        return Some(first as u32);
+    }
+
+    /// 符号位置ごとに分割する。
+    /// 不完全なサロゲートは1つの符号位置として扱う。
+    pub fn code_points(&self) -> CodePoints<'_> {
+        CodePoints { rest: &self.data }
+    }
+
+    /// 拡張書記素クラスタごとに分割する。
+    /// 不完全なサロゲートは1つの書記素として扱う。
+    pub fn graphemes(&self) -> Vec<&Utf16Str> {
+        let mut result = Vec::new();
+        let mut start = 0;
+        while start < self.data.len() {
+            // 不完全なサロゲートを含まない範囲を探す
+            let mut buf = String::new();
+            for char in decode_utf16(self.data[start..].iter().copied()) {
+                let Ok(char) = char else {
+                    break;
+                };
+                buf.push(char);
+            }
+            if buf.is_empty() {
+                // 不完全なサロゲート
+                result.push(Utf16Str::new(&self.data[start..start + 1]));
+                start += 1;
+                continue;
+            }
+            for grapheme in buf.graphemes(true) {
+                let len: usize = grapheme.chars().map(char::len_utf16).sum();
+                result.push(Utf16Str::new(&self.data[start..start + len]));
+                start += len;
+            }
+        }
+        return result;
+    }
+
+    /// `from`番目のコード単位以降で`pattern`が最初に現れる位置を返す。
+    pub fn find(&self, pattern: &Utf16Str, from: usize) -> Option<usize> {
+        if from > self.data.len() {
+            return None;
+        }
+        if pattern.is_empty() {
+            return Some(from);
+        }
+        return self.data[from..]
+            .windows(pattern.len())
+            .position(|window| window == pattern.as_u16s())
+            .map(|index| index + from);
+    }
+
+    /// ECMAScriptの`String.prototype.split`と同様に`separator`で分割する。
+    /// `separator`が空の場合はコード単位ごとに分割する。
+    pub fn split(&self, separator: &Utf16Str) -> Vec<&Utf16Str> {
+        if separator.is_empty() {
+            return self
+                .data
+                .iter()
+                .map(|char| Utf16Str::new(slice::from_ref(char)))
+                .collect();
+        }
+        let mut result = Vec::new();
+        let mut start = 0;
+        while let Some(index) = self.find(separator, start) {
+            result.push(Utf16Str::new(&self.data[start..index]));
+            start = index + separator.len();
+        }
+        result.push(Utf16Str::new(&self.data[start..]));
+        return result;
+    }
+
+    /// 先頭と末尾の空白と改行を取り除く。
+    pub fn trim(&self) -> &Utf16Str {
+        let data = &self.data;
+        let start = data
+            .iter()
+            .position(|&char| !is_white_space(char))
+            .unwrap_or(data.len());
+        let end = data
+            .iter()
+            .rposition(|&char| !is_white_space(char))
+            .map_or(start, |index| index + 1);
+        return Utf16Str::new(&data[start..end]);
+    }
+
+    /// 大文字に変換する。
+    pub fn to_uppercase(&self) -> Utf16String {
+        self.map_chars(str::to_uppercase)
+    }
+
+    /// 小文字に変換する。
+    pub fn to_lowercase(&self) -> Utf16String {
+        self.map_chars(str::to_lowercase)
+    }
+
+    /// 不完全なサロゲートを保ったまま、それ以外の部分を変換する。
+    fn map_chars(&self, f: fn(&str) -> String) -> Utf16String {
+        let mut result = Utf16String::with_capacity(self.len());
+        let mut buf = String::new();
+        for char in decode_utf16(self.data.iter().copied()) {
+            match char {
+                Ok(char) => buf.push(char),
+                Err(error) => {
+                    result.extend(f(&buf).encode_utf16());
+                    buf.clear();
+                    result.push(error.unpaired_surrogate());
+                }
+            }
+        }
+        result.extend(f(&buf).encode_utf16());
+        return result;
+    }
+}
+
+/// [Utf16Str::code_points]が返すイテレータ。
+pub struct CodePoints<'a> {
+    rest: &'a [u16],
+}
+
+impl<'a> Iterator for CodePoints<'a> {
+    type Item = &'a Utf16Str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = *self.rest.first()?;
+        let len = match self.rest.get(1) {
+            Some(&second) if is_high_surrogate(first) && is_low_surrogate(second) => 2,
+            _ => 1,
+        };
+        let (code_point, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        return Some(Utf16Str::new(code_point));
+    }
+}
+
+fn is_high_surrogate(char: u16) -> bool {
+    (0xD800..=0xDBFF).contains(&char)
+}
+
+fn is_low_surrogate(char: u16) -> bool {
+    (0xDC00..=0xDFFF).contains(&char)
+}
+
+/// ECMAScriptの`WhiteSpace`または`LineTerminator`であるか
+fn is_white_space(char: u16) -> bool {
+    matches!(
+        char,
+        0x0009..=0x000D
+            | 0x0020
+            | 0x00A0
+            | 0x1680
+            | 0x2000..=0x200A
+            | 0x2028
+            | 0x2029
+            | 0x202F
+            | 0x205F
+            | 0x3000
+            | 0xFEFF
+    )
 }
 
 impl ToOwned for Utf16Str {
@@ -73,7 +251,7 @@ impl IndexMut<usize> for Utf16Str {
 
 impl Display for Utf16Str {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for char in decode_utf16(self.data.iter().map(|r| r.clone())) {
+        for char in decode_utf16(self.data.iter().copied()) {
             match char {
                 Ok(char) => write!(f, "{}", char)?,
                 Err(_) => write!(f, "\u{FFFD}")?, // U+FFFD: REPLACEMENT CHARACTER
@@ -95,7 +273,7 @@ impl<'a> IntoIterator for &'a Utf16Str {
     type IntoIter = iter::Cloned<slice::Iter<'a, u16>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.data.into_iter().cloned()
+        self.data.iter().cloned()
     }
 }
 
@@ -131,7 +309,7 @@ impl Utf16String {
     }
 
     pub fn as_utf16_str(&self) -> &Utf16Str {
-        &Utf16Str::new(&self.data)
+        Utf16Str::new(&self.data)
     }
 
     pub fn as_mut_utf16_str(&mut self) -> &mut Utf16Str {
@@ -146,6 +324,22 @@ impl Utf16String {
         self.data.push(ch);
     }
 
+    /// 符号位置を追加する。
+    /// サロゲートの範囲の符号位置はそのまま1つのコード単位として追加する。
+    ///
+    /// # Panics
+    /// 符号位置が0x10FFFFを超える場合
+    pub fn push_code_point(&mut self, code_point: u32) {
+        assert!(code_point <= 0x10FFFF, "invalid code point: {}", code_point);
+        if code_point < 0x10000 {
+            self.data.push(code_point as u16);
+        } else {
+            let code_point = code_point - 0x10000;
+            self.data.push(0xD800 + (code_point >> 10) as u16);
+            self.data.push(0xDC00 + (code_point & 0x3FF) as u16);
+        }
+    }
+
     /// sepを区切り文字として引数に与えらえれた文字列を結合する。
     pub fn join<S>(strings: &[S], sep: &Utf16Str) -> Utf16String
     where
@@ -158,7 +352,7 @@ impl Utf16String {
         } else {
             return result;
         }
-        while let Some(item) = iter.next() {
+        for item in iter {
             result += sep;
             result += item.borrow();
         }
@@ -201,7 +395,7 @@ impl IndexMut<usize> for Utf16String {
 impl<'a> FromIterator<&'a u16> for Utf16String {
     fn from_iter<T: IntoIterator<Item = &'a u16>>(iter: T) -> Self {
         Utf16String {
-            data: iter.into_iter().map(|&value| value).collect(),
+            data: iter.into_iter().copied().collect(),
         }
     }
 }
@@ -360,6 +554,62 @@ impl FromUtf16Str for f64 {
     }
 }
 
+/// 数値をJavaScriptの`Number.prototype.toString`と同じ文字列に変換します。
+/// 元の値に戻せる最短の桁数で表し、絶対値が1e21以上または1e-6未満の場合は指数表記になります。
+pub fn num_to_string(value: f64) -> Utf16String {
+    if value.is_nan() {
+        return Utf16String::from("NaN");
+    }
+    if value == 0.0 {
+        // -0も0になる
+        return Utf16String::from("0");
+    }
+    if value.is_infinite() {
+        return Utf16String::from(if value < 0.0 { "-Infinity" } else { "Infinity" });
+    }
+
+    // Rustの指数表記は最短で元の値に戻せる桁を出力する
+    let formatted = format!("{:e}", value.abs());
+    let (mantissa, exponent) = formatted
+        .split_once('e')
+        .expect("exponential notation should contain 'e'");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent
+        .parse::<i32>()
+        .expect("exponent should be an integer")
+        + 1;
+
+    let mut result = String::new();
+    if value < 0.0 {
+        result.push('-');
+    }
+    if k <= n && n <= 21 {
+        result.push_str(&digits);
+        result.extend(iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        result.push_str(integer);
+        result.push('.');
+        result.push_str(fraction);
+    } else if -6 < n && n <= 0 {
+        result.push_str("0.");
+        result.extend(iter::repeat_n('0', -n as usize));
+        result.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        result.push_str(first);
+        if !rest.is_empty() {
+            result.push('.');
+            result.push_str(rest);
+        }
+        result.push('e');
+        result.push(if n - 1 < 0 { '-' } else { '+' });
+        result.push_str(&(n - 1).abs().to_string());
+    }
+    return Utf16String::from(result.as_str());
+}
+
 #[cfg(test)]
 mod tests {
     use utf16_literal::utf16;
@@ -401,6 +651,114 @@ mod tests {
             assert_eq!(s.parse::<f64>().unwrap(), 9.75);
         }
 
+        #[test]
+        fn num_to_string() {
+            let cases: &[(f64, &str)] = &[
+                (0.0, "0"),
+                (-0.0, "0"),
+                (f64::NAN, "NaN"),
+                (f64::INFINITY, "Infinity"),
+                (f64::NEG_INFINITY, "-Infinity"),
+                (1.0, "1"),
+                (-42.0, "-42"),
+                (0.1, "0.1"),
+                (0.1 + 0.2, "0.30000000000000004"),
+                (1.5e-6, "0.0000015"),
+                (1e-7, "1e-7"),
+                (-1.25e-7, "-1.25e-7"),
+                (123456789012345680000.0, "123456789012345680000"),
+                (1e21, "1e+21"),
+                (1.2345e22, "1.2345e+22"),
+                (f64::MAX, "1.7976931348623157e+308"),
+                (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+                (5e-324, "5e-324"),
+                (9007199254740993.0, "9007199254740992"),
+            ];
+            for &(value, expected) in cases {
+                assert_eq!(super::super::num_to_string(value).to_string(), expected);
+            }
+        }
+
+        #[test]
+        fn code_point_at() {
+            let s = Utf16Str::new(&utf16!("a🥰"));
+            assert_eq!(s.code_point_at(0), Some(0x61));
+            assert_eq!(s.code_point_at(1), Some(0x1F970));
+            assert_eq!(s.code_point_at(2), Some(0xDD70));
+            assert_eq!(s.code_point_at(3), None);
+        }
+
+        #[test]
+        fn code_points() {
+            let data = utf16!("a🥰");
+            let s = Utf16Str::new(&data);
+            let code_points: Vec<_> = s.code_points().collect();
+            assert_eq!(
+                code_points,
+                [Utf16Str::new(&data[0..1]), Utf16Str::new(&data[1..3])]
+            );
+
+            let s = Utf16Str::new(&data[0..2]);
+            assert_eq!(s.code_points().count(), 2);
+        }
+
+        #[test]
+        fn graphemes() {
+            let s = Utf16Str::new(&utf16!("a👍🏽🇯🇵"));
+            let graphemes: Vec<String> = s.graphemes().iter().map(|s| s.to_string()).collect();
+            assert_eq!(graphemes, ["a", "👍🏽", "🇯🇵"]);
+
+            let data = utf16!("a🥰b");
+            let mut data = data.to_vec();
+            data.remove(2);
+            let s = Utf16Str::new(&data);
+            assert_eq!(s.graphemes().len(), 3);
+        }
+
+        #[test]
+        fn find() {
+            let s = Utf16Str::new(&utf16!("abcabc"));
+            assert_eq!(s.find(Utf16Str::new(&utf16!("bc")), 0), Some(1));
+            assert_eq!(s.find(Utf16Str::new(&utf16!("bc")), 2), Some(4));
+            assert_eq!(s.find(Utf16Str::new(&utf16!("d")), 0), None);
+            assert_eq!(s.find(Utf16Str::new(&[]), 6), Some(6));
+            assert_eq!(s.find(Utf16Str::new(&[]), 7), None);
+        }
+
+        #[test]
+        fn split() {
+            let s = Utf16Str::new(&utf16!("a,b,,c"));
+            let parts: Vec<String> = s
+                .split(Utf16Str::new(&utf16!(",")))
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            assert_eq!(parts, ["a", "b", "", "c"]);
+
+            let s = Utf16Str::new(&utf16!("abc"));
+            assert_eq!(s.split(Utf16Str::new(&[])).len(), 3);
+            assert_eq!(Utf16Str::new(&[]).split(Utf16Str::new(&[])).len(), 0);
+        }
+
+        #[test]
+        fn trim() {
+            let s = Utf16Str::new(&utf16!(" \t\nabc\u{3000}"));
+            assert_eq!(s.trim(), Utf16Str::new(&utf16!("abc")));
+            let s = Utf16Str::new(&utf16!("  "));
+            assert!(s.trim().is_empty());
+        }
+
+        #[test]
+        fn case() {
+            let s = Utf16Str::new(&utf16!("abCß"));
+            assert_eq!(s.to_uppercase(), Utf16String::from("ABCSS"));
+            assert_eq!(s.to_lowercase(), Utf16String::from("abcß"));
+
+            let data = [0xD800, utf16!('a')];
+            let s = Utf16Str::new(&data);
+            assert_eq!(s.to_uppercase().as_u16s(), [0xD800, utf16!('A')]);
+        }
+
         #[test]
         fn join() {
             let sep = Utf16Str::new(&utf16!(" "));
@@ -520,6 +878,15 @@ mod tests {
             assert_eq!(format!("{:?}", Utf16String::from("abc")), original);
         }
 
+        #[test]
+        fn push_code_point() {
+            let mut s = Utf16String::new();
+            s.push_code_point(0x61);
+            s.push_code_point(0x1F970);
+            s.push_code_point(0xD800);
+            assert_eq!(s.as_u16s(), [0x61, 0xD83E, 0xDD70, 0xD800]);
+        }
+
         #[test]
         fn into_iter() {
             let mut iter = Utf16String::from_iter(&utf16!("abc")).into_iter();
@@ -0,0 +1,30 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// 実行中のインタプリタを停止させるためのハンドル。
+/// 別のスレッドやネイティブ関数のコールバックから停止を要求できます。
+#[derive(Clone, Debug, Default)]
+pub struct AbortHandle {
+    aborted: Arc<AtomicBool>,
+}
+
+impl AbortHandle {
+    pub fn new() -> Self {
+        AbortHandle {
+            aborted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 実行の停止を要求します。
+    /// 実行中の命令が終わった時点で停止します。
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// 停止が要求されているかを返します。
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+}
@@ -1,7 +1,11 @@
-use std::borrow::Cow;
+use std::rc::Rc;
 
-use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result};
-use aiscript_engine_values::{require_any, require_boolean, require_number, Value};
+use aiscript_engine_common::Result;
+use aiscript_engine_values::{
+    require_any, require_array, require_boolean, require_function, require_number, require_object,
+    require_string, VArr, VFn, VObj, Value,
+};
+use gc::{Gc, GcCell};
 
 /// 関数の引数を取り出す。
 pub(crate) struct Arguments {
@@ -32,4 +36,44 @@ impl Arguments {
     pub(crate) fn expect_number(&mut self) -> Result<f64> {
         require_number(&self.next())
     }
+
+    pub(crate) fn expect_string(&mut self) -> Result<Rc<[u16]>> {
+        require_string(&self.next())
+    }
+
+    /// 省略された場合は`None`を返します。
+    pub(crate) fn expect_optional_number(&mut self) -> Result<Option<f64>> {
+        match self.next() {
+            Value::Uninitialized => Ok(None),
+            value => require_number(&value).map(Some),
+        }
+    }
+
+    /// 省略された場合は`None`を返します。
+    pub(crate) fn expect_optional_string(&mut self) -> Result<Option<Rc<[u16]>>> {
+        match self.next() {
+            Value::Uninitialized => Ok(None),
+            value => require_string(&value).map(Some),
+        }
+    }
+
+    pub(crate) fn expect_array(&mut self) -> Result<Gc<GcCell<VArr>>> {
+        require_array(&self.next())
+    }
+
+    pub(crate) fn expect_object(&mut self) -> Result<Gc<GcCell<VObj>>> {
+        require_object(&self.next())
+    }
+
+    pub(crate) fn expect_function(&mut self) -> Result<Gc<GcCell<VFn>>> {
+        require_function(&self.next())
+    }
+
+    /// 省略された場合は`None`を返します。
+    pub(crate) fn expect_optional_any(&mut self) -> Option<Value> {
+        match self.next() {
+            Value::Uninitialized => None,
+            value => Some(value),
+        }
+    }
 }
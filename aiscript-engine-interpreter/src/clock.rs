@@ -0,0 +1,37 @@
+use std::{
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// `Date`名前空間の関数や`Core:sleep`が参照する時計。
+/// テストなどで時刻を固定したい場合は、独自の実装を`InterpreterOpts::clock`で返します。
+pub trait Clock {
+    /// 現在時刻をUNIXエポックからのミリ秒で返します。
+    fn now(&self) -> f64;
+
+    /// 指定した時刻におけるUTCと地方時の差を分で返します。
+    /// JavaScriptの`Date.prototype.getTimezoneOffset`と同様に、UTCより進んでいる地域では負の値になります。
+    fn timezone_offset(&self, _time: f64) -> f64 {
+        0.0
+    }
+
+    /// 指定したミリ秒だけ待機します。
+    /// `Core:sleep`から短い間隔に区切って呼び出されます。
+    fn sleep(&self, ms: f64) {
+        thread::sleep(Duration::from_secs_f64(ms / 1000.0));
+    }
+}
+
+/// システムの時刻を返す時計。
+/// 標準ライブラリだけでは地方時を取得できないため、タイムゾーンはUTCとして扱います。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as f64,
+            Err(error) => -(error.duration().as_millis() as f64),
+        }
+    }
+}
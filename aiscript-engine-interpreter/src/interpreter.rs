@@ -1,31 +1,156 @@
 //! AiScript interpreter
 
 use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
 
 use aiscript_engine_ast as ast;
-use aiscript_engine_common::Result;
+use aiscript_engine_common::{Result, Utf16String};
 use utf16_literal::utf16;
 
+use crate::abort::AbortHandle;
 use crate::arguments::Arguments;
-use crate::ir::Translator;
+use crate::clock::{Clock, SystemClock};
+use crate::ir::{GlobalVariables, Ir, Translator};
 use crate::library::{std_library, LibraryValue, NativeFn};
+use crate::random::{DefaultRandomSource, RandomSource};
 use crate::vm::{Value, Vm};
 
 pub trait InterpreterOpts {
     fn out(&self, value: Value);
+
+    /// 実行できる命令数の上限。超えた場合は実行を中断します。
+    fn max_step(&self) -> Option<usize> {
+        None
+    }
+
+    /// `Date`名前空間の関数や`Core:sleep`が参照する時計。
+    fn clock(&self) -> Rc<dyn Clock> {
+        Rc::new(SystemClock)
+    }
+
+    /// `Math:rnd`や`Util:uuid`が使う乱数源。
+    fn random_source(&self) -> Rc<dyn RandomSource> {
+        Rc::new(DefaultRandomSource)
+    }
 }
 
 pub struct Interpreter {
     opts: Rc<dyn InterpreterOpts>,
+    abort_handle: AbortHandle,
+
+    /// ホストが登録した値
+    library: Vec<(Utf16String, LibraryValue)>,
+
+    /// 実行後の関数呼び出しのため、実行をまたいで保持する
+    vm: Vm,
+
+    /// 実行をまたいでグローバル変数を保持するか
+    session: bool,
+
+    /// 次の実行から参照できるグローバル変数。
+    /// ライブラリを登録するまでは`None`
+    globals: Option<GlobalVariables>,
+
+    /// 実行ごとに登録したユーザー関数の番号の範囲。
+    /// 関数の値が残っていない実行の関数は破棄し、その位置を以降の実行で再利用する
+    runs: Vec<Range<usize>>,
 }
 
 impl Interpreter {
     pub fn new(opts: Rc<dyn InterpreterOpts>) -> Self {
-        Interpreter { opts }
+        let abort_handle = AbortHandle::new();
+        let mut vm = Vm::new();
+        vm.set_max_step(opts.max_step());
+        vm.set_abort_handle(abort_handle.clone());
+        vm.set_clock(opts.clock());
+        vm.set_random_source(opts.random_source());
+        Interpreter {
+            opts,
+            abort_handle,
+            library: Vec::new(),
+            vm,
+            session: false,
+            globals: None,
+            runs: Vec::new(),
+        }
+    }
+
+    /// ホストの値や関数を登録するためのビルダーを返します。
+    pub fn builder(opts: Rc<dyn InterpreterOpts>) -> InterpreterBuilder {
+        InterpreterBuilder {
+            interpreter: Interpreter::new(opts),
+        }
     }
 
+    /// 実行を停止させるためのハンドルを返します。
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort_handle.clone()
+    }
+
+    /// 実行を停止させます。
+    /// 以降の実行も停止したままになります。
+    pub fn abort(&self) {
+        self.abort_handle.abort();
+    }
+
+    /// プログラムを実行し、最後に評価した値を返します。
     pub fn run(&mut self, program: &[ast::Node]) -> Result<Value> {
+        return self.exec(|translator| translator.translate(program));
+    }
+
+    /// 単独の式を評価し、その値を返します。
+    pub fn eval_expression(&mut self, expr: &ast::Expression) -> Result<Value> {
+        return self.exec(|translator| translator.translate_expression(expr));
+    }
+
+    fn exec<'ast>(&mut self, translate: impl FnOnce(&mut Translator<'ast>)) -> Result<Value> {
+        if self.globals.is_none() {
+            self.link_library()?;
+        }
+        let mut translator = self.translator();
+        if let Some(globals) = &self.globals {
+            translator.link_globals(globals);
+        }
+        // セッションでなければ、ライブラリのグローバル変数だけを次の実行に引き継ぐ
+        if self.session {
+            translator.persist_globals();
+        }
+        translate(&mut translator);
+        if self.session {
+            self.globals = Some(translator.globals());
+        }
+        let mut ir = translator.build();
+        let len = ir.user_functions.len();
+        let start = self.vm.free_user_functions(len);
+        ir.relocate_user_functions(self.vm.user_function_count(), start);
+        if len > 0 {
+            self.runs.push(start..start + len);
+        }
+        let result = exec_ir(&mut self.vm, start, ir);
+        self.release_functions();
+        return result;
+    }
+
+    /// 値が残っていない関数を仮想マシンから破棄します。
+    /// ユーザー関数は、同じ実行で定義された関数の値がすべてなくなった時点で破棄します。
+    fn release_functions(&mut self) {
+        // 到達できない関数の値を回収し、参照数に反映させる
+        gc::force_collect();
+        let vm = &mut self.vm;
+        vm.release_generated_natives();
+        self.runs.retain(|run| {
+            if vm.user_functions_in_use(run.clone()) {
+                return true;
+            }
+            vm.release_user_functions(run.clone());
+            return false;
+        });
+    }
+
+    /// 標準ライブラリとホストの値を仮想マシンに登録し、次の実行から参照できるようにします。
+    /// 実行のたびに登録し直さないよう、最初の実行の前に1回だけ呼び出します。
+    fn link_library(&mut self) -> Result<()> {
         let opts = Rc::clone(&self.opts);
         let lib = HashMap::from([(
             &utf16!("print") as &[u16],
@@ -35,16 +160,207 @@ impl Interpreter {
                 Ok(Value::Null)
             }))),
         )]);
-        let mut translator = Translator::new();
+        let mut translator = self.translator();
         translator.link_library(std_library());
         translator.link_library(lib);
-        translator.translate(&program);
-        let ir = translator.build();
-        let mut vm = Vm::new();
-        for native_fn in ir.native_functions {
-            vm.register_native_fn(native_fn);
+        translator.link_library(self.library.iter().cloned());
+        self.globals = Some(translator.globals());
+        let start = self.vm.user_function_count();
+        exec_ir(&mut self.vm, start, translator.build())?;
+        return Ok(());
+    }
+
+    /// 仮想マシンに登録済みの関数やグローバル変数に続けて翻訳する[`Translator`]を作成します。
+    fn translator<'ast>(&self) -> Translator<'ast> {
+        return Translator::with_offsets(
+            self.vm.native_function_count(),
+            self.vm.user_function_count(),
+            self.vm.global_count(),
+        );
+    }
+
+    /// スクリプトの関数を呼び出します。
+    /// 以前の実行で得られた関数の値を、実行の終了後に呼び出すことができます。
+    pub fn exec_fn(&mut self, f: &Value, args: Vec<Value>) -> Result<Value> {
+        self.vm.reset_step_count();
+        return self.vm.exec_fn(f, args);
+    }
+
+    /// 時刻`now`(UNIXエポックからのミリ秒)までに実行すべきタイマーのコールバックを呼び出します。
+    /// ホストのイベントループから定期的に呼び出してください。
+    pub fn tick(&mut self, now: f64) -> Result<()> {
+        return self.vm.tick(now);
+    }
+
+    /// 次にタイマーを実行すべき時刻を返します。
+    /// 実行待ちのタイマーがなければ`None`を返します。
+    pub fn next_timer(&self) -> Option<f64> {
+        return self.vm.next_timer();
+    }
+}
+
+/// 翻訳結果を仮想マシンに登録し、エントリーポイントを実行します。
+/// ユーザー関数は`start`番目から登録します。
+fn exec_ir(vm: &mut Vm, start: usize, ir: Ir) -> Result<Value> {
+    vm.reserve_globals(ir.global_length);
+    for native_fn in ir.native_functions {
+        vm.register_native_fn(native_fn);
+    }
+    vm.register_user_functions(start, ir.user_functions);
+    vm.reset_step_count();
+    return vm.exec(&ir.entry_point, ir.result);
+}
+
+/// ホストの値や関数を登録した[`Interpreter`]を作成します。
+pub struct InterpreterBuilder {
+    interpreter: Interpreter,
+}
+
+impl InterpreterBuilder {
+    /// 値を登録します。
+    /// 名前は`Mk:name`のように名前空間を含めて指定します。
+    pub fn value(mut self, name: impl Into<Utf16String>, value: LibraryValue) -> Self {
+        self.interpreter.library.push((name.into(), value));
+        self
+    }
+
+    /// ネイティブ関数を登録します。
+    pub fn function(
+        self,
+        name: impl Into<Utf16String>,
+        function: impl Fn(Vec<Value>, &mut Vm) -> Result<Value> + 'static,
+    ) -> Self {
+        self.value(name, LibraryValue::Fn(NativeFn::Dynamic(Rc::new(function))))
+    }
+
+    /// REPLのように、実行をまたいでグローバル変数を保持します。
+    ///
+    /// セッションで定義したグローバル変数は、使われなくなっても破棄されません。
+    /// 関数はセッションかどうかによらず、同じ実行で定義した関数の値がすべてなくなった時点で破棄されます。
+    pub fn session(mut self) -> Self {
+        self.interpreter.session = true;
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        self.interpreter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aiscript_engine_ast as ast;
+    use aiscript_engine_common::Position;
+    use aiscript_engine_parser::Parser;
+
+    use super::*;
+
+    struct NoOut;
+
+    impl InterpreterOpts for NoOut {
+        fn out(&self, _value: Value) {}
+    }
+
+    fn num(value: f64) -> ast::Node {
+        let loc = ast::Loc {
+            start: Position::At { line: 1, column: 1 },
+            end: Position::At { line: 1, column: 1 },
+        };
+        return ast::Node::Expr(ast::Expression::Num(ast::Num { loc, value }));
+    }
+
+    fn table_sizes(interpreter: &Interpreter) -> (usize, usize, usize) {
+        let vm = &interpreter.vm;
+        return (
+            vm.native_function_count(),
+            vm.user_function_count(),
+            vm.global_count(),
+        );
+    }
+
+    fn parse(source: &str) -> Vec<ast::Node> {
+        return Parser::new().parse(&Utf16String::from(source)).unwrap();
+    }
+
+    #[test]
+    fn functions_are_released() {
+        let mut interpreter = Interpreter::new(Rc::new(NoOut));
+        interpreter.run(&[]).unwrap();
+        let sizes = table_sizes(&interpreter);
+        let program = parse("@f() { f }\nlet a = [@() { a }]\nf()\nnull");
+        for _ in 0..10 {
+            interpreter.run(&program).unwrap();
+            assert_eq!(table_sizes(&interpreter), sizes);
+        }
+
+        // 返された関数の値が残っている間は保持し、なくなったら位置を再利用する
+        let program = parse("@f() { f }\nlet a = [@() { a }]\nf()");
+        let first = interpreter.run(&program).unwrap();
+        for _ in 0..10 {
+            let f = interpreter.run(&program).unwrap();
+            assert_eq!(interpreter.exec_fn(&first, Vec::new()).unwrap(), first);
+            assert_eq!(interpreter.exec_fn(&f, Vec::new()).unwrap(), f);
+            assert!(table_sizes(&interpreter).1 <= sizes.1 + 6);
+        }
+        drop(first);
+        interpreter.run(&[]).unwrap();
+        assert_eq!(table_sizes(&interpreter), sizes);
+    }
+
+    #[test]
+    fn session_releases_temporary_functions() {
+        let mut interpreter = Interpreter::builder(Rc::new(NoOut)).session().build();
+        interpreter.run(&parse("@f() { 1 }")).unwrap();
+        let sizes = table_sizes(&interpreter);
+        let program = parse("[1, 2].map(@(x) { x + f() })");
+        for _ in 0..10 {
+            interpreter.run(&program).unwrap();
+            assert_eq!(table_sizes(&interpreter), sizes);
+        }
+    }
+
+    #[test]
+    fn generated_natives_are_released() {
+        let mut interpreter = Interpreter::new(Rc::new(NoOut));
+        interpreter.run(&[]).unwrap();
+        let sizes = table_sizes(&interpreter);
+
+        // 実行中も値が残っていない関数の位置を再利用する
+        let program = parse("for 1000 { Math:gen_rng(1) }\nlet rng = Math:gen_rng(1)\nrng");
+        let rng = interpreter.run(&program).unwrap();
+        assert!(table_sizes(&interpreter).0 < sizes.0 + 1000);
+        interpreter.exec_fn(&rng, Vec::new()).unwrap();
+
+        drop(rng);
+        interpreter.run(&[]).unwrap();
+        assert_eq!(table_sizes(&interpreter), sizes);
+    }
+
+    #[test]
+    fn unsupported_node() {
+        let loc = ast::Loc {
+            start: Position::At { line: 1, column: 1 },
+            end: Position::At { line: 1, column: 1 },
+        };
+        let attr = ast::Node::Attr(ast::Attribute {
+            loc: loc.clone(),
+            name: Utf16String::from("x"),
+            value: ast::Expression::Num(ast::Num { loc, value: 1.0 }),
+        });
+        let mut interpreter = Interpreter::new(Rc::new(NoOut));
+        let err = interpreter.run(&[attr]).unwrap_err();
+        assert_eq!(err.name(), "NotImplemented");
+    }
+
+    #[test]
+    fn library_is_linked_once() {
+        let mut interpreter = Interpreter::new(Rc::new(NoOut));
+        interpreter.run(&[num(1.0)]).unwrap();
+        let sizes = table_sizes(&interpreter);
+        for _ in 0..10 {
+            interpreter.run(&[num(1.0)]).unwrap();
+            interpreter.run(&[]).unwrap();
+            assert_eq!(table_sizes(&interpreter), sizes);
         }
-        vm.exec(&ir.entry_point)?;
-        return Ok(Value::Null);
     }
 }
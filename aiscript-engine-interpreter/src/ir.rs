@@ -1,7 +1,10 @@
+mod captured_names;
+#[allow(clippy::module_inception)]
 mod ir;
 mod reference;
 mod scopes;
 mod translate;
 
 pub(crate) use ir::*;
+pub(crate) use scopes::GlobalVariables;
 pub(crate) use translate::Translator;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use aiscript_engine_ast as ast;
+use aiscript_engine_common::NamePath;
+
+/// 関数内から参照される変数名を収集する。
+/// 変数名ごとに、参照している関数の最大の深さを記録する。
+#[derive(Default)]
+pub(crate) struct CapturedNames<'ast> {
+    names: HashMap<&'ast NamePath, usize>,
+    depth: usize,
+}
+
+impl<'ast> CapturedNames<'ast> {
+    /// 深さ`depth`で定義される変数が内側の関数から参照されうるかを返します。
+    pub(crate) fn is_captured(&self, name: &NamePath, depth: usize) -> bool {
+        self.names
+            .get(name)
+            .is_some_and(|max_depth| *max_depth > depth)
+    }
+
+    pub(crate) fn collect(&mut self, nodes: &'ast [ast::Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    pub(crate) fn collect_expr(&mut self, node: &'ast ast::Expression) {
+        self.expr(node);
+    }
+
+    fn node(&mut self, node: &'ast ast::Node) {
+        match node {
+            ast::Node::Ns(node) => self.namespace(node),
+            ast::Node::Statement(node) => self.statement(node),
+            ast::Node::Expr(node) => self.expr(node),
+            ast::Node::Meta(_) | ast::Node::TypeSource(_) | ast::Node::Attr(_) => {}
+        }
+    }
+
+    fn namespace(&mut self, node: &'ast ast::Namespace) {
+        for member in &node.members {
+            match member {
+                ast::NamespaceMember::Ns(node) => self.namespace(node),
+                ast::NamespaceMember::Def(node) => self.expr(&node.expr),
+            }
+        }
+    }
+
+    fn statement_or_expr(&mut self, node: &'ast ast::StatementOrExpression) {
+        match node {
+            ast::StatementOrExpression::Statement(node) => self.statement(node),
+            ast::StatementOrExpression::Expression(node) => self.expr(node),
+        }
+    }
+
+    fn statement(&mut self, node: &'ast ast::Statement) {
+        match node {
+            ast::Statement::Def(node) => self.expr(&node.expr),
+            ast::Statement::Return(node) => self.expr(&node.expr),
+            ast::Statement::Each(node) => {
+                self.expr(&node.items);
+                self.statement_or_expr(&node.for_statement);
+            }
+            ast::Statement::For(node) => {
+                match &node.iter {
+                    ast::ForIterator::Range { var: _, from, to } => {
+                        self.expr(from);
+                        self.expr(to);
+                    }
+                    ast::ForIterator::Times { times } => self.expr(times),
+                }
+                self.statement_or_expr(&node.for_statement);
+            }
+            ast::Statement::Loop(node) => {
+                for statement in &node.statements {
+                    self.statement_or_expr(statement);
+                }
+            }
+            ast::Statement::Break(_) | ast::Statement::Continue(_) => {}
+            ast::Statement::Assign(node) => {
+                self.expr(&node.dest);
+                self.expr(&node.expr);
+            }
+        }
+    }
+
+    fn expr(&mut self, node: &'ast ast::Expression) {
+        match node {
+            ast::Expression::If(node) => {
+                self.expr(&node.cond);
+                self.statement_or_expr(&node.then);
+                for elif in &node.elseif {
+                    self.expr(&elif.cond);
+                    self.statement_or_expr(&elif.then);
+                }
+                if let Some(else_statement) = &node.else_statement {
+                    self.statement_or_expr(else_statement);
+                }
+            }
+            ast::Expression::Fn(node) => {
+                // 引数の初期値は関数の外側で評価される
+                for arg in &node.args {
+                    if let ast::FnArgValue::Required {
+                        default: Some(default),
+                    } = &arg.value
+                    {
+                        self.expr(default);
+                    }
+                }
+                self.depth += 1;
+                for statement in &node.children {
+                    self.statement_or_expr(statement);
+                }
+                self.depth -= 1;
+            }
+            ast::Expression::Match(node) => {
+                self.expr(&node.about);
+                for q in &node.qs {
+                    self.expr(&q.q);
+                    self.statement_or_expr(&q.a);
+                }
+                if let Some(default) = &node.default {
+                    self.statement_or_expr(default);
+                }
+            }
+            ast::Expression::Block(node) => {
+                for statement in &node.statements {
+                    self.statement_or_expr(statement);
+                }
+            }
+            ast::Expression::Tmpl(node) => {
+                for expr in &node.tmpl {
+                    self.expr(expr);
+                }
+            }
+            ast::Expression::Obj(node) => {
+                for value in node.value.values() {
+                    self.expr(value);
+                }
+            }
+            ast::Expression::Arr(node) => {
+                for value in &node.value {
+                    self.expr(value);
+                }
+            }
+            ast::Expression::Not(node) => self.expr(&node.expr),
+            ast::Expression::Identifier(node) => {
+                if self.depth > 0 {
+                    let max_depth = self.names.entry(&node.name).or_insert(0);
+                    *max_depth = (*max_depth).max(self.depth);
+                }
+            }
+            ast::Expression::Call(node) => {
+                self.expr(&node.target);
+                for arg in &node.args {
+                    self.expr(arg);
+                }
+            }
+            ast::Expression::Index(node) => {
+                self.expr(&node.target);
+                self.expr(&node.index);
+            }
+            ast::Expression::Prop(node) => self.expr(&node.target),
+            ast::Expression::Binary(node) => {
+                self.expr(&node.left);
+                self.expr(&node.right);
+            }
+            ast::Expression::Exists(_)
+            | ast::Expression::Str(_)
+            | ast::Expression::Num(_)
+            | ast::Expression::Bool(_)
+            | ast::Expression::Null(_) => {}
+        }
+    }
+}
@@ -1,37 +1,116 @@
 use std::{fmt::Debug, rc::Rc};
 
 use crate::library::NativeFn;
-use aiscript_engine_common::AiScriptBasicError;
-use aiscript_engine_values::{VArr, VObj};
+use aiscript_engine_common::{AiScriptBasicError, Position, Utf16String};
+use aiscript_engine_values::{VArr, VObj, Value};
 use gc::{Gc, GcCell};
 
 /// 中間表現
 #[derive(Debug)]
 pub(crate) struct Ir {
     pub native_functions: Vec<NativeFn>,
+    pub user_functions: Vec<UserFn>,
     pub entry_point: UserFn,
+
+    /// エントリーポイントの実行結果を格納するレジスタ
+    pub result: Option<Register>,
+
+    /// 使用するグローバル変数の数
+    pub global_length: usize,
+}
+
+impl Ir {
+    /// `from`番目からのユーザー関数を`to`番目からに登録するよう、関数の番号を付け替えます。
+    pub(crate) fn relocate_user_functions(&mut self, from: UserFnIndex, to: UserFnIndex) {
+        if from == to {
+            return;
+        }
+        self.entry_point.body.relocate_user_functions(from, to);
+        for user_fn in &mut self.user_functions {
+            user_fn.body.relocate_user_functions(from, to);
+        }
+    }
 }
 
 impl Default for Ir {
     fn default() -> Self {
         Self {
             native_functions: Vec::new(),
+            user_functions: Vec::new(),
             entry_point: UserFn::new(),
+            result: None,
+            global_length: 0,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct UserFn {
+    /// 呼び出し履歴に表示する関数名
+    pub name: Option<Utf16String>,
+
     pub register_length: usize,
-    pub instructions: Vec<Instruction>,
+    pub cell_length: usize,
+
+    /// 引数名。
+    /// この関数の値はすべてこれを共有するため、参照数から関数の値が残っているかを判定できる
+    pub params: Rc<[Utf16String]>,
+
+    /// 捕捉した変数を格納するセル
+    pub captures: Vec<CellIndex>,
+
+    pub body: Block,
 }
 
 impl UserFn {
     pub(crate) fn new() -> Self {
         UserFn {
+            name: None,
             register_length: 0,
-            instructions: Vec::new(),
+            cell_length: 0,
+            params: Rc::from([]),
+            captures: Vec::new(),
+            body: Block::default(),
+        }
+    }
+}
+
+/// 命令列
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Block {
+    pub instructions: Vec<Instruction>,
+
+    /// 各命令に対応するソースコード上の位置。
+    /// 実行時エラーの発生位置を求めるために、命令と同じ添字で参照します。
+    pub positions: Vec<Option<Position>>,
+}
+
+impl Block {
+    pub(crate) fn push(&mut self, instruction: Instruction, pos: Option<Position>) {
+        self.instructions.push(instruction);
+        self.positions.push(pos);
+    }
+
+    /// 別の命令列を末尾に連結します。
+    pub(crate) fn append(&mut self, other: Block) {
+        self.instructions.extend(other.instructions);
+        self.positions.extend(other.positions);
+    }
+
+    fn relocate_user_functions(&mut self, from: UserFnIndex, to: UserFnIndex) {
+        for instruction in &mut self.instructions {
+            match instruction {
+                Instruction::Fn(_, index, _, _) => *index = *index - from + to,
+                Instruction::If(_, then_code, else_code) => {
+                    then_code.relocate_user_functions(from, to);
+                    else_code.relocate_user_functions(from, to);
+                }
+                Instruction::Loop(code)
+                | Instruction::Times(_, code)
+                | Instruction::Range(_, _, _, code)
+                | Instruction::Each(_, _, code) => code.relocate_user_functions(from, to),
+                _ => {}
+            }
         }
     }
 }
@@ -42,16 +121,42 @@ pub(crate) type UserFnIndex = usize;
 
 pub(crate) type Register = usize;
 
+pub(crate) type CellIndex = usize;
+
+pub(crate) type GlobalIndex = usize;
+
 #[derive(Clone, Debug)]
 pub(crate) enum Instruction {
     /// 何もしない
+    #[allow(dead_code)]
     Nop,
 
     /// エラーによる強制終了
     Panic(AiScriptBasicError),
 
     /// レジスタの値が真なら前のコード、偽なら後のコードを実行
-    If(Register, Vec<Instruction>, Vec<Instruction>),
+    If(Register, Block, Block),
+
+    /// レジスタの値を返して関数を終了
+    Return(Register),
+
+    /// breakされるまで命令列を繰り返す
+    Loop(Block),
+
+    /// レジスタの値の回数だけ命令列を繰り返す
+    Times(Register, Block),
+
+    /// レジスタ1の値からレジスタ2の値の回数だけ、カウンタをレジスタ0に格納して命令列を繰り返す
+    Range(Register, Register, Register, Block),
+
+    /// レジスタ1の配列の各要素をレジスタ0に格納して命令列を繰り返す
+    Each(Register, Register, Block),
+
+    /// 最も内側から数えて指定した数だけ外側のループを終了
+    Break(usize),
+
+    /// 最も内側から数えて指定した数だけ外側のループの次の繰り返しに移る
+    Continue(usize),
 
     /// nullを格納
     Null(Register),
@@ -65,24 +170,79 @@ pub(crate) enum Instruction {
     /// strを格納
     Str(Register, Rc<[u16]>),
 
-    /// arrを格納
+    /// arrの複製を格納
     Arr(Register, Gc<GcCell<VArr>>),
 
-    /// objを格納
+    /// objの複製を格納
     Obj(Register, Gc<GcCell<VObj>>),
 
+    /// 値を複製せずに格納
+    Value(Register, Value),
+
     /// ネイティブ関数のクロージャを格納
     NativeFn(Register, NativeFnIndex),
 
+    /// ユーザー関数のクロージャを格納
+    /// 即値2のレジスタの値を引数の初期値とし、即値3のセルを捕捉する
+    Fn(Register, UserFnIndex, Vec<Option<Register>>, Vec<CellIndex>),
+
     /// レジスタ0にレジスタ1の値をコピー
     Move(Register, Register),
 
+    /// セル即値0を新しく作成
+    Cell(CellIndex),
+
+    /// セル即値1からレジスタ0にコピー
+    LoadCell(Register, CellIndex),
+
+    /// レジスタ0からセル即値1にコピー
+    StoreCell(Register, CellIndex),
+
+    /// グローバル変数即値1からレジスタ0にコピー
+    LoadGlobal(Register, GlobalIndex),
+
+    /// レジスタ0からグローバル変数即値1にコピー
+    StoreGlobal(Register, GlobalIndex),
+
+    /// 即値1のレジスタの値を文字列に変換して連結し、レジスタ0に格納
+    Tmpl(Register, Vec<Register>),
+
     /// レジスタ0にレジスタ1+レジスタ2を代入
     Add(Register, Register, Register),
 
     /// レジスタ0にレジスタ1-レジスタ2を代入
     Sub(Register, Register, Register),
 
+    /// レジスタ0にレジスタ1*レジスタ2を代入
+    Mul(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1^レジスタ2を代入
+    Pow(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1/レジスタ2を代入
+    Div(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1%レジスタ2を代入
+    Rem(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1<レジスタ2を代入
+    Lt(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1<=レジスタ2を代入
+    Lteq(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1>レジスタ2を代入
+    Gt(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1>=レジスタ2を代入
+    Gteq(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1とレジスタ2が等しいかを代入
+    Eq(Register, Register, Register),
+
+    /// レジスタ0にレジスタ1とレジスタ2が等しくないかを代入
+    Neq(Register, Register, Register),
+
     /// レジスタ0にレジスタ1の論理否定を代入
     Not(Register, Register),
 
@@ -2,11 +2,11 @@ use std::rc::Rc;
 
 use indexmap::IndexMap;
 
-use super::Register;
+use super::{scopes::Location, Register};
 
 pub(super) enum Reference {
     Variable {
-        register: Register,
+        location: Location,
     },
     Index {
         target: Register,
@@ -3,21 +3,35 @@ use std::{borrow::Cow, collections::HashMap};
 use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, NamePath, Utf16Str};
 use utf16_literal::utf16;
 
-pub(crate) use variable::Variable;
+pub(crate) use variable::{Location, Variable};
 
-use super::Register;
+use super::{CellIndex, GlobalIndex, Register};
 
 mod variable;
 
 /// グローバル変数が登録されたスコープ。
 pub(crate) struct RootScope<'ast> {
     variables: HashMap<Cow<'ast, NamePath>, Variable>,
+
+    /// 定義に先立って関数から参照できる変数
+    declared: HashMap<Cow<'ast, NamePath>, Variable>,
 }
 
+/// 翻訳をまたいで保持するグローバル変数。
+pub(crate) type GlobalVariables = HashMap<NamePath, Variable>;
+
 impl<'ast> RootScope<'ast> {
     pub(crate) fn add(&mut self, name: impl Into<Cow<'ast, NamePath>>, variable: Variable) {
         self.variables.insert(name.into(), variable);
     }
+
+    /// 定義されたグローバル変数を複製して返します。
+    pub(crate) fn to_global_variables(&self) -> GlobalVariables {
+        self.variables
+            .iter()
+            .map(|(name, variable)| (name.clone().into_owned(), *variable))
+            .collect()
+    }
 }
 
 /// 名前空間内のスコープ。
@@ -28,7 +42,13 @@ struct NamespaceScope<'ast> {
 
 /// 関数や制御構文内のスコープ。
 struct BlockScope<'ast> {
-    variables: HashMap<&'ast NamePath, Variable>,
+    variables: HashMap<Cow<'ast, NamePath>, Variable>,
+
+    /// 定義に先立って内側の関数から参照できる変数
+    declared: HashMap<&'ast NamePath, Variable>,
+
+    /// このスコープが属する関数の深さ
+    depth: usize,
 }
 
 pub(crate) struct Scopes<'ast> {
@@ -42,6 +62,7 @@ impl<'ast> Scopes<'ast> {
         Scopes {
             root: RootScope {
                 variables: HashMap::new(),
+                declared: HashMap::new(),
             },
             namespaces: Vec::new(),
             blocks: Vec::new(),
@@ -56,6 +77,17 @@ impl<'ast> Scopes<'ast> {
         !self.namespaces.is_empty()
     }
 
+    /// 変数がグローバル変数として定義されるスコープかどうかを返します。
+    pub(crate) fn is_global(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// 現在のスコープが属する関数の深さを返します。
+    /// ルートは0です。
+    pub(crate) fn depth(&self) -> usize {
+        self.blocks.last().map_or(0, |block| block.depth)
+    }
+
     pub(crate) fn current_scope_name(&self) -> &Utf16Str {
         if self.is_root() {
             Utf16Str::new(&utf16!("<root>"))
@@ -71,16 +103,25 @@ impl<'ast> Scopes<'ast> {
     pub(crate) fn push_block_scope(&mut self) {
         self.blocks.push(BlockScope {
             variables: HashMap::new(),
+            declared: HashMap::new(),
+            depth: self.depth(),
+        });
+    }
+
+    /// 関数本体のスコープを追加します。
+    pub(crate) fn push_fn_scope(&mut self) {
+        self.blocks.push(BlockScope {
+            variables: HashMap::new(),
+            declared: HashMap::new(),
+            depth: self.depth() + 1,
         });
     }
 
     /// スタックのトップからローカルスコープを1つ破棄します。
     /// ローカルスコープがない場合はパニックします。
     pub(crate) fn drop_local_scope(&mut self) {
-        if let None = self.blocks.pop() {
-            if let None = self.namespaces.pop() {
-                panic!("No local scopes");
-            }
+        if self.blocks.pop().is_none() && self.namespaces.pop().is_none() {
+            panic!("No local scopes");
         }
     }
 
@@ -96,44 +137,97 @@ impl<'ast> Scopes<'ast> {
     }
 
     pub(crate) fn get(&self, name: &NamePath) -> Option<&Variable> {
-        for block in &self.blocks {
+        self.lookup(name).map(|(variable, _)| variable)
+    }
+
+    /// 変数を検索し、その変数が属する関数の深さとともに返します。
+    /// 内側の関数からは定義に先立って宣言された変数も参照できます。
+    pub(crate) fn lookup(&self, name: &NamePath) -> Option<(&Variable, usize)> {
+        let depth = self.depth();
+
+        for block in self.blocks.iter().rev() {
             if let Some(variable) = block.variables.get(name) {
-                return Some(variable);
+                return Some((variable, block.depth));
+            }
+            if block.depth < depth {
+                if let Some(variable) = block.declared.get(name) {
+                    return Some((variable, block.depth));
+                }
             }
         }
 
+        let get_root = |name: &NamePath| {
+            self.root.variables.get(name).or_else(|| {
+                if depth > 0 {
+                    self.root.declared.get(name)
+                } else {
+                    None
+                }
+            })
+        };
+
         if self.is_namespace() {
-            if let Some(variable) = self.root.variables.get(&self.resolve(name)) {
-                return Some(variable);
+            if let Some(variable) = get_root(&self.resolve(name)) {
+                return Some((variable, 0));
             }
         }
 
-        return self.root.variables.get(name);
+        return get_root(name).map(|variable| (variable, 0));
     }
 
     pub(crate) fn exists(&self, name: &NamePath) -> bool {
         self.get(name).is_some()
     }
 
-    pub(crate) fn add(&mut self, name: &'ast NamePath, variable: Variable) {
+    pub(crate) fn add(&mut self, name: impl Into<Cow<'ast, NamePath>>, variable: Variable) {
+        let name = name.into();
         if let Some(block) = self.blocks.last_mut() {
             // ブロック
             block.variables.insert(name, variable);
         } else {
             if self.namespaces.is_empty() {
                 // ルート
-                self.root.variables.insert(Cow::Borrowed(name), variable);
+                self.root.variables.insert(name, variable);
             } else {
                 // 名前空間
                 self.root
                     .variables
-                    .insert(Cow::Owned(self.resolve(name)), variable);
+                    .insert(Cow::Owned(self.resolve(&name)), variable);
             }
         }
     }
 
-    pub(crate) fn assign(&mut self, name: &'ast NamePath) -> Result<Register, AiScriptBasicError> {
-        if let Some(variable) = self.get(name) {
+    /// 定義に先立って変数を宣言します。
+    pub(crate) fn declare(&mut self, name: &'ast NamePath, variable: Variable) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.declared.insert(name, variable);
+        } else if self.namespaces.is_empty() {
+            self.root.declared.insert(Cow::Borrowed(name), variable);
+        } else {
+            self.root
+                .declared
+                .insert(Cow::Owned(self.resolve(name)), variable);
+        }
+    }
+
+    /// 現在のスコープで宣言された変数を取り出します。
+    pub(crate) fn take_declared(&mut self, name: &NamePath) -> Option<Variable> {
+        if let Some(block) = self.blocks.last_mut() {
+            block.declared.remove(name)
+        } else if self.namespaces.is_empty() {
+            self.root.declared.remove(name)
+        } else {
+            let name = self.resolve(name);
+            self.root.declared.remove(&name)
+        }
+    }
+
+    /// 代入先の変数の格納場所と、その変数が属する関数の深さを返します。
+    pub(crate) fn assign(
+        &mut self,
+        name: &'ast NamePath,
+    ) -> Result<(Location, usize), AiScriptBasicError> {
+        if let Some((variable, depth)) = self.lookup(name) {
             if !variable.is_mutable {
                 return Err(AiScriptBasicError::new(
                     AiScriptBasicErrorKind::Runtime,
@@ -141,7 +235,7 @@ impl<'ast> Scopes<'ast> {
                     None,
                 ));
             }
-            return Ok(variable.register);
+            return Ok((variable.location, depth));
         }
 
         return Err(AiScriptBasicError::new(
@@ -246,4 +340,60 @@ mod tests {
         scopes.drop_local_scope();
         assert!(scopes.is_root());
     }
+
+    #[test]
+    fn shadowing() {
+        let mut scopes = Scopes::new();
+        let variable_name = name_path("a");
+        scopes.push_block_scope();
+        scopes.add(
+            &variable_name,
+            Variable {
+                is_mutable: false,
+                location: Location::Register(0),
+            },
+        );
+        scopes.push_block_scope();
+        scopes.add(
+            &variable_name,
+            Variable {
+                is_mutable: false,
+                location: Location::Register(1),
+            },
+        );
+        assert_eq!(
+            scopes.get(&variable_name).unwrap().location,
+            Location::Register(1)
+        );
+        scopes.drop_local_scope();
+        assert_eq!(
+            scopes.get(&variable_name).unwrap().location,
+            Location::Register(0)
+        );
+    }
+
+    #[test]
+    fn declared() {
+        let mut scopes = Scopes::new();
+        let variable_name = name_path("a");
+        scopes.push_block_scope();
+        scopes.declare(
+            &variable_name,
+            Variable {
+                is_mutable: false,
+                location: Location::Cell(0),
+            },
+        );
+        assert!(!scopes.exists(&variable_name));
+
+        scopes.push_fn_scope();
+        assert_eq!(scopes.depth(), 1);
+        let (variable, depth) = scopes.lookup(&variable_name).unwrap();
+        assert_eq!(variable.location, Location::Cell(0));
+        assert_eq!(depth, 0);
+        scopes.drop_local_scope();
+
+        assert!(scopes.take_declared(&variable_name).is_some());
+        assert!(scopes.take_declared(&variable_name).is_none());
+    }
 }
@@ -1,16 +1,30 @@
-use super::Register;
+use super::{CellIndex, GlobalIndex, Register};
+
+/// 変数の格納場所。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Location {
+    /// 関数内のレジスタ
+    Register(Register),
+
+    /// クロージャに捕捉されうるセル
+    Cell(CellIndex),
+
+    /// ルートスコープのグローバル変数
+    Global(GlobalIndex),
+}
 
 /// 変数の型などを格納する。
+#[derive(Clone, Copy, Debug)]
 pub(crate) struct Variable {
     pub is_mutable: bool,
-    pub register: Register,
+    pub location: Location,
 }
 
 impl Default for Variable {
     fn default() -> Self {
         Self {
             is_mutable: false,
-            register: 0,
+            location: Location::Register(0),
         }
     }
 }
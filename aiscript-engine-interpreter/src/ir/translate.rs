@@ -1,42 +1,107 @@
 use std::{borrow::Cow, collections::HashSet, rc::Rc};
 
-use crate::library::{Library, LibraryValue, NativeFn};
-use aiscript_engine_ast::{self as ast, NamespaceMember};
+use crate::library::{LibraryValue, NativeFn};
+use aiscript_engine_ast::{self as ast, NamespaceMember, NodeBase};
 use aiscript_engine_common::{
-    AiScriptBasicError, AiScriptBasicErrorKind, NamePath, Utf16Str, Utf16String,
+    AiScriptBasicError, AiScriptBasicErrorKind, NamePath, Position, StackGuard, Utf16Str,
+    Utf16String,
 };
 use aiscript_engine_values::{VObj, Value};
 use gc::{Gc, GcCell};
 use indexmap::IndexMap;
 
 use super::{
+    captured_names::CapturedNames,
     reference::Reference,
-    scopes::{Scopes, Variable},
-    Instruction, Ir, Register, UserFn, UserFnIndex,
+    scopes::{GlobalVariables, Location, Scopes, Variable},
+    Block, CellIndex, GlobalIndex, Instruction, Ir, NativeFnIndex, Register, UserFn, UserFnIndex,
 };
 
+/// 翻訳中の関数の状態。
+#[derive(Default)]
+struct FnContext {
+    register_length: usize,
+    cell_length: usize,
+
+    /// 外側の関数のセルと、それを捕捉するこの関数のセルの組
+    captures: Vec<(CellIndex, CellIndex)>,
+
+    block: Block,
+    procedures: Vec<Block>,
+
+    /// 翻訳中のループのラベル。末尾が最も内側のループ
+    loops: Vec<Option<Utf16String>>,
+}
+
 pub(crate) struct Translator<'ast> {
     scopes: Scopes<'ast>,
     native_functions: Vec<NativeFn>,
+    user_functions: Vec<UserFn>,
+
+    /// VMに登録済みのネイティブ関数の数
+    native_function_offset: usize,
+
+    /// VMに登録済みのユーザー関数の数
+    user_function_offset: usize,
+
     strings: HashSet<Rc<[u16]>>,
-    register_length: usize,
-    block: Vec<Instruction>,
-    procedures: Vec<Vec<Instruction>>,
+    captured_names: CapturedNames<'ast>,
+    global_length: usize,
+
+    /// プログラムの実行結果を格納するレジスタ
+    result: Option<Register>,
+
+    /// 翻訳中の関数
+    function: FnContext,
+
+    /// 翻訳中の関数を囲む関数
+    outer_functions: Vec<FnContext>,
+
+    /// 翻訳中のノードの位置。追加する命令に対応付けられる
+    pos: Option<Position>,
+
+    /// 次に翻訳する関数の名前
+    fn_name: Option<Utf16String>,
+
+    /// トップレベルの変数を、以降の翻訳から参照できるグローバル変数として定義するか
+    persistent_globals: bool,
 }
 
 impl<'ast> Translator<'ast> {
-    pub(crate) fn new() -> Self {
+    /// 既に関数やグローバル変数が登録されたVMで実行するための翻訳を開始します。
+    pub(crate) fn with_offsets(
+        native_functions: usize,
+        user_functions: usize,
+        globals: usize,
+    ) -> Self {
         Translator {
             scopes: Scopes::new(),
             native_functions: Vec::new(),
+            user_functions: Vec::new(),
+            native_function_offset: native_functions,
+            user_function_offset: user_functions,
             strings: HashSet::new(),
-            register_length: 0,
-            block: Vec::new(),
-            procedures: Vec::new(),
+            captured_names: CapturedNames::default(),
+            global_length: globals,
+            result: None,
+            function: FnContext::default(),
+            outer_functions: Vec::new(),
+            pos: None,
+            fn_name: None,
+            persistent_globals: false,
         }
     }
 
-    pub(crate) fn link_library(&mut self, library: Library) {
+    /// トップレベルの変数をグローバル変数として定義し、以降の翻訳から参照できるようにします。
+    /// そうでなければ、トップレベルの変数はエントリーポイントのセルに格納されます。
+    pub(crate) fn persist_globals(&mut self) {
+        self.persistent_globals = true;
+    }
+
+    pub(crate) fn link_library<N: Into<Utf16String>>(
+        &mut self,
+        library: impl IntoIterator<Item = (N, LibraryValue)>,
+    ) {
         for (name, value) in library {
             let register = self.use_register();
             match value {
@@ -51,43 +116,86 @@ impl<'ast> Translator<'ast> {
                     let value = self.str_literal(&value);
                     self.append_instruction(Instruction::Str(register, value));
                 }
-                LibraryValue::Obj(_value) => todo!(),
-                LibraryValue::Arr(_value) => todo!(),
+                LibraryValue::Obj(value) => {
+                    self.append_instruction(Instruction::Value(register, Value::Obj(value)))
+                }
+                LibraryValue::Arr(value) => {
+                    self.append_instruction(Instruction::Value(register, Value::Arr(value)))
+                }
+                LibraryValue::Error(value) => {
+                    self.append_instruction(Instruction::Value(register, Value::Error(value)))
+                }
                 LibraryValue::Fn(value) => {
                     let index = self.add_native_function(value);
                     self.append_instruction(Instruction::NativeFn(register, index));
                 }
             }
+            let global = self.use_global();
+            self.append_instruction(Instruction::StoreGlobal(register, global));
             self.scopes.root.add(
-                Cow::Owned(NamePath::from(Utf16String::from(name))),
+                Cow::Owned(NamePath::from(name.into())),
                 Variable {
-                    register,
+                    location: Location::Global(global),
                     is_mutable: false,
                 },
             );
         }
     }
 
+    /// 以前の翻訳で定義されたグローバル変数を参照できるようにします。
+    pub(crate) fn link_globals(&mut self, globals: &GlobalVariables) {
+        for (name, variable) in globals {
+            self.scopes.root.add(Cow::Owned(name.clone()), *variable);
+        }
+    }
+
+    /// 定義されたグローバル変数を返します。
+    pub(crate) fn globals(&self) -> GlobalVariables {
+        self.scopes.root.to_global_variables()
+    }
+
     pub(crate) fn translate(&mut self, ast: &'ast [ast::Node]) {
         if ast.is_empty() {
             return;
         }
+        self.captured_names.collect(ast);
+        for node in ast {
+            if let ast::Node::Statement(ast::Statement::Def(node)) = node {
+                self.hoist(node);
+            }
+        }
         self.collect_ns(ast.iter().filter_map(|ns| match ns {
             aiscript_engine_ast::Node::Ns(node) => Some(node),
             _ => None,
         }));
         let register = self.use_register();
         self.run(register, ast);
+        self.result = Some(register);
+    }
+
+    /// 単独の式を翻訳します。
+    pub(crate) fn translate_expression(&mut self, node: &'ast ast::Expression) {
+        self.captured_names.collect_expr(node);
+        let register = self.use_register();
+        self.eval_expr(register, node);
+        self.result = Some(register);
     }
 
     pub(crate) fn build(self) -> Ir {
         let entry_point = UserFn {
-            register_length: self.register_length,
-            instructions: self.block,
+            name: None,
+            register_length: self.function.register_length,
+            cell_length: self.function.cell_length,
+            params: Rc::from([]),
+            captures: Vec::new(),
+            body: self.function.block,
         };
         Ir {
             native_functions: self.native_functions,
+            user_functions: self.user_functions,
             entry_point,
+            result: self.result,
+            global_length: self.global_length,
         }
     }
 
@@ -105,6 +213,12 @@ impl<'ast> Translator<'ast> {
             NamespaceMember::Def(_) => None,
         }));
 
+        for node in &ns.members {
+            if let NamespaceMember::Def(node) = node {
+                self.hoist(node);
+            }
+        }
+
         for node in &ns.members {
             if let NamespaceMember::Def(node) = node {
                 let ast::Expression::Identifier(dest) = &node.dest else {
@@ -145,7 +259,13 @@ impl<'ast> Translator<'ast> {
             }
             ast::Node::Statement(node) => self.eval_statement(register, node),
             ast::Node::Expr(node) => self.eval_expr(register, node),
-            ast::Node::Attr(_) | ast::Node::TypeSource(_) => panic!("invalid node type"),
+            ast::Node::Attr(_) | ast::Node::TypeSource(_) => {
+                self.append_instruction(Instruction::Panic(AiScriptBasicError::new(
+                    AiScriptBasicErrorKind::NotImplemented,
+                    "invalid node type",
+                    Some(node.loc().start.clone()),
+                )));
+            }
         }
     }
 
@@ -161,18 +281,91 @@ impl<'ast> Translator<'ast> {
     }
 
     fn eval_statement(&mut self, register: Register, node: &'ast ast::Statement) {
+        let pos = self.pos.replace(node.loc().start.clone());
+        let stack_guard = StackGuard::new();
+        if stack_guard.exceeded() {
+            self.append_instruction(Instruction::Panic(nesting_error(node.loc())));
+        } else {
+            self.eval_statement_node(register, node);
+        }
+        self.pos = pos;
+    }
+
+    fn eval_statement_node(&mut self, register: Register, node: &'ast ast::Statement) {
         match node {
             ast::Statement::Def(node) => {
                 let register = self.use_register();
+                if let (ast::Expression::Identifier(dest), ast::Expression::Fn(_)) =
+                    (&node.dest, &node.expr)
+                {
+                    self.fn_name = Some(dest.name.to_string().as_str().into());
+                }
                 self.eval_expr(register, &node.expr);
                 self.define(&node.dest, register, node.is_mut);
             }
-            ast::Statement::Return(_node) => todo!(),
-            ast::Statement::Each(_node) => todo!(),
-            ast::Statement::For(_node) => todo!(),
-            ast::Statement::Loop(_node) => todo!(),
-            ast::Statement::Break(_node) => todo!(),
-            ast::Statement::Continue(_node) => todo!(),
+            ast::Statement::Return(node) => {
+                let value = self.use_register();
+                self.eval_expr(value, &node.expr);
+                self.append_instruction(Instruction::Return(value));
+            }
+            ast::Statement::Each(node) => {
+                let items = self.use_register();
+                self.eval_expr(items, &node.items);
+                let item = self.use_register();
+                let body = self.use_register();
+                self.begin_loop(&node.label);
+                self.define(&node.var, item, false);
+                self.eval_statement_or_expr(body, &node.for_statement);
+                let code = self.end_loop();
+                self.append_instruction(Instruction::Each(item, items, code));
+            }
+            ast::Statement::For(node) => match &node.iter {
+                ast::ForIterator::Range { var, from, to } => {
+                    let from_register = self.use_register();
+                    self.eval_expr(from_register, from);
+                    let to_register = self.use_register();
+                    self.eval_expr(to_register, to);
+                    let counter = self.use_register();
+                    let body = self.use_register();
+                    self.begin_loop(&node.label);
+                    self.define_variable(NamePath::from(var), counter, false);
+                    self.eval_statement_or_expr(body, &node.for_statement);
+                    let code = self.end_loop();
+                    self.append_instruction(Instruction::Range(
+                        counter,
+                        from_register,
+                        to_register,
+                        code,
+                    ));
+                }
+                ast::ForIterator::Times { times } => {
+                    let times_register = self.use_register();
+                    self.eval_expr(times_register, times);
+                    let body = self.use_register();
+                    self.begin_loop(&node.label);
+                    self.eval_statement_or_expr(body, &node.for_statement);
+                    let code = self.end_loop();
+                    self.append_instruction(Instruction::Times(times_register, code));
+                }
+            },
+            ast::Statement::Loop(node) => {
+                let body = self.use_register();
+                self.begin_loop(&node.label);
+                self.hoist_statements(&node.statements);
+                for statement in &node.statements {
+                    self.eval_statement_or_expr(body, statement);
+                }
+                let code = self.end_loop();
+                self.append_instruction(Instruction::Loop(code));
+            }
+            ast::Statement::Break(node) => match self.loop_depth(&node.label, &node.loc) {
+                Ok(depth) => self.append_instruction(Instruction::Break(depth)),
+                Err(error) => self.append_instruction(Instruction::Panic(error)),
+            },
+            ast::Statement::Continue(node) => match self.loop_depth(&node.label, &node.loc) {
+                Ok(depth) => self.append_instruction(Instruction::Continue(depth)),
+                Err(error) => self.append_instruction(Instruction::Panic(error)),
+            },
             ast::Statement::Assign(node) => {
                 let right = self.use_register();
                 self.eval_expr(right, &node.expr);
@@ -207,6 +400,17 @@ impl<'ast> Translator<'ast> {
     }
 
     fn eval_expr(&mut self, register: Register, node: &'ast ast::Expression) {
+        let pos = self.pos.replace(node.loc().start.clone());
+        let stack_guard = StackGuard::new();
+        if stack_guard.exceeded() {
+            self.append_instruction(Instruction::Panic(nesting_error(node.loc())));
+        } else {
+            self.eval_expr_node(register, node);
+        }
+        self.pos = pos;
+    }
+
+    fn eval_expr_node(&mut self, register: Register, node: &'ast ast::Expression) {
         match node {
             ast::Expression::If(node) => {
                 self.eval_expr(register, &node.cond);
@@ -227,7 +431,7 @@ impl<'ast> Translator<'ast> {
                         self.eval_statement_or_expr(register, else_statement);
                         self.end_block()
                     }
-                    None => vec![Instruction::Null(register)],
+                    None => self.single_block(Instruction::Null(register)),
                 };
 
                 // elif節
@@ -244,12 +448,81 @@ impl<'ast> Translator<'ast> {
 
                 self.append_instruction(Instruction::If(register, then_code, else_code));
             }
-            ast::Expression::Fn(_node) => todo!(),
-            ast::Expression::Match(_node) => todo!(),
+            ast::Expression::Fn(node) => {
+                let name = self.fn_name.take();
+                // 引数の初期値は関数の定義時に評価する
+                let mut defaults = Vec::new();
+                for arg in &node.args {
+                    let default = match &arg.value {
+                        ast::FnArgValue::Optional => {
+                            let default = self.use_register();
+                            self.append_instruction(Instruction::Null(default));
+                            Some(default)
+                        }
+                        ast::FnArgValue::Required {
+                            default: Some(expr),
+                        } => {
+                            let default = self.use_register();
+                            self.eval_expr(default, expr);
+                            Some(default)
+                        }
+                        ast::FnArgValue::Required { default: None } => None,
+                    };
+                    defaults.push(default);
+                }
+                let (index, captures) = self.translate_fn(node, name);
+                self.append_instruction(Instruction::Fn(register, index, defaults, captures));
+            }
+            ast::Expression::Match(node) => {
+                let about = self.use_register();
+                self.eval_expr(about, &node.about);
+
+                // 各節の条件と処理
+                let q = self.use_register();
+                let arms: Vec<_> = node
+                    .qs
+                    .iter()
+                    .map(|qa| {
+                        self.begin_procedure();
+                        self.eval_expr(q, &qa.q);
+                        self.append_instruction(Instruction::Eq(q, about, q));
+                        let q_code = self.end_procedure();
+
+                        self.begin_block();
+                        self.eval_statement_or_expr(register, &qa.a);
+                        let a_code = self.end_block();
+
+                        (q_code, a_code)
+                    })
+                    .collect();
+
+                // デフォルト節
+                let mut code = match &node.default {
+                    Some(default) => {
+                        self.begin_block();
+                        self.eval_statement_or_expr(register, default);
+                        self.end_block()
+                    }
+                    None => self.single_block(Instruction::Null(register)),
+                };
+
+                // 後ろの節から順に、一致しなかった場合の処理として組み立てる
+                for (mut q_code, a_code) in arms.into_iter().rev() {
+                    q_code.push(Instruction::If(q, a_code, code), self.pos.clone());
+                    code = q_code;
+                }
+                self.function.block.append(code);
+            }
             ast::Expression::Block(node) => {
+                if node.statements.is_empty() {
+                    self.append_instruction(Instruction::Null(register));
+                }
+                self.scopes.push_block_scope();
+                self.hoist_statements(&node.statements);
                 for statement in &node.statements {
                     self.eval_statement_or_expr(register, statement);
                 }
+                self.scopes.drop_local_scope();
             }
             ast::Expression::Exists(node) => {
                 self.append_instruction(Instruction::Bool(
@@ -257,7 +530,18 @@ impl<'ast> Translator<'ast> {
                     self.scopes.exists(&node.identifier.name),
                 ));
             }
-            ast::Expression::Tmpl(_node) => todo!(),
+            ast::Expression::Tmpl(node) => {
+                let parts = node
+                    .tmpl
+                    .iter()
+                    .map(|part| {
+                        let part_register = self.use_register();
+                        self.eval_expr(part_register, part);
+                        part_register
+                    })
+                    .collect();
+                self.append_instruction(Instruction::Tmpl(register, parts));
+            }
             ast::Expression::Str(node) => {
                 let value = self.str_literal(&node.value);
                 self.append_instruction(Instruction::Str(register, value));
@@ -300,9 +584,11 @@ impl<'ast> Translator<'ast> {
             }
             ast::Expression::Identifier(node) => {
                 let name = &node.name;
-                if let Some(variable) = self.scopes.get(name) {
-                    let src = variable.register;
-                    self.append_instruction(Instruction::Move(register, src));
+                if let Some((variable, depth)) = self.scopes.lookup(name) {
+                    let location = variable.location;
+                    if let Some(location) = self.resolve_location(location, depth) {
+                        self.load(register, location);
+                    }
                 } else {
                     self.append_instruction(Instruction::Panic(AiScriptBasicError::new(
                         AiScriptBasicErrorKind::Runtime,
@@ -311,7 +597,7 @@ impl<'ast> Translator<'ast> {
                             name,
                             self.scopes.current_scope_name()
                         ),
-                        None,
+                        Some(node.loc.start.clone()),
                     )));
                 }
             }
@@ -343,22 +629,42 @@ impl<'ast> Translator<'ast> {
                         self.eval_expr(left, &node.left);
                         self.eval_expr(right, &node.right);
                         let instruction = match op {
-                            ast::BinaryArithmeticOperator::Pow => todo!(),
-                            ast::BinaryArithmeticOperator::Mul => todo!(),
-                            ast::BinaryArithmeticOperator::Div => todo!(),
-                            ast::BinaryArithmeticOperator::Rem => todo!(),
+                            ast::BinaryArithmeticOperator::Pow => {
+                                Instruction::Pow(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Mul => {
+                                Instruction::Mul(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Div => {
+                                Instruction::Div(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Rem => {
+                                Instruction::Rem(register, left, right)
+                            }
                             ast::BinaryArithmeticOperator::Add => {
                                 Instruction::Add(register, left, right)
                             }
                             ast::BinaryArithmeticOperator::Sub => {
                                 Instruction::Sub(register, left, right)
                             }
-                            ast::BinaryArithmeticOperator::Lt => todo!(),
-                            ast::BinaryArithmeticOperator::Lteq => todo!(),
-                            ast::BinaryArithmeticOperator::Gt => todo!(),
-                            ast::BinaryArithmeticOperator::Gteq => todo!(),
-                            ast::BinaryArithmeticOperator::Eq => todo!(),
-                            ast::BinaryArithmeticOperator::Neq => todo!(),
+                            ast::BinaryArithmeticOperator::Lt => {
+                                Instruction::Lt(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Lteq => {
+                                Instruction::Lteq(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Gt => {
+                                Instruction::Gt(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Gteq => {
+                                Instruction::Gteq(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Eq => {
+                                Instruction::Eq(register, left, right)
+                            }
+                            ast::BinaryArithmeticOperator::Neq => {
+                                Instruction::Neq(register, left, right)
+                            }
                         };
                         self.append_instruction(instruction);
                     }
@@ -372,7 +678,11 @@ impl<'ast> Translator<'ast> {
                                 self.end_procedure()
                             };
                             // 短絡処理: 左辺が真なら右辺を実行
-                            self.append_instruction(Instruction::If(register, right, vec![]));
+                            self.append_instruction(Instruction::If(
+                                register,
+                                right,
+                                Block::default(),
+                            ));
                         }
                         ast::BinaryLogicalOperator::Or => {
                             self.eval_expr(register, &node.left);
@@ -383,7 +693,11 @@ impl<'ast> Translator<'ast> {
                                 self.end_procedure()
                             };
                             // 短絡処理: 左辺が偽なら右辺を実行
-                            self.append_instruction(Instruction::If(register, vec![], right));
+                            self.append_instruction(Instruction::If(
+                                register,
+                                Block::default(),
+                                right,
+                            ));
                         }
                     },
                 }
@@ -427,15 +741,78 @@ impl<'ast> Translator<'ast> {
         register: Register,
         is_mutable: bool,
     ) {
+        self.define_variable(&dest.name, register, is_mutable);
+    }
+
+    fn define_variable(
+        &mut self,
+        name: impl Into<Cow<'ast, NamePath>>,
+        register: Register,
+        is_mutable: bool,
+    ) {
+        let name = name.into();
+        let location = match self.scopes.take_declared(&name) {
+            Some(variable) => variable.location,
+            None => {
+                if self.scopes.is_global()
+                    || self.captured_names.is_captured(&name, self.scopes.depth())
+                {
+                    self.new_location()
+                } else {
+                    Location::Register(register)
+                }
+            }
+        };
+        if location != Location::Register(register) {
+            self.store(register, location);
+        }
         self.scopes.add(
-            &dest.name,
+            name,
             Variable {
                 is_mutable,
-                register,
+                location,
+            },
+        );
+    }
+
+    /// 関数から参照できる変数の格納場所を新しく確保します。
+    fn new_location(&mut self) -> Location {
+        if self.scopes.is_global() && self.persistent_globals {
+            Location::Global(self.use_global())
+        } else {
+            let cell = self.use_cell();
+            self.append_instruction(Instruction::Cell(cell));
+            Location::Cell(cell)
+        }
+    }
+
+    /// 関数から参照される変数を定義に先立って宣言します。
+    fn hoist(&mut self, node: &'ast ast::Definition) {
+        let ast::Expression::Identifier(dest) = &node.dest else {
+            return;
+        };
+        let name = &dest.name;
+        if !self.captured_names.is_captured(name, self.scopes.depth()) {
+            return;
+        }
+        let location = self.new_location();
+        self.scopes.declare(
+            name,
+            Variable {
+                is_mutable: node.is_mut,
+                location,
             },
         );
     }
 
+    fn hoist_statements(&mut self, statements: &'ast [ast::StatementOrExpression]) {
+        for statement in statements {
+            if let ast::StatementOrExpression::Statement(ast::Statement::Def(node)) = statement {
+                self.hoist(node);
+            }
+        }
+    }
+
     fn define_arr(&mut self, dest: &'ast ast::Arr, register: Register, is_mutable: bool) {
         for (i, item) in dest.value.iter().enumerate() {
             let dest_register = self.use_register();
@@ -446,7 +823,7 @@ impl<'ast> Translator<'ast> {
 
     fn define_obj(&mut self, dest: &'ast ast::Obj, register: Register, is_mutable: bool) {
         for (key, item) in &dest.value {
-            let key_str = self.str_literal(&key);
+            let key_str = self.str_literal(key);
             let dest_register = self.use_register();
             self.append_instruction(Instruction::LoadProp(dest_register, register, key_str));
             self.define(item, dest_register, is_mutable);
@@ -458,7 +835,10 @@ impl<'ast> Translator<'ast> {
             ast::Expression::Identifier(dest) => {
                 let result = self.scopes.assign(&dest.name);
                 match result {
-                    Ok(register) => Some(Reference::Variable { register }),
+                    Ok((location, depth)) => {
+                        let location = self.resolve_location(location, depth)?;
+                        Some(Reference::Variable { location })
+                    }
                     Err(err) => {
                         self.append_instruction(Instruction::Panic(err));
                         None
@@ -491,7 +871,7 @@ impl<'ast> Translator<'ast> {
                     .value
                     .iter()
                     .map(|(key, item)| {
-                        let key = self.str_literal(&key);
+                        let key = self.str_literal(key);
                         let item = self.get_reference(item)?;
                         Some((key, item))
                     })
@@ -511,8 +891,8 @@ impl<'ast> Translator<'ast> {
 
     fn dereference(&mut self, dest: Register, reference: &Reference) {
         match reference {
-            Reference::Variable { register } => {
-                self.append_instruction(Instruction::Move(dest, *register));
+            Reference::Variable { location } => {
+                self.load(dest, *location);
             }
             Reference::Index { target, index } => {
                 self.append_instruction(Instruction::Load(dest, *target, *index));
@@ -544,8 +924,8 @@ impl<'ast> Translator<'ast> {
 
     fn assign(&mut self, dest: &Reference, src: Register) {
         match dest {
-            Reference::Variable { register } => {
-                self.append_instruction(Instruction::Move(*register, src));
+            Reference::Variable { location } => {
+                self.store(src, *location);
             }
             Reference::Index { target, index } => {
                 self.append_instruction(Instruction::Store(src, *target, *index));
@@ -570,16 +950,156 @@ impl<'ast> Translator<'ast> {
         }
     }
 
+    /// 関数を翻訳し、その番号と捕捉する外側の関数のセルを返します。
+    fn translate_fn(
+        &mut self,
+        node: &'ast ast::Fn,
+        name: Option<Utf16String>,
+    ) -> (UserFnIndex, Vec<CellIndex>) {
+        self.begin_function();
+
+        let param_names = node
+            .args
+            .iter()
+            .map(|arg| match &arg.dest {
+                ast::Expression::Identifier(dest) => Utf16String::from(dest.name.as_utf16_str()),
+                _ => Utf16String::from("?"),
+            })
+            .collect();
+
+        let params: Vec<Register> = node.args.iter().map(|_| self.use_register()).collect();
+        for (arg, param) in node.args.iter().zip(params) {
+            self.define(&arg.dest, param, true);
+        }
+
+        let result = self.use_register();
+        self.append_instruction(Instruction::Null(result));
+        self.hoist_statements(&node.children);
+        for statement in &node.children {
+            self.eval_statement_or_expr(result, statement);
+        }
+        self.append_instruction(Instruction::Return(result));
+
+        return self.end_function(name, param_names);
+    }
+
+    /// 外側の関数の変数の格納場所を、翻訳中の関数から参照できる格納場所に変換します。
+    /// `depth`は変数が属する関数の深さです。
+    fn resolve_location(&mut self, location: Location, depth: usize) -> Option<Location> {
+        if depth == self.outer_functions.len() {
+            return Some(location);
+        }
+        match location {
+            Location::Global(_) => Some(location),
+            Location::Cell(cell) => Some(Location::Cell(self.capture(cell, depth))),
+            Location::Register(_) => {
+                self.append_instruction(Instruction::Panic(AiScriptBasicError::new(
+                    AiScriptBasicErrorKind::Runtime,
+                    "Cannot capture a variable that is not stored in a cell.",
+                    None,
+                )));
+                None
+            }
+        }
+    }
+
+    /// 深さ`depth`の関数のセルを翻訳中の関数まで順に捕捉し、翻訳中の関数におけるセルを返します。
+    fn capture(&mut self, cell: CellIndex, depth: usize) -> CellIndex {
+        let mut cell = cell;
+        for level in depth + 1..=self.outer_functions.len() {
+            let function = if level == self.outer_functions.len() {
+                &mut self.function
+            } else {
+                &mut self.outer_functions[level]
+            };
+            let existing = function
+                .captures
+                .iter()
+                .find(|(outer, _)| *outer == cell)
+                .map(|(_, own)| *own);
+            cell = match existing {
+                Some(own) => own,
+                None => {
+                    let own = function.cell_length;
+                    function.cell_length += 1;
+                    function.captures.push((cell, own));
+                    own
+                }
+            };
+        }
+        return cell;
+    }
+
+    fn load(&mut self, dest: Register, location: Location) {
+        let instruction = match location {
+            Location::Register(src) => Instruction::Move(dest, src),
+            Location::Cell(src) => Instruction::LoadCell(dest, src),
+            Location::Global(src) => Instruction::LoadGlobal(dest, src),
+        };
+        self.append_instruction(instruction);
+    }
+
+    fn store(&mut self, src: Register, location: Location) {
+        let instruction = match location {
+            Location::Register(dest) => Instruction::Move(dest, src),
+            Location::Cell(dest) => Instruction::StoreCell(src, dest),
+            Location::Global(dest) => Instruction::StoreGlobal(src, dest),
+        };
+        self.append_instruction(instruction);
+    }
+
     fn use_register(&mut self) -> Register {
-        let index = self.register_length;
-        self.register_length += 1;
+        let index = self.function.register_length;
+        self.function.register_length += 1;
+        return index;
+    }
+
+    fn use_cell(&mut self) -> CellIndex {
+        let index = self.function.cell_length;
+        self.function.cell_length += 1;
+        return index;
+    }
+
+    fn use_global(&mut self) -> GlobalIndex {
+        let index = self.global_length;
+        self.global_length += 1;
         return index;
     }
 
+    /// 関数本体のスコープを生成して新しく関数の翻訳を開始します。
+    fn begin_function(&mut self) {
+        self.outer_functions
+            .push(std::mem::take(&mut self.function));
+        self.scopes.push_fn_scope();
+    }
+
+    /// 関数本体のスコープを破棄し、関数の翻訳を終了します。
+    fn end_function(
+        &mut self,
+        name: Option<Utf16String>,
+        params: Rc<[Utf16String]>,
+    ) -> (UserFnIndex, Vec<CellIndex>) {
+        self.scopes.drop_local_scope();
+        let outer = self.outer_functions.pop().expect("no outer functions");
+        let function = std::mem::replace(&mut self.function, outer);
+        let (outer_cells, captures) = function.captures.into_iter().unzip();
+        let index = self.user_function_offset + self.user_functions.len();
+        self.user_functions.push(UserFn {
+            name,
+            register_length: function.register_length,
+            cell_length: function.cell_length,
+            params,
+            captures,
+            body: function.block,
+        });
+        return (index, outer_cells);
+    }
+
     /// 新しく命令列を開始します。
     fn begin_procedure(&mut self) {
-        self.procedures
-            .push(std::mem::replace(&mut self.block, Vec::new()));
+        self.function
+            .procedures
+            .push(std::mem::take(&mut self.function.block));
     }
 
     /// ローカルスコープを生成して新しく命令列を開始します。
@@ -589,25 +1109,68 @@ impl<'ast> Translator<'ast> {
     }
 
     /// ローカルスコープを破棄し、命令列を終了して返します。
-    fn end_block(&mut self) -> Vec<Instruction> {
+    fn end_block(&mut self) -> Block {
         self.scopes.drop_local_scope();
         self.end_procedure()
     }
 
+    /// ループ本体の命令列を開始します。
+    fn begin_loop(&mut self, label: &Option<Utf16String>) {
+        self.begin_block();
+        self.function.loops.push(label.clone());
+    }
+
+    /// ループ本体の命令列を終了して返します。
+    fn end_loop(&mut self) -> Block {
+        self.function.loops.pop().expect("no loops");
+        self.end_block()
+    }
+
+    /// break / continueの対象のループが最も内側から数えていくつ外側にあるかを求めます。
+    fn loop_depth(
+        &self,
+        label: &Option<Utf16String>,
+        loc: &ast::Loc,
+    ) -> Result<usize, AiScriptBasicError> {
+        let Some(label) = label else {
+            return Ok(0);
+        };
+        let depth = self
+            .function
+            .loops
+            .iter()
+            .rev()
+            .position(|loop_label| loop_label.as_ref() == Some(label));
+        return depth.ok_or_else(|| {
+            AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Syntax,
+                format!("label \"{}\" is not defined", label),
+                Some(loc.start.clone()),
+            )
+        });
+    }
+
     /// 命令列を終了して返します。
-    fn end_procedure(&mut self) -> Vec<Instruction> {
+    fn end_procedure(&mut self) -> Block {
         std::mem::replace(
-            &mut self.block,
-            self.procedures.pop().expect("no outer blocks"),
+            &mut self.function.block,
+            self.function.procedures.pop().expect("no outer blocks"),
         )
     }
 
     fn append_instruction(&mut self, instruction: Instruction) {
-        self.block.push(instruction);
+        self.function.block.push(instruction, self.pos.clone());
+    }
+
+    /// 1つの命令だけからなる命令列を作成します。
+    fn single_block(&self, instruction: Instruction) -> Block {
+        let mut block = Block::default();
+        block.push(instruction, self.pos.clone());
+        return block;
     }
 
-    fn add_native_function(&mut self, f: NativeFn) -> UserFnIndex {
-        let index = self.native_functions.len();
+    fn add_native_function(&mut self, f: NativeFn) -> NativeFnIndex {
+        let index = self.native_function_offset + self.native_functions.len();
         self.native_functions.push(f);
         return index;
     }
@@ -623,3 +1186,12 @@ impl<'ast> Translator<'ast> {
         }
     }
 }
+
+/// 入れ子が深すぎて翻訳できない場合のエラー
+fn nesting_error(loc: &ast::Loc) -> AiScriptBasicError {
+    return AiScriptBasicError::new(
+        AiScriptBasicErrorKind::Syntax,
+        "nesting is too deep",
+        Some(loc.start.clone()),
+    );
+}
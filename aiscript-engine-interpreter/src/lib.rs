@@ -1,7 +1,15 @@
+mod abort;
 mod arguments;
+mod clock;
 mod interpreter;
 mod ir;
 mod library;
+mod random;
 mod vm;
 
-pub use interpreter::{Interpreter, InterpreterOpts};
+pub use abort::AbortHandle;
+pub use clock::{Clock, SystemClock};
+pub use interpreter::{Interpreter, InterpreterBuilder, InterpreterOpts};
+pub use library::{LibraryValue, NativeFn};
+pub use random::{DefaultRandomSource, RandomSource};
+pub use vm::Vm;
@@ -1,5 +1,9 @@
+#[allow(clippy::module_inception)]
 mod library;
+mod primitive_props;
 mod standard;
 
-pub(crate) use library::*;
-pub(crate) use standard::std_library;
+pub(crate) use library::{Library, MAX_ARRAY_LENGTH};
+pub use library::{LibraryValue, NativeFn};
+pub(crate) use primitive_props::{get_prim_prop, primitive_methods, PRIMITIVE_METHOD_COUNT};
+pub(crate) use standard::{bound_functions, std_library, BoundFn};
@@ -1,14 +1,20 @@
 use std::{collections::HashMap, fmt::Debug, rc::Rc};
 
 use aiscript_engine_common::{Result, Utf16String};
-use aiscript_engine_values::{VArr, VObj, Value};
+use aiscript_engine_values::{VArr, VError, VObj, Value};
 use gc::{Gc, GcCell};
 
 use crate::vm::Vm;
 
 pub(crate) type Library = HashMap<&'static [u16], LibraryValue>;
 
-pub(crate) enum LibraryValue {
+/// 配列の長さの上限。
+/// 巨大な配列の生成でホストのメモリを使い果たさないよう、ECMAScriptの上限よりも小さくしています。
+pub(crate) const MAX_ARRAY_LENGTH: usize = 1 << 24;
+
+/// ライブラリとしてスクリプトに公開する値。
+#[derive(Clone)]
+pub enum LibraryValue {
     Null,
     Bool(bool),
     Num(f64),
@@ -16,10 +22,13 @@ pub(crate) enum LibraryValue {
     Obj(Gc<GcCell<VObj>>),
     Arr(Gc<GcCell<VArr>>),
     Fn(NativeFn),
-    // TODO: Error
+    Error(Gc<VError>),
 }
 
-pub(crate) enum NativeFn {
+/// ホストが実装する関数。
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub enum NativeFn {
     Static(fn(Vec<Value>, &mut Vm) -> Result<Value>),
     Dynamic(Rc<dyn Fn(Vec<Value>, &mut Vm) -> Result<Value>>),
 }
@@ -0,0 +1,879 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::{
+    AiScriptBasicError, AiScriptBasicErrorKind, Result, Utf16Str, Utf16String,
+};
+use aiscript_engine_values::{FnIndex, VFn, Value};
+use gc::{Gc, GcCell};
+use utf16_literal::utf16;
+
+use crate::{arguments::Arguments, vm::Vm};
+
+type Method = fn(Vec<Value>, &mut Vm) -> Result<Value>;
+
+/// strのメソッド
+/// 呼び出し時には対象の値が第1引数として渡される。
+const STR_METHODS: &[(&[u16], Method)] = &[
+    (&utf16!("to_num"), str::to_num),
+    (&utf16!("to_arr"), str::to_arr),
+    (&utf16!("to_unicode_arr"), str::to_unicode_arr),
+    (
+        &utf16!("to_unicode_codepoint_arr"),
+        str::to_unicode_codepoint_arr,
+    ),
+    (&utf16!("to_char_arr"), str::to_char_arr),
+    (&utf16!("to_charcode_arr"), str::to_charcode_arr),
+    (&utf16!("to_utf8_byte_arr"), str::to_utf8_byte_arr),
+    (&utf16!("replace"), str::replace),
+    (&utf16!("index_of"), str::index_of),
+    (&utf16!("incl"), str::incl),
+    (&utf16!("trim"), str::trim),
+    (&utf16!("upper"), str::upper),
+    (&utf16!("lower"), str::lower),
+    (&utf16!("split"), str::split),
+    (&utf16!("slice"), str::slice),
+    (&utf16!("pick"), str::pick),
+    (&utf16!("charcode_at"), str::charcode_at),
+    (&utf16!("codepoint_at"), str::codepoint_at),
+    (&utf16!("starts_with"), str::starts_with),
+    (&utf16!("ends_with"), str::ends_with),
+    (&utf16!("pad_start"), str::pad_start),
+    (&utf16!("pad_end"), str::pad_end),
+];
+
+/// arrのメソッド
+/// 呼び出し時には対象の値が第1引数として渡される。
+const ARR_METHODS: &[(&[u16], Method)] = &[
+    (&utf16!("push"), arr::push),
+    (&utf16!("unshift"), arr::unshift),
+    (&utf16!("pop"), arr::pop),
+    (&utf16!("shift"), arr::shift),
+    (&utf16!("concat"), arr::concat),
+    (&utf16!("slice"), arr::slice),
+    (&utf16!("join"), arr::join),
+    (&utf16!("map"), arr::map),
+    (&utf16!("filter"), arr::filter),
+    (&utf16!("reduce"), arr::reduce),
+    (&utf16!("find"), arr::find),
+    (&utf16!("incl"), arr::incl),
+    (&utf16!("index_of"), arr::index_of),
+    (&utf16!("reverse"), arr::reverse),
+    (&utf16!("copy"), arr::copy),
+    (&utf16!("sort"), arr::sort),
+    (&utf16!("fill"), arr::fill),
+    (&utf16!("repeat"), arr::repeat),
+    (&utf16!("splice"), arr::splice),
+    (&utf16!("flat"), arr::flat),
+    (&utf16!("flat_map"), arr::flat_map),
+    (&utf16!("every"), arr::every),
+    (&utf16!("some"), arr::some),
+    (&utf16!("at"), arr::at),
+];
+
+/// numのメソッド
+/// 呼び出し時には対象の値が第1引数として渡される。
+const NUM_METHODS: &[(&[u16], Method)] = &[
+    (&utf16!("to_str"), num::to_str),
+    (&utf16!("to_hex"), num::to_hex),
+];
+
+/// プリミティブ値のメソッドの数
+pub(crate) const PRIMITIVE_METHOD_COUNT: usize =
+    STR_METHODS.len() + ARR_METHODS.len() + NUM_METHODS.len();
+
+/// プリミティブ値のメソッドとして使用するネイティブ関数。
+/// VMはこの順で先頭から登録する。
+pub(crate) fn primitive_methods() -> impl Iterator<Item = Method> {
+    STR_METHODS
+        .iter()
+        .chain(ARR_METHODS)
+        .chain(NUM_METHODS)
+        .map(|(_, method)| *method)
+}
+
+/// プリミティブ値のプロパティを取得します。
+pub(crate) fn get_prim_prop(target: &Value, name: &[u16]) -> Result<Value> {
+    match target {
+        Value::Str(value) => {
+            if name == utf16!("len") {
+                let len = Utf16Str::new(value).graphemes().len();
+                return Ok(Value::Num(len as f64));
+            }
+            return bind_method(target, name, STR_METHODS, 0);
+        }
+        Value::Arr(value) => {
+            if name == utf16!("len") {
+                return Ok(Value::Num(value.borrow().len() as f64));
+            }
+            return bind_method(target, name, ARR_METHODS, STR_METHODS.len());
+        }
+        Value::Num(_) => {
+            let offset = STR_METHODS.len() + ARR_METHODS.len();
+            return bind_method(target, name, NUM_METHODS, offset);
+        }
+        Value::Error(value) => {
+            if name == utf16!("name") {
+                return Ok(Value::Str(Rc::from(value.value.as_u16s())));
+            }
+            if name == utf16!("info") {
+                return Ok(value.info.clone().unwrap_or(Value::Null));
+            }
+            return bind_method(target, name, &[], 0);
+        }
+        _ => {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                format!(
+                    "Cannot read prop of {}. (reading {})",
+                    target.type_name(),
+                    Utf16Str::new(name)
+                ),
+                None,
+            )));
+        }
+    }
+}
+
+/// 対象の値を捕捉したメソッドを生成します。
+fn bind_method(
+    target: &Value,
+    name: &[u16],
+    methods: &[(&[u16], Method)],
+    offset: usize,
+) -> Result<Value> {
+    let Some(index) = methods.iter().position(|(key, _)| *key == name) else {
+        return Err(Box::new(AiScriptBasicError::new(
+            AiScriptBasicErrorKind::Runtime,
+            format!(
+                "No such prop: {} in {}.",
+                Utf16Str::new(name),
+                target.type_name()
+            ),
+            None,
+        )));
+    };
+    return Ok(Value::Fn(Gc::new(GcCell::new(VFn {
+        index: FnIndex::Native(offset + index),
+        defaults: Vec::new(),
+        capture: vec![Gc::new(GcCell::new(target.clone()))],
+        params: Rc::from([]),
+    }))));
+}
+
+fn str_value(value: &Utf16Str) -> Value {
+    Value::Str(Rc::from(value.as_u16s()))
+}
+
+fn arr_value(values: Vec<Value>) -> Value {
+    Value::Arr(Gc::new(GcCell::new(values)))
+}
+
+/// ECMAScriptのToIntegerOrInfinityで変換し、`0..=len`の範囲に収めます。
+fn clamp_index(index: f64, len: usize) -> usize {
+    if index.is_nan() || index <= 0.0 {
+        return 0;
+    }
+    return (index.trunc() as usize).min(len);
+}
+
+/// 文字列の長さ(UTF-16のコード単位数)の上限。
+/// ECMAScriptの処理系と同様に、巨大な文字列の生成をエラーにします。
+const MAX_STRING_LENGTH: usize = 1 << 30;
+
+/// `Array.prototype.slice`などと同様に、負の値を末尾からの位置として`0..=len`の範囲に収めます。
+fn relative_index(index: f64, len: usize) -> usize {
+    if index < 0.0 {
+        return clamp_index(len as f64 + index, len);
+    }
+    return clamp_index(index, len);
+}
+
+/// 整数の添字に変換します。
+fn to_index(index: f64) -> Option<usize> {
+    if index.is_nan() {
+        return Some(0);
+    }
+    let index = index.trunc();
+    if index < 0.0 || index > usize::MAX as f64 {
+        return None;
+    }
+    return Some(index as usize);
+}
+
+mod num {
+    use aiscript_engine_common::to_radix_string;
+    use aiscript_engine_values::repr_value;
+
+    use super::*;
+
+    pub(super) fn to_str(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = Value::Num(args.expect_number()?);
+        return Ok(Value::Str(Rc::from(repr_value(&target, false)?.as_u16s())));
+    }
+
+    pub(super) fn to_hex(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_number()?;
+        return Ok(Value::Str(Rc::from(to_radix_string(target, 16).as_u16s())));
+    }
+}
+
+mod str {
+    use aiscript_engine_common::parse_int;
+
+    use super::*;
+
+    pub(super) fn to_num(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let value = parse_int(Utf16Str::new(&target), 10);
+        if value.is_nan() {
+            return Ok(Value::Null);
+        }
+        return Ok(Value::Num(value));
+    }
+
+    pub(super) fn to_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let values = Utf16Str::new(&target)
+            .graphemes()
+            .into_iter()
+            .map(str_value)
+            .collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn to_unicode_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let values = Utf16Str::new(&target)
+            .code_points()
+            .map(str_value)
+            .collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn to_unicode_codepoint_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let values = Utf16Str::new(&target)
+            .code_points()
+            .filter_map(|char| char.code_point_at(0))
+            .map(|code_point| Value::Num(code_point as f64))
+            .collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn to_char_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let values = target
+            .iter()
+            .map(|char| Value::Str(Rc::from([*char])))
+            .collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn to_charcode_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let values = target.iter().map(|char| Value::Num(*char as f64)).collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn to_utf8_byte_arr(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        // 不完全なサロゲートはU+FFFDに置き換えられる
+        let values = Utf16Str::new(&target)
+            .to_string()
+            .bytes()
+            .map(|byte| Value::Num(byte as f64))
+            .collect();
+        return Ok(arr_value(values));
+    }
+
+    pub(super) fn replace(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let pattern = args.expect_string()?;
+        let replacement = args.expect_string()?;
+        let parts = Utf16Str::new(&target).split(Utf16Str::new(&pattern));
+        let result = Utf16String::join(&parts, Utf16Str::new(&replacement));
+        return Ok(str_value(&result));
+    }
+
+    pub(super) fn index_of(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let search = args.expect_string()?;
+        let from = args.expect_optional_number()?;
+        let from = match from {
+            Some(from) if from < 0.0 => target.len() as f64 + from,
+            Some(from) => from,
+            None => 0.0,
+        };
+        let from = clamp_index(from, target.len());
+        let index = Utf16Str::new(&target).find(Utf16Str::new(&search), from);
+        return Ok(Value::Num(index.map_or(-1.0, |index| index as f64)));
+    }
+
+    pub(super) fn incl(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let search = args.expect_string()?;
+        let index = Utf16Str::new(&target).find(Utf16Str::new(&search), 0);
+        return Ok(Value::Bool(index.is_some()));
+    }
+
+    pub(super) fn trim(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        return Ok(str_value(Utf16Str::new(&target).trim()));
+    }
+
+    pub(super) fn upper(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        return Ok(str_value(&Utf16Str::new(&target).to_uppercase()));
+    }
+
+    pub(super) fn lower(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        return Ok(str_value(&Utf16Str::new(&target).to_lowercase()));
+    }
+
+    pub(super) fn split(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let separator = args.expect_optional_string()?;
+        let target = Utf16Str::new(&target);
+        let parts = match &separator {
+            Some(separator) => target.split(Utf16Str::new(separator)),
+            None => target.graphemes(),
+        };
+        return Ok(arr_value(parts.into_iter().map(str_value).collect()));
+    }
+
+    pub(super) fn slice(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let begin = args.expect_number()?;
+        let end = args.expect_number()?;
+        let graphemes = Utf16Str::new(&target).graphemes();
+        let begin = relative_index(begin, graphemes.len());
+        let end = relative_index(end, graphemes.len());
+        let graphemes = graphemes.get(begin..end).unwrap_or_default();
+        return Ok(str_value(&Utf16String::join(graphemes, Utf16Str::new(&[]))));
+    }
+
+    pub(super) fn pick(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let index = args.expect_number()?;
+        if index.fract() != 0.0 || index < 0.0 {
+            return Ok(Value::Null);
+        }
+        let graphemes = Utf16Str::new(&target).graphemes();
+        return Ok(graphemes
+            .get(index as usize)
+            .map_or(Value::Null, |grapheme| str_value(grapheme)));
+    }
+
+    pub(super) fn charcode_at(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let index = args.expect_number()?;
+        let char = to_index(index).and_then(|index| target.get(index));
+        return Ok(char.map_or(Value::Null, |char| Value::Num(*char as f64)));
+    }
+
+    pub(super) fn codepoint_at(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let index = args.expect_number()?;
+        let code_point =
+            to_index(index).and_then(|index| Utf16Str::new(&target).code_point_at(index));
+        return Ok(code_point.map_or(Value::Null, |code_point| Value::Num(code_point as f64)));
+    }
+
+    pub(super) fn starts_with(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let prefix = args.expect_string()?;
+        let index = match args.expect_optional_number()? {
+            Some(index) => match resolve_relative_index(index, target.len()) {
+                Some(index) => index,
+                None => return Ok(Value::Bool(false)),
+            },
+            None => 0,
+        };
+        return Ok(Value::Bool(target[index..].starts_with(&prefix)));
+    }
+
+    pub(super) fn ends_with(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let suffix = args.expect_string()?;
+        let index = match args.expect_optional_number()? {
+            Some(index) => match resolve_relative_index(index, target.len()) {
+                Some(index) => index,
+                None => return Ok(Value::Bool(false)),
+            },
+            None => target.len(),
+        };
+        return Ok(Value::Bool(target[..index].ends_with(&suffix)));
+    }
+
+    pub(super) fn pad_start(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let width = args.expect_number()?;
+        let pad = args.expect_optional_string()?;
+        let padding = padding(&target, width, pad.as_deref().unwrap_or(&utf16!(" ")))?;
+        return Ok(str_value(&(padding + Utf16Str::new(&target))));
+    }
+
+    pub(super) fn pad_end(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_string()?;
+        let width = args.expect_number()?;
+        let pad = args.expect_optional_string()?;
+        let padding = padding(&target, width, pad.as_deref().unwrap_or(&utf16!(" ")))?;
+        let result = Utf16String::from(&*target) + padding.as_utf16_str();
+        return Ok(str_value(&result));
+    }
+
+    /// 負の値を末尾からの位置として解釈します。
+    /// 範囲外の場合は`None`を返します。
+    fn resolve_relative_index(index: f64, len: usize) -> Option<usize> {
+        let len_f64 = len as f64;
+        if index < -len_f64 || index > len_f64 {
+            return None;
+        }
+        let index = if index >= 0.0 { index } else { len_f64 + index };
+        return Some(clamp_index(index, len));
+    }
+
+    /// `String.prototype.padStart`と同様に、`width`に満たない分の埋め草を生成します。
+    fn padding(target: &[u16], width: f64, pad: &[u16]) -> Result<Utf16String> {
+        let width = clamp_index(width, usize::MAX);
+        let mut result = Utf16String::new();
+        if pad.is_empty() || width <= target.len() {
+            return Ok(result);
+        }
+        if width > MAX_STRING_LENGTH {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "Invalid string length",
+                None,
+            )));
+        }
+        result.extend(pad.iter().copied().cycle().take(width - target.len()));
+        return Ok(result);
+    }
+}
+
+mod arr {
+    use aiscript_engine_values::{require_array, require_boolean, require_number, VArr};
+
+    use crate::library::MAX_ARRAY_LENGTH;
+
+    use super::*;
+
+    /// 呼び出し時点の長さまで、添字と要素を順に取り出します。
+    /// コールバックの中で配列が変更されても安全に参照できます。
+    fn items(target: &Gc<GcCell<VArr>>, start: usize) -> impl Iterator<Item = (usize, Value)> + '_ {
+        let len = target.borrow().len();
+        (start..len).map_while(move |index| {
+            let item = target.borrow().get(index).cloned();
+            item.map(|item| (index, item))
+        })
+    }
+
+    pub(super) fn push(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = args.expect_any()?;
+        target.borrow_mut().push(value);
+        return Ok(Value::Arr(target));
+    }
+
+    pub(super) fn unshift(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = args.expect_any()?;
+        target.borrow_mut().insert(0, value);
+        return Ok(Value::Arr(target));
+    }
+
+    pub(super) fn pop(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = target.borrow_mut().pop();
+        return Ok(value.unwrap_or(Value::Null));
+    }
+
+    pub(super) fn shift(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let mut target = target.borrow_mut();
+        if target.is_empty() {
+            return Ok(Value::Null);
+        }
+        return Ok(target.remove(0));
+    }
+
+    pub(super) fn concat(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let other = args.expect_array()?;
+        let mut result = target.borrow().clone();
+        result.extend(other.borrow().iter().cloned());
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn slice(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let begin = args.expect_number()?;
+        let end = args.expect_number()?;
+        let target = target.borrow();
+        let begin = relative_index(begin, target.len());
+        let end = relative_index(end, target.len());
+        let result = target.get(begin..end).unwrap_or_default().to_vec();
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn join(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let joiner = args.expect_optional_string()?;
+        let target = target.borrow();
+        // 文字列以外の要素は空文字列として扱う
+        let items: Vec<&Utf16Str> = target
+            .iter()
+            .map(|item| match item {
+                Value::Str(value) => Utf16Str::new(value),
+                _ => Utf16Str::new(&[]),
+            })
+            .collect();
+        let joiner = joiner.as_deref().unwrap_or_default();
+        let result = Utf16String::join(&items, Utf16Str::new(joiner));
+        return Ok(str_value(&result));
+    }
+
+    pub(super) fn map(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        let mut result = Vec::new();
+        for (index, item) in items(&target, 0) {
+            result.push(vm.call(&f, vec![item, Value::Num(index as f64)])?);
+        }
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn filter(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        let mut result = Vec::new();
+        for (index, item) in items(&target, 0) {
+            let value = vm.call(&f, vec![item.clone(), Value::Num(index as f64)])?;
+            if require_boolean(&value)? {
+                result.push(item);
+            }
+        }
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn reduce(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        let (mut accumulator, start) = match args.expect_optional_any() {
+            Some(initial_value) => (initial_value, 0),
+            None => {
+                let first = target.borrow().first().cloned();
+                let Some(first) = first else {
+                    return Err(Box::new(AiScriptBasicError::new(
+                        AiScriptBasicErrorKind::Runtime,
+                        "Reduce of empty array without initial value",
+                        None,
+                    )));
+                };
+                (first, 1)
+            }
+        };
+        for (index, item) in items(&target, start) {
+            accumulator = vm.call(&f, vec![accumulator, item, Value::Num(index as f64)])?;
+        }
+        return Ok(accumulator);
+    }
+
+    pub(super) fn find(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        for (index, item) in items(&target, 0) {
+            let value = vm.call(&f, vec![item.clone(), Value::Num(index as f64)])?;
+            if require_boolean(&value)? {
+                return Ok(item);
+            }
+        }
+        return Ok(Value::Null);
+    }
+
+    pub(super) fn incl(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = args.expect_any()?;
+        // プリミティブ値のみをSameValueZeroで比較する
+        let same_value_zero = |item: &Value| match (item, &value) {
+            (Value::Num(a), Value::Num(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::Str(_), Value::Str(_))
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::Null, Value::Null) => item == &value,
+            _ => false,
+        };
+        let result = target.borrow().iter().any(same_value_zero);
+        return Ok(Value::Bool(result));
+    }
+
+    pub(super) fn index_of(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = args.expect_any()?;
+        let from = args.expect_optional_number()?;
+        let target = target.borrow();
+        let from = from.map_or(0, |from| relative_index(from, target.len()));
+        let index = target[from..].iter().position(|item| item == &value);
+        return Ok(Value::Num(
+            index.map_or(-1.0, |index| (index + from) as f64),
+        ));
+    }
+
+    pub(super) fn reverse(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        target.borrow_mut().reverse();
+        return Ok(Value::Null);
+    }
+
+    pub(super) fn copy(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let result = target.borrow().clone();
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn sort(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        let items = target.borrow().clone();
+        let result = merge_sort(items, &f, vm)?;
+        *target.borrow_mut() = result;
+        return Ok(Value::Arr(target));
+    }
+
+    /// 比較関数が負の値を返した場合のみ左側の要素を先に並べるマージソート
+    fn merge_sort(mut items: Vec<Value>, f: &Gc<GcCell<VFn>>, vm: &mut Vm) -> Result<Vec<Value>> {
+        if items.len() <= 1 {
+            return Ok(items);
+        }
+        let right = items.split_off(items.len() / 2);
+        let left = merge_sort(items, f, vm)?;
+        let right = merge_sort(right, f, vm)?;
+        let mut result = Vec::with_capacity(left.len() + right.len());
+        let mut left = left.into_iter().peekable();
+        let mut right = right.into_iter().peekable();
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            let order = vm.call(f, vec![l.clone(), r.clone()])?;
+            if require_number(&order)? < 0.0 {
+                result.extend(left.next());
+            } else {
+                result.extend(right.next());
+            }
+        }
+        result.extend(left);
+        result.extend(right);
+        return Ok(result);
+    }
+
+    pub(super) fn fill(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let value = args.expect_any()?;
+        let start = args.expect_optional_number()?;
+        let end = args.expect_optional_number()?;
+        {
+            let mut items = target.borrow_mut();
+            let len = items.len();
+            let start = start.map_or(0, |start| relative_index(start, len));
+            let end = end.map_or(len, |end| relative_index(end, len));
+            for item in items.get_mut(start..end).unwrap_or_default() {
+                *item = value.clone();
+            }
+        }
+        return Ok(Value::Arr(target));
+    }
+
+    pub(super) fn repeat(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let times = args.expect_number()?;
+        if times < 0.0 {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "arr.repeat expected non-negative number, got negative",
+                None,
+            )));
+        }
+        if times.fract() != 0.0 {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "arr.repeat expected integer, got non-integer",
+                None,
+            )));
+        }
+        let items = target.borrow();
+        if items.is_empty() {
+            return Ok(arr_value(Vec::new()));
+        }
+        if times * items.len() as f64 > MAX_ARRAY_LENGTH as f64 {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "Invalid array length",
+                None,
+            )));
+        }
+        let mut result = Vec::with_capacity(times as usize * items.len());
+        for _ in 0..times as usize {
+            for item in items.iter() {
+                vm.count_step()?;
+                result.push(item.clone());
+            }
+        }
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn splice(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let index = args.expect_number()?;
+        let remove_count = args.expect_optional_number()?;
+        let items = match args.expect_optional_any() {
+            Some(items) => Some(require_array(&items)?),
+            None => None,
+        };
+        let items = items.map_or_else(Vec::new, |items| items.borrow().clone());
+        let mut target = target.borrow_mut();
+        let len = target.len();
+        let index = relative_index(index, len);
+        let remove_count =
+            remove_count.map_or(len - index, |count| clamp_index(count, len - index));
+        let removed = target.splice(index..index + remove_count, items).collect();
+        return Ok(arr_value(removed));
+    }
+
+    pub(super) fn flat(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let depth = args.expect_optional_number()?.unwrap_or(1.0);
+        if depth.fract() != 0.0 {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "arr.flat expected integer, got non-integer",
+                None,
+            )));
+        }
+        // 循環参照で無限に再帰しないように、明示的なスタックで展開する
+        let mut result = Vec::new();
+        let mut stack = vec![(target.borrow().clone().into_iter(), depth)];
+        while let Some((items, depth)) = stack.last_mut() {
+            let depth = *depth;
+            let Some(item) = items.next() else {
+                stack.pop();
+                continue;
+            };
+            match item {
+                Value::Arr(item) if depth != 0.0 => {
+                    if stack.len() >= MAX_FLAT_DEPTH {
+                        return Err(Box::new(AiScriptBasicError::new(
+                            AiScriptBasicErrorKind::Runtime,
+                            "Maximum call stack size exceeded",
+                            None,
+                        )));
+                    }
+                    let items = item.borrow().clone().into_iter();
+                    stack.push((items, depth - 1.0));
+                }
+                item => result.push(item),
+            }
+        }
+        return Ok(arr_value(result));
+    }
+
+    /// `flat`で展開する配列の入れ子の上限
+    const MAX_FLAT_DEPTH: usize = 10000;
+
+    pub(super) fn flat_map(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        let mut result = Vec::new();
+        for (index, item) in items(&target, 0) {
+            match vm.call(&f, vec![item, Value::Num(index as f64)])? {
+                Value::Arr(value) => result.extend(value.borrow().iter().cloned()),
+                value => result.push(value),
+            }
+        }
+        return Ok(arr_value(result));
+    }
+
+    pub(super) fn every(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        for (index, item) in items(&target, 0) {
+            let value = vm.call(&f, vec![item, Value::Num(index as f64)])?;
+            if !require_boolean(&value)? {
+                return Ok(Value::Bool(false));
+            }
+        }
+        return Ok(Value::Bool(true));
+    }
+
+    pub(super) fn some(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let f = args.expect_function()?;
+        for (index, item) in items(&target, 0) {
+            let value = vm.call(&f, vec![item, Value::Num(index as f64)])?;
+            if require_boolean(&value)? {
+                return Ok(Value::Bool(true));
+            }
+        }
+        return Ok(Value::Bool(false));
+    }
+
+    pub(super) fn at(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let target = args.expect_array()?;
+        let index = args.expect_number()?;
+        let otherwise = args.expect_optional_any();
+        let target = target.borrow();
+        let index = if index < 0.0 {
+            target.len() as f64 + index.trunc()
+        } else {
+            index.trunc()
+        };
+        let item = to_index(index).and_then(|index| target.get(index)).cloned();
+        return Ok(item.or(otherwise).unwrap_or(Value::Null));
+    }
+}
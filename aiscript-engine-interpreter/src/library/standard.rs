@@ -1,9 +1,24 @@
 use std::collections::HashMap;
 
-use aiscript_engine_common::Utf16String;
+use aiscript_engine_common::{Result, Utf16String};
+use aiscript_engine_values::Value;
 use utf16_literal::utf16;
 
 use super::{Library, LibraryValue};
+use crate::vm::Vm;
+
+mod r#async;
+mod chacha20;
+mod date;
+mod error;
+mod json;
+mod math;
+mod num;
+mod obj;
+mod seeded_rng;
+mod seedrandom;
+mod str;
+mod util;
 
 macro_rules! str {
     ($name: expr , $value: expr) => {
@@ -16,6 +31,15 @@ macro_rules! str {
     };
 }
 
+macro_rules! num {
+    ($name: expr , $value: expr) => {
+        (
+            &$name as &'static [u16],
+            $crate::library::LibraryValue::Num($value),
+        )
+    };
+}
+
 macro_rules! func {
     ($name: expr , $value: expr) => {
         (
@@ -47,22 +71,137 @@ pub(crate) fn std_library() -> Library {
         func!(utf16!("Core:lt"), core::lt),
         func!(utf16!("Core:gteq"), core::gteq),
         func!(utf16!("Core:lteq"), core::lteq),
+        func!(utf16!("Core:type"), core::type_of),
+        func!(utf16!("Core:to_str"), core::to_str),
+        func!(utf16!("Core:range"), core::range),
+        func!(utf16!("Core:sleep"), core::sleep),
+        func!(utf16!("Core:abort"), core::abort),
+        num!(utf16!("Math:Infinity"), f64::INFINITY),
+        num!(utf16!("Math:E"), std::f64::consts::E),
+        num!(utf16!("Math:LN2"), std::f64::consts::LN_2),
+        num!(utf16!("Math:LN10"), std::f64::consts::LN_10),
+        num!(utf16!("Math:LOG2E"), std::f64::consts::LOG2_E),
+        num!(utf16!("Math:LOG10E"), std::f64::consts::LOG10_E),
+        num!(utf16!("Math:PI"), std::f64::consts::PI),
+        num!(utf16!("Math:SQRT1_2"), std::f64::consts::FRAC_1_SQRT_2),
+        num!(utf16!("Math:SQRT2"), std::f64::consts::SQRT_2),
+        func!(utf16!("Math:abs"), math::abs),
+        func!(utf16!("Math:acos"), math::acos),
+        func!(utf16!("Math:acosh"), math::acosh),
+        func!(utf16!("Math:asin"), math::asin),
+        func!(utf16!("Math:asinh"), math::asinh),
+        func!(utf16!("Math:atan"), math::atan),
+        func!(utf16!("Math:atanh"), math::atanh),
+        func!(utf16!("Math:atan2"), math::atan2),
+        func!(utf16!("Math:cbrt"), math::cbrt),
+        func!(utf16!("Math:ceil"), math::ceil),
+        func!(utf16!("Math:clz32"), math::clz32),
+        func!(utf16!("Math:cos"), math::cos),
+        func!(utf16!("Math:cosh"), math::cosh),
+        func!(utf16!("Math:exp"), math::exp),
+        func!(utf16!("Math:expm1"), math::expm1),
+        func!(utf16!("Math:floor"), math::floor),
+        func!(utf16!("Math:fround"), math::fround),
+        func!(utf16!("Math:hypot"), math::hypot),
+        func!(utf16!("Math:imul"), math::imul),
+        func!(utf16!("Math:log"), math::log),
+        func!(utf16!("Math:log1p"), math::log1p),
+        func!(utf16!("Math:log10"), math::log10),
+        func!(utf16!("Math:log2"), math::log2),
+        func!(utf16!("Math:max"), math::max),
+        func!(utf16!("Math:min"), math::min),
+        func!(utf16!("Math:pow"), math::pow),
+        func!(utf16!("Math:round"), math::round),
+        func!(utf16!("Math:sign"), math::sign),
+        func!(utf16!("Math:sin"), math::sin),
+        func!(utf16!("Math:sinh"), math::sinh),
+        func!(utf16!("Math:sqrt"), math::sqrt),
+        func!(utf16!("Math:tan"), math::tan),
+        func!(utf16!("Math:tanh"), math::tanh),
+        func!(utf16!("Math:trunc"), math::trunc),
+        func!(utf16!("Math:rnd"), math::rnd),
+        func!(utf16!("Math:gen_rng"), math::gen_rng),
+        func!(utf16!("Num:to_hex"), num::to_hex),
+        func!(utf16!("Num:from_hex"), num::from_hex),
+        str!(utf16!("Str:lf"), utf16!("\n")),
+        func!(utf16!("Str:lt"), str::lt),
+        func!(utf16!("Str:gt"), str::gt),
+        func!(utf16!("Str:from_codepoint"), str::from_codepoint),
+        func!(
+            utf16!("Str:from_unicode_codepoints"),
+            str::from_unicode_codepoints
+        ),
+        func!(utf16!("Str:from_utf8_bytes"), str::from_utf8_bytes),
+        func!(utf16!("Obj:keys"), obj::keys),
+        func!(utf16!("Obj:vals"), obj::vals),
+        func!(utf16!("Obj:kvs"), obj::kvs),
+        func!(utf16!("Obj:get"), obj::get),
+        func!(utf16!("Obj:set"), obj::set),
+        func!(utf16!("Obj:has"), obj::has),
+        func!(utf16!("Obj:copy"), obj::copy),
+        func!(utf16!("Obj:merge"), obj::merge),
+        func!(utf16!("Obj:pick"), obj::pick),
+        func!(utf16!("Json:stringify"), json::stringify),
+        func!(utf16!("Json:parse"), json::parse),
+        func!(utf16!("Json:parsable"), json::parsable),
+        func!(utf16!("Util:uuid"), util::uuid),
+        func!(utf16!("Error:create"), error::create),
+        func!(utf16!("Async:timeout"), r#async::timeout),
+        func!(utf16!("Async:interval"), r#async::interval),
+        func!(utf16!("Date:now"), date::now),
+        func!(utf16!("Date:year"), date::year),
+        func!(utf16!("Date:month"), date::month),
+        func!(utf16!("Date:day"), date::day),
+        func!(utf16!("Date:hour"), date::hour),
+        func!(utf16!("Date:minute"), date::minute),
+        func!(utf16!("Date:second"), date::second),
+        func!(utf16!("Date:millisecond"), date::millisecond),
+        func!(utf16!("Date:parse"), date::parse),
+        func!(utf16!("Date:to_iso_str"), date::to_iso_str),
     ])
 }
 
+/// 標準ライブラリの関数が値を捕捉して生成する関数。
+/// 生成のたびにVMへ登録しないよう、VMはプリミティブ値のメソッドに続けてこの順で登録する。
+/// 捕捉した値は呼び出し時に引数の先頭に渡される。
+#[derive(Clone, Copy)]
+pub(crate) enum BoundFn {
+    /// `Async:timeout`と`Async:interval`が返すタイマーの取り消し
+    CancelTimer,
+}
+
+impl BoundFn {
+    const ALL: [BoundFn; 1] = [BoundFn::CancelTimer];
+
+    fn function(self) -> fn(Vec<Value>, &mut Vm) -> Result<Value> {
+        match self {
+            BoundFn::CancelTimer => r#async::cancel,
+        }
+    }
+}
+
+/// 値を捕捉して生成される関数として使用するネイティブ関数。
+pub(crate) fn bound_functions() -> impl Iterator<Item = fn(Vec<Value>, &mut Vm) -> Result<Value>> {
+    BoundFn::ALL.into_iter().map(BoundFn::function)
+}
+
 fn version() -> Utf16String {
+    // パッケージのバージョンのビルドメタデータが対応するAiScriptのバージョン
     let version_str = env!("CARGO_PKG_VERSION");
-    let separator = version_str
-        .find('+')
-        .expect("no plus sign in package version");
-    return Utf16String::from(&version_str[separator + 1..]);
+    let version = version_str
+        .split_once('+')
+        .map_or(version_str, |(_, version)| version);
+    return Utf16String::from(version);
 }
 
 mod core {
-    use aiscript_engine_common::Result;
-    use aiscript_engine_values::Value;
+    use std::rc::Rc;
 
-    use crate::{arguments::Arguments, vm::Vm};
+    use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result, Utf16Str};
+    use aiscript_engine_values::{repr_value, Value};
+    use gc::{Gc, GcCell};
+
+    use crate::{arguments::Arguments, library::MAX_ARRAY_LENGTH, vm, vm::Vm};
 
     pub(super) fn not(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
         let mut args = Arguments::from(args);
@@ -122,7 +261,7 @@ mod core {
         let mut args = Arguments::from(args);
         let left = args.expect_number()?;
         let right = args.expect_number()?;
-        Ok(Value::Num(left.powf(right)))
+        Ok(Value::Num(vm::pow(left, right)))
     }
 
     pub(super) fn div(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
@@ -166,4 +305,71 @@ mod core {
         let right = args.expect_number()?;
         Ok(Value::Bool(left <= right))
     }
+
+    pub(super) fn type_of(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let value = args.expect_any()?;
+        Ok(Value::Str(Rc::from(value.type_name().as_u16s())))
+    }
+
+    pub(super) fn to_str(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let value = args.expect_any()?;
+        Ok(Value::Str(Rc::from(repr_value(&value, false)?.as_u16s())))
+    }
+
+    pub(super) fn range(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let start = args.expect_number()?;
+        let end = args.expect_number()?;
+        let length = (end - start).abs().floor() + 1.0;
+        if !length.is_finite() || length > MAX_ARRAY_LENGTH as f64 {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "Invalid array length",
+                None,
+            )));
+        }
+        let step = if start <= end { 1.0 } else { -1.0 };
+        let mut result = Vec::with_capacity(length as usize);
+        let mut i = start;
+        for _ in 0..length as usize {
+            vm.count_step()?;
+            result.push(Value::Num(i));
+            i += step;
+        }
+        Ok(Value::Arr(Gc::new(GcCell::new(result))))
+    }
+
+    /// `Core:sleep`が一度に待機する最大のミリ秒。
+    /// 待機中も停止の要求に応じられるよう、この間隔ごとに確認する。
+    const SLEEP_SLICE: f64 = 10.0;
+
+    pub(super) fn sleep(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let delay = args.expect_number()?;
+        // setTimeoutと同様に、範囲外の値は1ミリ秒として扱う
+        let mut remaining = if (1.0..=2147483647.0).contains(&delay) {
+            delay
+        } else {
+            1.0
+        };
+        while remaining > 0.0 {
+            vm.check_aborted()?;
+            let slice = remaining.min(SLEEP_SLICE);
+            vm.clock().sleep(slice);
+            remaining -= slice;
+        }
+        Ok(Value::Null)
+    }
+
+    pub(super) fn abort(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+        let mut args = Arguments::from(args);
+        let message = args.expect_string()?;
+        Err(Box::new(AiScriptBasicError::new(
+            AiScriptBasicErrorKind::User,
+            Utf16Str::new(&message).to_string(),
+            None,
+        )))
+    }
 }
@@ -0,0 +1,57 @@
+use aiscript_engine_common::Result;
+use aiscript_engine_values::Value;
+use gc::Gc;
+
+use super::BoundFn;
+use crate::{arguments::Arguments, vm::Vm};
+
+/// `setTimeout`の遅延として指定できる最大値
+const MAX_DELAY: f64 = 2147483647.0;
+
+pub(super) fn timeout(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let delay = args.expect_number()?;
+    let callback = args.expect_function()?;
+    let due = vm.clock().now() + to_delay(delay);
+    let id = vm.timers().schedule(due, None, callback);
+    Ok(cancel_fn(vm, id))
+}
+
+pub(super) fn interval(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let interval = to_delay(args.expect_number()?);
+    let callback = args.expect_function()?;
+    let immediate = match args.expect_optional_any() {
+        Some(Value::Bool(immediate)) => immediate,
+        _ => false,
+    };
+    let due = vm.clock().now() + interval;
+    let id = vm
+        .timers()
+        .schedule(due, Some(interval), Gc::clone(&callback));
+    if immediate {
+        vm.call(&callback, Vec::new())?;
+    }
+    Ok(cancel_fn(vm, id))
+}
+
+/// タイマーを取り消す関数を作成します。
+fn cancel_fn(vm: &Vm, id: u64) -> Value {
+    return vm.bound_fn(BoundFn::CancelTimer, vec![Value::Num(id as f64)]);
+}
+
+/// 捕捉したIDのタイマーを取り消します。
+pub(super) fn cancel(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let id = args.expect_number()?;
+    vm.timers().cancel(id as u64);
+    Ok(Value::Null)
+}
+
+/// Node.jsの`setTimeout`と同様に、1未満や上限を超える遅延は1ミリ秒として扱います。
+fn to_delay(delay: f64) -> f64 {
+    if !(1.0..=MAX_DELAY).contains(&delay) {
+        return 1.0;
+    }
+    return delay.trunc();
+}
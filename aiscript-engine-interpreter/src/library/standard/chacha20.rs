@@ -0,0 +1,143 @@
+//! ChaCha20の鍵ストリームによる乱数生成器
+
+/// 鍵のバイト数
+const KEY_SIZE: usize = 32;
+
+/// nonceのバイト数。カウンターとnonceはそれぞれ64ビット
+const NONCE_SIZE: usize = 8;
+
+/// 1ブロックのバイト数
+const BLOCK_SIZE: usize = 64;
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// シードから決定的にバイト列を生成する。
+pub(crate) struct ChaCha20 {
+    /// 定数、鍵、カウンター、nonceからなる入力
+    state: [u32; 16],
+
+    /// 生成済みの鍵ストリーム
+    block: [u8; BLOCK_SIZE],
+
+    /// `block`の中で次に返すバイトの位置
+    position: usize,
+}
+
+impl ChaCha20 {
+    /// シードの先頭から鍵とnonceを取り出します。
+    /// シードが足りない分は0で埋め、余った分は使用しません。
+    pub(crate) fn new(seed: &[u8]) -> Self {
+        let mut key_nonce = [0; KEY_SIZE + NONCE_SIZE];
+        let len = seed.len().min(key_nonce.len());
+        key_nonce[..len].copy_from_slice(&seed[..len]);
+
+        let mut state = [0; 16];
+        state[..4].copy_from_slice(&CONSTANTS);
+        for (word, bytes) in state[4..12]
+            .iter_mut()
+            .zip(key_nonce[..KEY_SIZE].chunks_exact(4))
+        {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for (word, bytes) in state[14..]
+            .iter_mut()
+            .zip(key_nonce[KEY_SIZE..].chunks_exact(4))
+        {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        return ChaCha20 {
+            state,
+            block: [0; BLOCK_SIZE],
+            position: BLOCK_SIZE,
+        };
+    }
+
+    pub(crate) fn next_byte(&mut self) -> u8 {
+        if self.position == BLOCK_SIZE {
+            self.block = block(&self.state);
+            self.position = 0;
+            // 64ビットのカウンターを進める
+            self.state[12] = self.state[12].wrapping_add(1);
+            if self.state[12] == 0 {
+                self.state[13] = self.state[13].wrapping_add(1);
+            }
+        }
+        let byte = self.block[self.position];
+        self.position += 1;
+        return byte;
+    }
+}
+
+/// 入力から1ブロックの鍵ストリームを生成します。
+fn block(input: &[u32; 16]) -> [u8; BLOCK_SIZE] {
+    let mut x = *input;
+    for _ in 0..10 {
+        // 列
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        // 対角線
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    let mut output = [0; BLOCK_SIZE];
+    for ((bytes, x), input) in output.chunks_exact_mut(4).zip(x).zip(input) {
+        bytes.copy_from_slice(&x.wrapping_add(*input).to_le_bytes());
+    }
+    return output;
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 7539 2.3.2のテストベクター
+    #[test]
+    fn block_function() {
+        let mut input = [0; 16];
+        input[..4].copy_from_slice(&CONSTANTS);
+        for (i, word) in input[4..12].iter_mut().enumerate() {
+            let i = i as u8 * 4;
+            *word = u32::from_le_bytes([i, i + 1, i + 2, i + 3]);
+        }
+        input[12] = 1;
+        input[13] = 0x09000000;
+        input[14] = 0x4a000000;
+        input[15] = 0;
+        let expected = [
+            0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20,
+            0x71, 0xc4, 0xc7, 0xd1, 0xf4, 0xc7, 0x33, 0xc0, 0x68, 0x03, 0x04, 0x22, 0xaa, 0x9a,
+            0xc3, 0xd4, 0x6c, 0x4e, 0xd2, 0x82, 0x64, 0x46, 0x07, 0x9f, 0xaa, 0x09, 0x14, 0xc2,
+            0xd7, 0x05, 0xd9, 0x8b, 0x02, 0xa2, 0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9,
+            0xcb, 0xd0, 0x83, 0xe8, 0xa2, 0x50, 0x3c, 0x4e,
+        ];
+        assert_eq!(block(&input), expected);
+    }
+
+    /// ブロックをまたいでカウンターを進めながら鍵ストリームを返す
+    #[test]
+    fn key_stream() {
+        let mut rng = ChaCha20::new(&[]);
+        let stream: Vec<u8> = (0..BLOCK_SIZE * 2).map(|_| rng.next_byte()).collect();
+        let mut input = [0; 16];
+        input[..4].copy_from_slice(&CONSTANTS);
+        assert_eq!(stream[..BLOCK_SIZE], block(&input));
+        input[12] = 1;
+        assert_eq!(stream[BLOCK_SIZE..], block(&input));
+    }
+}
@@ -0,0 +1,392 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::{num_to_string, Result, Utf16Str, Utf16String};
+use aiscript_engine_values::Value;
+
+use crate::{arguments::Arguments, vm::Vm};
+
+const MS_PER_SECOND: f64 = 1000.0;
+const MS_PER_MINUTE: f64 = 60000.0;
+const MS_PER_HOUR: f64 = 3600000.0;
+const MS_PER_DAY: f64 = 86400000.0;
+
+/// JavaScriptの`Date`が表せる時刻の絶対値の上限
+const MAX_TIME: f64 = 8.64e15;
+
+pub(super) fn now(_: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(vm.clock().now()))
+}
+
+pub(super) fn year(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.year)?))
+}
+
+pub(super) fn month(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.month)?))
+}
+
+pub(super) fn day(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.day)?))
+}
+
+pub(super) fn hour(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.hour)?))
+}
+
+pub(super) fn minute(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.minute)?))
+}
+
+pub(super) fn second(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| fields.second)?))
+}
+
+pub(super) fn millisecond(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    Ok(Value::Num(local_field(args, vm, |fields| {
+        fields.millisecond
+    })?))
+}
+
+pub(super) fn parse(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let s = args.expect_string()?;
+    let time = match parse_iso(Utf16Str::new(&s)) {
+        Some(ParsedDate::Utc(time)) => time_clip(time),
+        Some(ParsedDate::Local(time)) => {
+            time_clip(time + vm.clock().timezone_offset(time) * MS_PER_MINUTE)
+        }
+        None => f64::NAN,
+    };
+    if time.is_nan() {
+        return Ok(Value::error("not_date", None));
+    }
+    Ok(Value::Num(time))
+}
+
+pub(super) fn to_iso_str(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let time = args.expect_optional_number()?;
+    let ofs = args.expect_optional_number()?;
+    // 本家と同様に、0やNaNは省略した場合と同じ扱いになる
+    let time = time_clip(match time {
+        Some(time) if is_truthy(time) => time,
+        _ => vm.clock().now(),
+    });
+    let offset = match ofs {
+        Some(ofs) if is_truthy(ofs) => ofs,
+        _ => -vm.clock().timezone_offset(time),
+    };
+    let offset_str = if offset == 0.0 {
+        "Z".to_string()
+    } else {
+        let sign = if offset < 0.0 { '-' } else { '+' };
+        let hours = (offset.abs() / 60.0).floor();
+        let minutes = offset.abs() % 60.0;
+        format!("{}{}:{}", sign, pad_num(hours, 2), pad_num(minutes, 2))
+    };
+    let fields = DateFields::new(time_clip(time + offset * MS_PER_MINUTE));
+    let result = format!(
+        "{}-{}-{}T{}:{}:{}.{}{}",
+        pad_num(fields.year, 4),
+        pad_num(fields.month, 2),
+        pad_num(fields.day, 2),
+        pad_num(fields.hour, 2),
+        pad_num(fields.minute, 2),
+        pad_num(fields.second, 2),
+        pad_num(fields.millisecond, 3),
+        offset_str,
+    );
+    Ok(Value::Str(Rc::from(
+        Utf16String::from(result.as_str()).as_u16s(),
+    )))
+}
+
+/// 省略可能な時刻を受け取り、地方時における日時の要素を返します。
+fn local_field(
+    args: Vec<Value>,
+    vm: &mut Vm,
+    field: impl FnOnce(&DateFields) -> f64,
+) -> Result<f64> {
+    let mut args = Arguments::from(args);
+    let time = args.expect_optional_number()?;
+    let time = time_clip(time.unwrap_or_else(|| vm.clock().now()));
+    if time.is_nan() {
+        return Ok(f64::NAN);
+    }
+    let local = time - vm.clock().timezone_offset(time) * MS_PER_MINUTE;
+    return Ok(field(&DateFields::new(local)));
+}
+
+/// JavaScriptの`TimeClip`と同様に、範囲外の時刻をNaNにします。
+fn time_clip(time: f64) -> f64 {
+    if !time.is_finite() || time.abs() > MAX_TIME {
+        return f64::NAN;
+    }
+    return time.trunc() + 0.0;
+}
+
+fn is_truthy(value: f64) -> bool {
+    return value != 0.0 && !value.is_nan();
+}
+
+/// `String.prototype.padStart`と同様に、数値の文字列表現の先頭を0で埋めます。
+fn pad_num(value: f64, width: usize) -> String {
+    let s = num_to_string(value).to_string();
+    return format!("{}{}", "0".repeat(width.saturating_sub(s.len())), s);
+}
+
+/// 時刻を暦の各要素に分解したもの。
+/// 月は1始まりです。
+struct DateFields {
+    year: f64,
+    month: f64,
+    day: f64,
+    hour: f64,
+    minute: f64,
+    second: f64,
+    millisecond: f64,
+}
+
+impl DateFields {
+    fn new(time: f64) -> Self {
+        if time.is_nan() {
+            return DateFields {
+                year: f64::NAN,
+                month: f64::NAN,
+                day: f64::NAN,
+                hour: f64::NAN,
+                minute: f64::NAN,
+                second: f64::NAN,
+                millisecond: f64::NAN,
+            };
+        }
+        let days = (time / MS_PER_DAY).floor();
+        let time_in_day = time - days * MS_PER_DAY;
+        let (year, month, day) = civil_from_days(days as i64);
+        DateFields {
+            year: year as f64,
+            month: month as f64,
+            day: day as f64,
+            hour: (time_in_day / MS_PER_HOUR).floor(),
+            minute: (time_in_day / MS_PER_MINUTE).floor() % 60.0,
+            second: (time_in_day / MS_PER_SECOND).floor() % 60.0,
+            millisecond: time_in_day % MS_PER_SECOND,
+        }
+    }
+}
+
+/// 1970年1月1日からの日数を(年, 月, 日)に変換します。
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+/// (年, 月, 日)を1970年1月1日からの日数に変換します。
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    return era * 146097 + doe - 719468;
+}
+
+enum ParsedDate {
+    Utc(f64),
+    Local(f64),
+}
+
+/// ECMAScriptの日時文字列形式を解釈します。
+/// 日付のみの形式はUTC、オフセットのない日時の形式は地方時として扱います。
+fn parse_iso(s: &Utf16Str) -> Option<ParsedDate> {
+    let mut reader = Reader { s, pos: 0 };
+
+    let year = match reader.peek() {
+        Some(sign @ (0x2b | 0x2d)) => {
+            reader.pos += 1;
+            let year = reader.digits(6)?;
+            if sign == 0x2d {
+                if year == 0 {
+                    return None;
+                }
+                -year
+            } else {
+                year
+            }
+        }
+        _ => reader.digits(4)?,
+    };
+    let mut month = 1;
+    let mut day = 1;
+    if reader.eat(b'-') {
+        month = reader.digits(2)?;
+        if reader.eat(b'-') {
+            day = reader.digits(2)?;
+        }
+    }
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let date = days_from_civil(year, month, 1) + day - 1;
+    let date = date as f64 * MS_PER_DAY;
+
+    if reader.is_end() {
+        return Some(ParsedDate::Utc(date));
+    }
+    if !reader.eat(b'T') {
+        return None;
+    }
+    let hour = reader.digits(2)?;
+    if !reader.eat(b':') {
+        return None;
+    }
+    let minute = reader.digits(2)?;
+    let mut second = 0;
+    let mut millisecond = 0;
+    if reader.eat(b':') {
+        second = reader.digits(2)?;
+        if reader.eat(b'.') {
+            let start = reader.pos;
+            while reader.peek().is_some_and(|c| is_digit(&c)) {
+                reader.pos += 1;
+            }
+            let fraction = &s.as_u16s()[start..reader.pos];
+            if fraction.is_empty() {
+                return None;
+            }
+            // ミリ秒より細かい桁は切り捨てる
+            millisecond = fraction
+                .iter()
+                .chain(std::iter::repeat(&0x30))
+                .take(3)
+                .fold(0, |acc, &c| acc * 10 + (c - 0x30) as i64);
+        }
+    }
+    if hour > 24
+        || minute > 59
+        || second > 59
+        || (hour == 24 && (minute != 0 || second != 0 || millisecond != 0))
+    {
+        return None;
+    }
+    let time = date
+        + hour as f64 * MS_PER_HOUR
+        + minute as f64 * MS_PER_MINUTE
+        + second as f64 * MS_PER_SECOND
+        + millisecond as f64;
+
+    if reader.is_end() {
+        return Some(ParsedDate::Local(time));
+    }
+    let offset = if reader.eat(b'Z') {
+        0
+    } else {
+        let sign = match reader.peek() {
+            Some(0x2b) => 1,
+            Some(0x2d) => -1,
+            _ => return None,
+        };
+        reader.pos += 1;
+        let offset_hour = reader.digits(2)?;
+        if !reader.eat(b':') {
+            return None;
+        }
+        let offset_minute = reader.digits(2)?;
+        if offset_hour > 23 || offset_minute > 59 {
+            return None;
+        }
+        sign * (offset_hour * 60 + offset_minute)
+    };
+    if !reader.is_end() {
+        return None;
+    }
+    return Some(ParsedDate::Utc(time - offset as f64 * MS_PER_MINUTE));
+}
+
+struct Reader<'a> {
+    s: &'a Utf16Str,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<u16> {
+        return self.s.as_u16s().get(self.pos).copied();
+    }
+
+    fn is_end(&self) -> bool {
+        return self.pos >= self.s.len();
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c as u16) {
+            self.pos += 1;
+            return true;
+        }
+        return false;
+    }
+
+    /// ちょうど`len`桁の10進数を読み取ります。
+    fn digits(&mut self, len: usize) -> Option<i64> {
+        let mut value = 0;
+        for _ in 0..len {
+            let c = self.peek().filter(is_digit)?;
+            value = value * 10 + (c - 0x30) as i64;
+            self.pos += 1;
+        }
+        return Some(value);
+    }
+}
+
+fn is_digit(c: &u16) -> bool {
+    return (0x30..=0x39).contains(c);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Option<f64> {
+        match parse_iso(&Utf16String::from(s)) {
+            Some(ParsedDate::Utc(time)) => Some(time),
+            Some(ParsedDate::Local(time)) => Some(time),
+            None => None,
+        }
+    }
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        for days in [-1000000, -719468, -1, 0, 59, 60, 11016, 2932896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_parse_iso() {
+        assert_eq!(parse("1970"), Some(0.0));
+        assert_eq!(parse("2024-02-29"), Some(1709164800000.0));
+        assert_eq!(parse("2024-02-29T12:34:56.789Z"), Some(1709210096789.0));
+        assert_eq!(parse("2024-02-29T12:34:56.7891Z"), Some(1709210096789.0));
+        assert_eq!(parse("2024-02-29T21:34+09:00"), Some(1709210040000.0));
+        assert_eq!(parse("+002024-02"), Some(1706745600000.0));
+        assert_eq!(parse("1970-01-01T24:00"), Some(MS_PER_DAY));
+        assert_eq!(parse("-000000"), None);
+        assert_eq!(parse("2024-13-01"), None);
+        assert_eq!(parse("2024-01-01T24:01"), None);
+        assert_eq!(parse("2024-01-01T12"), None);
+        assert_eq!(parse("2024-01-01 "), None);
+        assert_eq!(parse("hoge"), None);
+    }
+}
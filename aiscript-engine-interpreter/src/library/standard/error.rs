@@ -0,0 +1,11 @@
+use aiscript_engine_common::{Result, Utf16Str};
+use aiscript_engine_values::Value;
+
+use crate::{arguments::Arguments, vm::Vm};
+
+pub(super) fn create(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let name = args.expect_string()?;
+    let info = args.expect_optional_any();
+    Ok(Value::error(Utf16Str::new(&name), info))
+}
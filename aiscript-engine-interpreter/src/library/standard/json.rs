@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result, Utf16Str};
+use aiscript_engine_values::{json_to_value, stringify as stringify_value, Value};
+
+use crate::{arguments::Arguments, vm::Vm};
+
+pub(super) fn stringify(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let value = args.expect_any()?;
+    let json = match stringify_value(&value) {
+        Ok(json) => json,
+        Err(error) => {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                error.to_string(),
+                None,
+            )));
+        }
+    };
+    Ok(Value::Str(Rc::from(json.as_u16s())))
+}
+
+pub(super) fn parse(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let json = args.expect_string()?;
+    match parse_json(&json) {
+        Some(json) => Ok(json_to_value(&json)),
+        None => Ok(Value::error("not_json", None)),
+    }
+}
+
+pub(super) fn parsable(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let json = args.expect_string()?;
+    Ok(Value::Bool(parse_json(&json).is_some()))
+}
+
+fn parse_json(json: &[u16]) -> Option<serde_json::Value> {
+    serde_json::from_str(&Utf16Str::new(json).to_string()).ok()
+}
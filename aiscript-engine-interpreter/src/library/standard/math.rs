@@ -0,0 +1,246 @@
+use std::cell::RefCell;
+
+use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result};
+use aiscript_engine_values::{require_number, Value};
+use utf16_literal::utf16;
+
+use super::seeded_rng::{Algorithm, Seed, SeededRng};
+use crate::{arguments::Arguments, vm, vm::Vm};
+
+/// 1引数の数学関数を定義する。
+macro_rules! unary {
+    ($($name: ident => $f: expr),* $(,)?) => {
+        $(
+            pub(super) fn $name(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+                let mut args = Arguments::from(args);
+                let x = args.expect_number()?;
+                let f: fn(f64) -> f64 = $f;
+                Ok(Value::Num(f(x)))
+            }
+        )*
+    };
+}
+
+unary! {
+    abs => f64::abs,
+    acos => f64::acos,
+    acosh => f64::acosh,
+    asin => f64::asin,
+    asinh => f64::asinh,
+    atan => f64::atan,
+    atanh => f64::atanh,
+    cbrt => f64::cbrt,
+    ceil => f64::ceil,
+    clz32 => |x| to_uint32(x).leading_zeros() as f64,
+    cos => f64::cos,
+    cosh => f64::cosh,
+    exp => f64::exp,
+    expm1 => f64::exp_m1,
+    floor => f64::floor,
+    fround => |x| x as f32 as f64,
+    log => f64::ln,
+    log1p => f64::ln_1p,
+    log10 => f64::log10,
+    log2 => f64::log2,
+    round => round_js,
+    sign => sign_js,
+    sin => f64::sin,
+    sinh => f64::sinh,
+    sqrt => f64::sqrt,
+    tan => f64::tan,
+    tanh => f64::tanh,
+    trunc => f64::trunc,
+}
+
+pub(super) fn atan2(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let y = args.expect_number()?;
+    let x = args.expect_number()?;
+    Ok(Value::Num(y.atan2(x)))
+}
+
+pub(super) fn hypot(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let values = args.expect_array()?;
+    let mut result = 0.0_f64;
+    let mut has_nan = false;
+    for value in values.borrow().iter() {
+        let value = require_number(value)?;
+        if value.is_infinite() {
+            return Ok(Value::Num(f64::INFINITY));
+        }
+        has_nan |= value.is_nan();
+        result = result.hypot(value);
+    }
+    Ok(Value::Num(if has_nan { f64::NAN } else { result }))
+}
+
+pub(super) fn imul(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let x = args.expect_number()?;
+    let y = args.expect_number()?;
+    Ok(Value::Num(to_int32(x).wrapping_mul(to_int32(y)) as f64))
+}
+
+pub(super) fn max(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let a = args.expect_number()?;
+    let b = args.expect_number()?;
+    let result = if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        // +0と-0では+0を優先する
+        if a.is_sign_negative() {
+            b
+        } else {
+            a
+        }
+    } else {
+        a.max(b)
+    };
+    Ok(Value::Num(result))
+}
+
+pub(super) fn min(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let a = args.expect_number()?;
+    let b = args.expect_number()?;
+    let result = if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        // +0と-0では-0を優先する
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    };
+    Ok(Value::Num(result))
+}
+
+pub(super) fn pow(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let x = args.expect_number()?;
+    let y = args.expect_number()?;
+    Ok(Value::Num(vm::pow(x, y)))
+}
+
+pub(super) fn rnd(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let value = vm.random_source().next_f64();
+    Ok(Value::Num(random_in_range(value, &args)))
+}
+
+/// シードと`{ algorithm }`の形式のオプションから乱数生成器を作成します。
+/// アルゴリズムは`chacha20`(既定)、`rc4`、`rc4_legacy`から選べます。
+pub(super) fn gen_rng(args: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let seed = args.expect_any()?;
+    let algorithm = match args.expect_optional_any() {
+        None => Algorithm::ChaCha20,
+        Some(Value::Obj(options)) => {
+            let name = options.borrow().0.get(&utf16!("algorithm")[..]).cloned();
+            let Some(Value::Str(name)) = name else {
+                return Err(runtime_error("`options.algorithm` must be string."));
+            };
+            match Algorithm::from_name(&name) {
+                Some(algorithm) => algorithm,
+                None => {
+                    return Err(runtime_error(
+                        "`options.algorithm` must be one of these: `chacha20`, `rc4`, or `rc4_legacy`.",
+                    ));
+                }
+            }
+        }
+        Some(_) => return Err(runtime_error("`options` must be an object if specified.")),
+    };
+    let seed = match &seed {
+        Value::Num(value) => Seed::Num(*value),
+        Value::Str(value) => Seed::Str(value),
+        _ => return Ok(Value::Null),
+    };
+    let rng = RefCell::new(SeededRng::new(algorithm, seed));
+    Ok(vm.generate_native_fn(move |args, _| {
+        let mut rng = rng.borrow_mut();
+        if let [Value::Num(min), Value::Num(max), ..] = args[..] {
+            return Ok(rng.next_integer(min, max).map_or(Value::Null, Value::Num));
+        }
+        Ok(Value::Num(rng.next_number()))
+    }))
+}
+
+fn runtime_error(message: &'static str) -> Box<AiScriptBasicError> {
+    return Box::new(AiScriptBasicError::new(
+        AiScriptBasicErrorKind::Runtime,
+        message,
+        None,
+    ));
+}
+
+/// 引数に最小値と最大値が指定されていれば、その範囲の整数に変換します。
+fn random_in_range(value: f64, args: &[Value]) -> f64 {
+    if let [Value::Num(min), Value::Num(max), ..] = args {
+        let min = min.ceil();
+        let max = max.floor();
+        return (value * (max - min + 1.0) + min).floor();
+    }
+    return value;
+}
+
+/// ECMAScriptのToUint32
+fn to_uint32(x: f64) -> u32 {
+    if !x.is_finite() {
+        return 0;
+    }
+    return x.trunc().rem_euclid(4294967296.0) as u32;
+}
+
+/// ECMAScriptのToInt32
+fn to_int32(x: f64) -> i32 {
+    to_uint32(x) as i32
+}
+
+/// `Math.round`と同様に、0.5は正の無限大の方向に丸めます。
+fn round_js(x: f64) -> f64 {
+    if !x.is_finite() {
+        return x;
+    }
+    let floor = x.floor();
+    let result = if x - floor >= 0.5 { floor + 1.0 } else { floor };
+    // -0.5以上0未満は-0になる
+    if result == 0.0 && x.is_sign_negative() {
+        return -0.0;
+    }
+    return result;
+}
+
+/// `Math.sign`と同様に、NaNと符号付きの0はそのまま返します。
+fn sign_js(x: f64) -> f64 {
+    if x.is_nan() || x == 0.0 {
+        return x;
+    }
+    return x.signum();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round() {
+        assert_eq!(round_js(2.5), 3.0);
+        assert_eq!(round_js(-2.5), -2.0);
+        assert_eq!(round_js(0.49999999999999994), 0.0);
+        assert!(round_js(-0.4).is_sign_negative());
+        assert!(round_js(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn int32() {
+        assert_eq!(to_int32(4294967295.0), -1);
+        assert_eq!(to_int32(-1.5), -1);
+        assert_eq!(to_uint32(-1.0), 4294967295);
+        assert_eq!(to_uint32(f64::NAN), 0);
+    }
+}
@@ -0,0 +1,18 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::{parse_int, to_radix_string, Result, Utf16Str};
+use aiscript_engine_values::Value;
+
+use crate::{arguments::Arguments, vm::Vm};
+
+pub(super) fn to_hex(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let value = args.expect_number()?;
+    Ok(Value::Str(Rc::from(to_radix_string(value, 16).as_u16s())))
+}
+
+pub(super) fn from_hex(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let value = args.expect_string()?;
+    Ok(Value::Num(parse_int(Utf16Str::new(&value), 16)))
+}
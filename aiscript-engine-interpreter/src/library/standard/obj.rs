@@ -0,0 +1,99 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::Result;
+use aiscript_engine_values::{require_string, VObj, Value};
+use gc::{Gc, GcCell};
+
+use crate::{arguments::Arguments, vm::Vm};
+
+pub(super) fn keys(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let keys = obj
+        .borrow()
+        .0
+        .keys()
+        .map(|key| Value::Str(Rc::clone(key)))
+        .collect();
+    Ok(Value::Arr(Gc::new(GcCell::new(keys))))
+}
+
+pub(super) fn vals(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let vals = obj.borrow().0.values().cloned().collect();
+    Ok(Value::Arr(Gc::new(GcCell::new(vals))))
+}
+
+pub(super) fn kvs(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let kvs = obj
+        .borrow()
+        .0
+        .iter()
+        .map(|(key, value)| {
+            let kv = vec![Value::Str(Rc::clone(key)), value.clone()];
+            Value::Arr(Gc::new(GcCell::new(kv)))
+        })
+        .collect();
+    Ok(Value::Arr(Gc::new(GcCell::new(kvs))))
+}
+
+pub(super) fn get(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let key = args.expect_string()?;
+    let value = obj.borrow().0.get(&key).cloned();
+    Ok(value.unwrap_or(Value::Null))
+}
+
+pub(super) fn set(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let key = args.expect_string()?;
+    let value = args.expect_any()?;
+    obj.borrow_mut().0.insert(key, value);
+    Ok(Value::Null)
+}
+
+pub(super) fn has(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let key = args.expect_string()?;
+    let result = obj.borrow().0.contains_key(&key);
+    Ok(Value::Bool(result))
+}
+
+pub(super) fn copy(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let result = obj.borrow().clone();
+    Ok(Value::Obj(Gc::new(GcCell::new(result))))
+}
+
+pub(super) fn merge(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let a = args.expect_object()?;
+    let b = args.expect_object()?;
+    // 重複するキーは`a`の位置のまま`b`の値で上書きする
+    let mut result = a.borrow().clone();
+    for (key, value) in b.borrow().0.iter() {
+        result.0.insert(Rc::clone(key), value.clone());
+    }
+    Ok(Value::Obj(Gc::new(GcCell::new(result))))
+}
+
+pub(super) fn pick(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let obj = args.expect_object()?;
+    let keys = args.expect_array()?;
+    let obj = obj.borrow();
+    let mut result = VObj::new();
+    for key in keys.borrow().iter() {
+        let key = require_string(key)?;
+        let value = obj.0.get(&key).cloned().unwrap_or(Value::Null);
+        result.0.insert(key, value);
+    }
+    Ok(Value::Obj(Gc::new(GcCell::new(result))))
+}
@@ -0,0 +1,167 @@
+//! `Math:gen_rng`が返す乱数生成器
+
+use aiscript_engine_common::{num_to_string, Utf16String};
+use sha2::{Digest, Sha384};
+
+use super::chacha20::ChaCha20;
+use super::seedrandom::SeedRandom;
+
+/// 2^53
+const SAFE_INTEGER_LIMIT: f64 = 9007199254740992.0;
+
+/// 乱数生成器のアルゴリズム
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    /// シードのSHA-384を鍵とするChaCha20
+    ChaCha20,
+
+    /// シード文字列を鍵とするARC4
+    Rc4,
+
+    /// AiScript 0.x互換。seedrandomの`rng()`と同じ数列を返す
+    Rc4Legacy,
+}
+
+impl Algorithm {
+    /// `options.algorithm`の名前からアルゴリズムを返します。
+    pub(crate) fn from_name(name: &[u16]) -> Option<Self> {
+        return match String::from_utf16_lossy(name).as_str() {
+            "chacha20" => Some(Algorithm::ChaCha20),
+            "rc4" => Some(Algorithm::Rc4),
+            "rc4_legacy" => Some(Algorithm::Rc4Legacy),
+            _ => None,
+        };
+    }
+}
+
+/// シード
+pub(crate) enum Seed<'a> {
+    Num(f64),
+    Str(&'a [u16]),
+}
+
+impl Seed<'_> {
+    /// ARC4の鍵にする文字列での表現を返します。
+    fn to_utf16_string(&self) -> Utf16String {
+        match self {
+            Seed::Num(value) => num_to_string(*value),
+            Seed::Str(value) => Utf16String::from(*value),
+        }
+    }
+}
+
+pub(crate) enum SeededRng {
+    ChaCha20(ChaCha20),
+    Rc4(SeedRandom),
+    Rc4Legacy(SeedRandom),
+}
+
+impl SeededRng {
+    pub(crate) fn new(algorithm: Algorithm, seed: Seed) -> Self {
+        match algorithm {
+            Algorithm::ChaCha20 => {
+                let bytes = match seed {
+                    Seed::Num(value) => value.to_le_bytes().to_vec(),
+                    Seed::Str(value) => String::from_utf16_lossy(value).into_bytes(),
+                };
+                return SeededRng::ChaCha20(ChaCha20::new(&Sha384::digest(bytes)));
+            }
+            Algorithm::Rc4 => {
+                return SeededRng::Rc4(SeedRandom::new(seed.to_utf16_string().as_u16s()));
+            }
+            Algorithm::Rc4Legacy => {
+                return SeededRng::Rc4Legacy(SeedRandom::new(seed.to_utf16_string().as_u16s()));
+            }
+        }
+    }
+
+    /// 0以上1未満の数を返します。
+    pub(crate) fn next_number(&mut self) -> f64 {
+        if let SeededRng::Rc4Legacy(rng) = self {
+            return rng.next();
+        }
+        return self.next_bits(53) as f64 / SAFE_INTEGER_LIMIT;
+    }
+
+    /// `min`以上`max`以下の整数を返します。
+    /// `min`と`max`は整数に丸められ、`min`が`max`より大きくても構いません。
+    /// 範囲を安全な整数で表現できない場合は`None`を返します。
+    pub(crate) fn next_integer(&mut self, min: f64, max: f64) -> Option<f64> {
+        let min = min.ceil();
+        let max = max.floor();
+        if let SeededRng::Rc4Legacy(rng) = self {
+            return Some((rng.next() * (max - min + 1.0) + min).floor());
+        }
+        let signed_scale = max - min;
+        if signed_scale == 0.0 {
+            return Some(min);
+        }
+        let scale = signed_scale.abs();
+        if ![scale, min, max]
+            .iter()
+            .all(|x| x.is_finite() && x.abs() < SAFE_INTEGER_LIMIT)
+        {
+            return None;
+        }
+        // 範囲を超えた値は捨てて偏りをなくす
+        let scale = scale as u64;
+        let bits = u64::BITS - scale.leading_zeros();
+        let value = loop {
+            let value = self.next_bits(bits);
+            if value <= scale {
+                break value;
+            }
+        };
+        return Some(value as f64 * signed_scale.signum() + min);
+    }
+
+    /// リトルエンディアンで読んだ乱数のバイト列の下位`bits`ビットを返します。
+    fn next_bits(&mut self, bits: u32) -> u64 {
+        let mut value = 0;
+        for i in 0..bits.div_ceil(8) {
+            value |= (self.next_byte() as u64) << (i * 8);
+        }
+        return value & (u64::MAX >> (u64::BITS - bits));
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        match self {
+            SeededRng::ChaCha20(rng) => rng.next_byte(),
+            SeededRng::Rc4(rng) | SeededRng::Rc4Legacy(rng) => rng.next_byte(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rng(algorithm: Algorithm, seed: &str) -> SeededRng {
+        let seed = Utf16String::from(seed);
+        return SeededRng::new(algorithm, Seed::Str(seed.as_u16s()));
+    }
+
+    #[test]
+    fn legacy() {
+        let mut rng = rng(Algorithm::Rc4Legacy, "hello.");
+        assert_eq!(rng.next_number(), 0.9282578795792454);
+    }
+
+    #[test]
+    fn integer_in_range() {
+        for algorithm in [Algorithm::ChaCha20, Algorithm::Rc4] {
+            let mut rng = rng(algorithm, "seed");
+            for _ in 0..100 {
+                let value = rng.next_integer(-1.5, 3.5).unwrap();
+                assert!([-1.0, 0.0, 1.0, 2.0, 3.0].contains(&value));
+                let value = rng.next_integer(3.0, -1.0).unwrap();
+                assert!([-1.0, 0.0, 1.0, 2.0, 3.0].contains(&value));
+                let value = rng.next_number();
+                assert!((0.0..1.0).contains(&value));
+            }
+            assert_eq!(rng.next_integer(2.0, 2.0), Some(2.0));
+            assert_eq!(rng.next_integer(0.0, SAFE_INTEGER_LIMIT), None);
+            assert_eq!(rng.next_integer(0.0, f64::NAN), None);
+        }
+    }
+}
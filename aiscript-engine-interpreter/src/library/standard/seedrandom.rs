@@ -0,0 +1,143 @@
+//! [seedrandom](https://github.com/davidbau/seedrandom)のARC4ベースの乱数生成器
+
+const WIDTH: f64 = 256.0;
+const CHUNKS: usize = 6;
+const MASK: usize = 255;
+
+/// 2^48
+const START_DENOM: f64 = 281474976710656.0;
+
+/// 2^52
+const SIGNIFICANCE: f64 = 4503599627370496.0;
+
+/// 2^53
+const OVERFLOW: f64 = 9007199254740992.0;
+
+/// 文字列のシードから決定的に乱数を生成する。
+/// 同じシードからはJavaScript版の`seedrandom(seed)`と同じ数列が得られる。
+pub(crate) struct SeedRandom {
+    arc4: Arc4,
+}
+
+impl SeedRandom {
+    pub(crate) fn new(seed: &[u16]) -> Self {
+        return SeedRandom {
+            arc4: Arc4::new(&mix_key(seed)),
+        };
+    }
+
+    /// 鍵ストリームの次のバイトを返します。
+    pub(crate) fn next_byte(&mut self) -> u8 {
+        return self.arc4.next_byte() as u8;
+    }
+
+    /// 0以上1未満の数を返します。
+    pub(crate) fn next(&mut self) -> f64 {
+        let mut n = self.arc4.g(CHUNKS);
+        let mut d = START_DENOM;
+        let mut x = 0.0;
+        while n < SIGNIFICANCE {
+            n = (n + x) * WIDTH;
+            d *= WIDTH;
+            x = self.arc4.g(1);
+        }
+        while n >= OVERFLOW {
+            n /= 2.0;
+            d /= 2.0;
+            x = ((x as u32) >> 1) as f64;
+        }
+        return (n + x) / d;
+    }
+}
+
+/// シード文字列を鍵に変換します。
+fn mix_key(seed: &[u16]) -> Vec<usize> {
+    let mut key: Vec<usize> = Vec::new();
+    let mut smear = 0;
+    for (j, char) in seed.iter().enumerate() {
+        let index = MASK & j;
+        if index == key.len() {
+            key.push(0);
+        }
+        smear ^= key[index] * 19;
+        key[index] = MASK & (smear + *char as usize);
+    }
+    return key;
+}
+
+struct Arc4 {
+    i: usize,
+    j: usize,
+    s: [usize; 256],
+}
+
+impl Arc4 {
+    fn new(key: &[usize]) -> Self {
+        // 空の鍵は[0]として扱う
+        let key = if key.is_empty() { &[0][..] } else { key };
+        let mut s = [0; 256];
+        for (i, s) in s.iter_mut().enumerate() {
+            *s = i;
+        }
+        let mut j = 0;
+        for i in 0..256 {
+            let t = s[i];
+            j = MASK & (j + key[i % key.len()] + t);
+            s[i] = s[j];
+            s[j] = t;
+        }
+        let mut arc4 = Arc4 { i: 0, j: 0, s };
+        // RC4-drop[256]
+        for _ in 0..256 {
+            arc4.next_byte();
+        }
+        return arc4;
+    }
+
+    fn next_byte(&mut self) -> usize {
+        self.i = MASK & (self.i + 1);
+        let t = self.s[self.i];
+        self.j = MASK & (self.j + t);
+        self.s[self.i] = self.s[self.j];
+        self.s[self.j] = t;
+        return self.s[MASK & (self.s[self.i] + t)];
+    }
+
+    /// `count`バイトの出力を1つの数として返します。
+    fn g(&mut self, count: usize) -> f64 {
+        let mut r = 0.0;
+        for _ in 0..count {
+            r = r * WIDTH + self.next_byte() as f64;
+        }
+        return r;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aiscript_engine_common::Utf16String;
+
+    use super::*;
+
+    #[test]
+    fn same_as_javascript() {
+        let mut rng = SeedRandom::new(Utf16String::from("hello.").as_u16s());
+        assert_eq!(rng.next(), 0.9282578795792454);
+    }
+
+    #[test]
+    fn same_seed() {
+        let seed = Utf16String::from("seed");
+        let mut rng1 = SeedRandom::new(seed.as_u16s());
+        let mut rng2 = SeedRandom::new(seed.as_u16s());
+        for _ in 0..100 {
+            assert_eq!(rng1.next(), rng2.next());
+        }
+    }
+
+    #[test]
+    fn empty_seed() {
+        let value = SeedRandom::new(&[]).next();
+        assert!((0.0..1.0).contains(&value));
+    }
+}
@@ -0,0 +1,81 @@
+use std::{cmp::Ordering, rc::Rc};
+
+use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result, Utf16String};
+use aiscript_engine_values::{require_number, Value};
+
+use crate::{arguments::Arguments, vm::Vm};
+
+pub(super) fn lt(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let a = args.expect_string()?;
+    let b = args.expect_string()?;
+    Ok(Value::Num(compare(a.cmp(&b))))
+}
+
+pub(super) fn gt(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let a = args.expect_string()?;
+    let b = args.expect_string()?;
+    Ok(Value::Num(compare(b.cmp(&a))))
+}
+
+pub(super) fn from_codepoint(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let code_point = args.expect_number()?;
+    let mut result = Utf16String::new();
+    push_code_point(&mut result, code_point)?;
+    Ok(Value::Str(Rc::from(result.as_u16s())))
+}
+
+pub(super) fn from_unicode_codepoints(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let code_points = args.expect_array()?;
+    let mut result = Utf16String::new();
+    for code_point in code_points.borrow().iter() {
+        push_code_point(&mut result, require_number(code_point)?)?;
+    }
+    Ok(Value::Str(Rc::from(result.as_u16s())))
+}
+
+pub(super) fn from_utf8_bytes(args: Vec<Value>, _: &mut Vm) -> Result<Value> {
+    let mut args = Arguments::from(args);
+    let bytes = args.expect_array()?;
+    let bytes = bytes
+        .borrow()
+        .iter()
+        .map(|byte| require_number(byte).map(to_uint8))
+        .collect::<Result<Vec<u8>>>()?;
+    // 不正なバイト列はU+FFFDに置き換える
+    let result = Utf16String::from(String::from_utf8_lossy(&bytes).as_ref());
+    Ok(Value::Str(Rc::from(result.as_u16s())))
+}
+
+/// 比較結果を-1, 0, 1のいずれかで表します。
+fn compare(ordering: Ordering) -> f64 {
+    match ordering {
+        Ordering::Less => -1.0,
+        Ordering::Equal => 0.0,
+        Ordering::Greater => 1.0,
+    }
+}
+
+/// `String.fromCodePoint`と同様に、整数でない値や範囲外の値はエラーとします。
+fn push_code_point(result: &mut Utf16String, code_point: f64) -> Result<()> {
+    if code_point.fract() != 0.0 || !(0.0..=1114111.0).contains(&code_point) {
+        return Err(Box::new(AiScriptBasicError::new(
+            AiScriptBasicErrorKind::Runtime,
+            format!("Invalid code point: {}", code_point),
+            None,
+        )));
+    }
+    result.push_code_point(code_point as u32);
+    return Ok(());
+}
+
+/// `Uint8Array`への代入と同様に、256を法とする整数に変換します。
+fn to_uint8(value: f64) -> u8 {
+    if !value.is_finite() {
+        return 0;
+    }
+    return value.trunc().rem_euclid(256.0) as u8;
+}
@@ -0,0 +1,46 @@
+use std::rc::Rc;
+
+use aiscript_engine_common::{Result, Utf16String};
+use aiscript_engine_values::Value;
+
+use crate::vm::Vm;
+
+pub(super) fn uuid(_: Vec<Value>, vm: &mut Vm) -> Result<Value> {
+    let random = vm.random_source();
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&random.next_u64().to_le_bytes());
+    bytes[8..].copy_from_slice(&random.next_u64().to_le_bytes());
+    Ok(Value::Str(Rc::from(format_uuid_v4(bytes).as_u16s())))
+}
+
+/// 乱数のバイト列からUUIDv4の文字列を作成します。
+fn format_uuid_v4(mut bytes: [u8; 16]) -> Utf16String {
+    // バージョン4、バリアント10xx
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let mut result = String::with_capacity(36);
+    for (index, byte) in bytes.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            result.push('-');
+        }
+        result.push_str(&format!("{:02x}", byte));
+    }
+    return Utf16String::from(result.as_str());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuid_v4_format() {
+        assert_eq!(
+            format_uuid_v4([0; 16]).to_string(),
+            "00000000-0000-4000-8000-000000000000"
+        );
+        assert_eq!(
+            format_uuid_v4([0xff; 16]).to_string(),
+            "ffffffff-ffff-4fff-bfff-ffffffffffff"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// `Math:rnd`や`Util:uuid`が使う乱数源。
+/// テストなどで結果を固定したい場合は、独自の実装を`InterpreterOpts::random_source`で返します。
+pub trait RandomSource {
+    /// 一様に分布する64ビットの乱数を返します。
+    fn next_u64(&self) -> u64;
+
+    /// 0以上1未満の乱数を返します。
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// スレッドごとの状態を持つxorshift64*による乱数源。
+/// シードは実行ごとに異なります。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultRandomSource;
+
+impl RandomSource for DefaultRandomSource {
+    fn next_u64(&self) -> u64 {
+        RANDOM_STATE.with(|state| {
+            let mut x = state.get();
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            state.set(x);
+            return x.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        })
+    }
+}
+
+thread_local! {
+    static RANDOM_STATE: Cell<u64> = Cell::new(random_seed());
+}
+
+/// 実行ごとに異なる乱数のシードを生成します。
+fn random_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(std::process::id() as u64);
+    // 0はxorshiftの不動点になるため避ける
+    return hasher.finish() | 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_range() {
+        for _ in 0..100 {
+            let value = DefaultRandomSource.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
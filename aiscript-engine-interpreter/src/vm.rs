@@ -1,6 +1,9 @@
+mod timer;
 mod utils;
+#[allow(clippy::module_inception)]
 mod vm;
 
 pub(crate) use aiscript_engine_values::*;
 
-pub(crate) use vm::*;
+pub(crate) use utils::pow;
+pub use vm::Vm;
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use aiscript_engine_values::VFn;
+use gc::{Gc, GcCell};
+
+/// `Async:timeout`や`Async:interval`で登録されたタイマー
+struct Timer {
+    /// 次に実行する時刻 (UNIXエポックからのミリ秒)
+    due: f64,
+
+    /// 繰り返しの間隔。一度だけ実行する場合は`None`
+    interval: Option<f64>,
+
+    callback: Gc<GcCell<VFn>>,
+}
+
+/// 実行待ちのタイマーの一覧。
+/// 時刻の進行はホストが`Interpreter::tick`で与えます。
+#[derive(Default)]
+pub(crate) struct TimerQueue {
+    timers: BTreeMap<u64, Timer>,
+    next_id: u64,
+}
+
+impl TimerQueue {
+    pub(crate) fn new() -> Self {
+        TimerQueue {
+            timers: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// タイマーを登録し、取り消しに使うIDを返します。
+    pub(crate) fn schedule(
+        &mut self,
+        due: f64,
+        interval: Option<f64>,
+        callback: Gc<GcCell<VFn>>,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.insert(
+            id,
+            Timer {
+                due,
+                interval,
+                callback,
+            },
+        );
+        return id;
+    }
+
+    pub(crate) fn cancel(&mut self, id: u64) {
+        self.timers.remove(&id);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    /// 最も早く実行されるタイマーの時刻を返します。
+    pub(crate) fn next_due(&self) -> Option<f64> {
+        return self.timers.values().map(|timer| timer.due).reduce(f64::min);
+    }
+
+    /// 時刻`now`までに実行すべきタイマーのIDを、実行する順に返します。
+    pub(crate) fn due_ids(&self, now: f64) -> Vec<u64> {
+        let mut ids: Vec<(f64, u64)> = self
+            .timers
+            .iter()
+            .filter(|(_, timer)| timer.due <= now)
+            .map(|(id, timer)| (timer.due, *id))
+            .collect();
+        ids.sort_by(|(a, a_id), (b, b_id)| a.total_cmp(b).then(a_id.cmp(b_id)));
+        return ids.into_iter().map(|(_, id)| id).collect();
+    }
+
+    /// タイマーを実行済みにし、そのコールバックを返します。
+    /// 繰り返しのタイマーは`now`から間隔をあけて再登録します。
+    /// 既に取り消されている場合は`None`を返します。
+    pub(crate) fn fire(&mut self, id: u64, now: f64) -> Option<Gc<GcCell<VFn>>> {
+        let timer = self.timers.get_mut(&id)?;
+        let callback = Gc::clone(&timer.callback);
+        match timer.interval {
+            Some(interval) => timer.due = now + interval,
+            None => {
+                self.timers.remove(&id);
+            }
+        }
+        return Some(callback);
+    }
+}
@@ -1,7 +1,3 @@
-use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result};
-use aiscript_engine_values::{VArr, VFn, VObj, Value};
-use gc::{Gc, GcCell};
-
 pub(crate) trait GetByF64<T> {
     fn get_by_f64(&self, index: f64) -> Option<&T>;
 
@@ -11,7 +7,7 @@ pub(crate) trait GetByF64<T> {
 impl<T> GetByF64<T> for [T] {
     fn get_by_f64(&self, index: f64) -> Option<&T> {
         let index_int = index as usize;
-        if index == index as f64 {
+        if index == index_int as f64 {
             self.get(index_int)
         } else {
             None
@@ -20,10 +16,18 @@ impl<T> GetByF64<T> for [T] {
 
     fn get_mut_by_f64(&mut self, index: f64) -> Option<&mut T> {
         let index_int = index as usize;
-        if index == index as f64 {
+        if index == index_int as f64 {
             self.get_mut(index_int)
         } else {
             None
         }
     }
 }
+
+/// ECMAScriptの`**`演算子と同じ規則で累乗を計算します。
+pub(crate) fn pow(base: f64, exponent: f64) -> f64 {
+    if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        return f64::NAN;
+    }
+    return base.powf(exponent);
+}
@@ -1,27 +1,53 @@
 use std::{
-    ops::{Index, IndexMut},
+    fmt::Display,
+    ops::{ControlFlow, Index, IndexMut, Range},
     rc::Rc,
 };
 
-use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result};
+use aiscript_engine_common::{
+    AiScriptBasicError, AiScriptBasicErrorKind, Position, Result, StackFrame, StackGuard,
+    Utf16String,
+};
 use aiscript_engine_values::{
-    require_array, require_boolean, require_function, require_number, require_object, FnIndex, VFn,
-    Value,
+    repr_value, require_any, require_array, require_boolean, require_function, require_number,
+    require_object, require_string, FnIndex, VFn, Value,
 };
 use gc::{Gc, GcCell};
 
-use super::utils::GetByF64;
-use crate::ir::{Instruction, Register, UserFn};
-use crate::library::NativeFn;
+use super::timer::TimerQueue;
+use super::utils::{pow, GetByF64};
+use crate::abort::AbortHandle;
+use crate::clock::{Clock, SystemClock};
+use crate::ir::{Block, Instruction, NativeFnIndex, Register, UserFn, UserFnIndex};
+use crate::library::{
+    bound_functions, get_prim_prop, primitive_methods, BoundFn, NativeFn, PRIMITIVE_METHOD_COUNT,
+};
+use crate::random::{DefaultRandomSource, RandomSource};
+
+/// 命令列の実行を中断させる制御文
+enum Control {
+    /// 値を返して関数を終了する
+    Return(Value),
+
+    /// 指定した数だけ外側のループを終了する
+    Break(usize),
+
+    /// 指定した数だけ外側のループの次の繰り返しに移る
+    Continue(usize),
+}
 
 struct Registers {
     registers: Vec<Value>,
+    cells: Vec<Gc<GcCell<Value>>>,
 }
 
 impl Registers {
-    fn new(len: usize) -> Self {
+    fn new(len: usize, cell_len: usize) -> Self {
         Registers {
             registers: vec![Value::Uninitialized; len],
+            cells: (0..cell_len)
+                .map(|_| Gc::new(GcCell::new(Value::Uninitialized)))
+                .collect(),
         }
     }
 }
@@ -40,40 +66,435 @@ impl IndexMut<Register> for Registers {
     }
 }
 
-pub(crate) struct Vm {
-    native_functions: Vec<NativeFn>,
+/// 生成したネイティブ関数の破棄を試みる最小の数。
+const MIN_GENERATED_NATIVE_LIMIT: usize = 64;
+
+/// ユーザー関数の呼び出しの深さの上限。
+/// ホストのスタックオーバーフローを防ぐために設けています。
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// 仮想マシン。
+/// ネイティブ関数から呼び出し元の実行環境を参照するために渡されます。
+pub struct Vm {
+    /// 登録したネイティブ関数。破棄した関数の位置は`None`
+    native_functions: Vec<Option<NativeFn>>,
+
+    /// 実行中に生成したネイティブ関数の番号と、その関数の値が共有する引数名。
+    /// 引数名の参照数から関数の値が残っているかを判断します。
+    generated_natives: Vec<(NativeFnIndex, Rc<[Utf16String]>)>,
+
+    /// 生成したネイティブ関数がこの数に達したら、値が残っていない関数を破棄する
+    generated_native_limit: usize,
+
+    /// 登録したユーザー関数。破棄した関数の位置は`None`
+    user_functions: Vec<Option<Rc<UserFn>>>,
+    globals: Vec<Value>,
+    call_depth: usize,
+
+    /// 実行中の関数の呼び出し履歴。外側の関数から順に並ぶ
+    call_stack: Vec<StackFrame>,
+
+    /// 実行できる命令数の上限
+    max_step: Option<usize>,
+
+    /// 実行した命令数
+    step_count: usize,
+
+    abort_handle: AbortHandle,
+
+    clock: Rc<dyn Clock>,
+
+    random_source: Rc<dyn RandomSource>,
+
+    timers: TimerQueue,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Vm {
     pub(crate) fn new() -> Self {
         Vm {
-            native_functions: Vec::new(),
+            native_functions: primitive_methods()
+                .chain(bound_functions())
+                .map(|function| Some(NativeFn::Static(function)))
+                .collect(),
+            generated_natives: Vec::new(),
+            generated_native_limit: MIN_GENERATED_NATIVE_LIMIT,
+            user_functions: Vec::new(),
+            globals: Vec::new(),
+            call_depth: 0,
+            call_stack: Vec::new(),
+            max_step: None,
+            step_count: 0,
+            abort_handle: AbortHandle::new(),
+            clock: Rc::new(SystemClock),
+            random_source: Rc::new(DefaultRandomSource),
+            timers: TimerQueue::new(),
+        }
+    }
+
+    pub(crate) fn set_max_step(&mut self, max_step: Option<usize>) {
+        self.max_step = max_step;
+    }
+
+    pub(crate) fn set_abort_handle(&mut self, abort_handle: AbortHandle) {
+        self.abort_handle = abort_handle;
+    }
+
+    pub(crate) fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// ネイティブ関数から参照する時計
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        return self.clock.as_ref();
+    }
+
+    pub(crate) fn set_random_source(&mut self, random_source: Rc<dyn RandomSource>) {
+        self.random_source = random_source;
+    }
+
+    /// ネイティブ関数から参照する乱数源
+    pub(crate) fn random_source(&self) -> &dyn RandomSource {
+        return self.random_source.as_ref();
+    }
+
+    /// 実行待ちのタイマー
+    pub(crate) fn timers(&mut self) -> &mut TimerQueue {
+        return &mut self.timers;
+    }
+
+    pub(crate) fn next_timer(&self) -> Option<f64> {
+        return self.timers.next_due();
+    }
+
+    /// 時刻`now`までに実行すべきタイマーのコールバックを呼び出します。
+    /// 停止が要求されている場合は、タイマーをすべて取り消します。
+    pub(crate) fn tick(&mut self, now: f64) -> Result<()> {
+        for id in self.timers.due_ids(now) {
+            if self.abort_handle.is_aborted() {
+                break;
+            }
+            // 先に実行したコールバックで取り消されたものは飛ばす
+            let Some(callback) = self.timers.fire(id, now) else {
+                continue;
+            };
+            self.reset_step_count();
+            self.call(&callback, Vec::new())?;
+        }
+        if self.abort_handle.is_aborted() {
+            self.timers.clear();
         }
+        return Ok(());
     }
 
     pub(crate) fn register_native_fn(&mut self, native_fn: NativeFn) {
-        self.native_functions.push(native_fn);
+        self.native_functions.push(Some(native_fn));
+    }
+
+    /// 状態を持つネイティブ関数を登録し、その関数の値を作成します。
+    /// 関数は値がなくなった後、次に関数を生成するときか実行の終了時に破棄されます。
+    pub(crate) fn generate_native_fn(
+        &mut self,
+        function: impl Fn(Vec<Value>, &mut Vm) -> Result<Value> + 'static,
+    ) -> Value {
+        if self.generated_natives.len() >= self.generated_native_limit {
+            gc::force_collect();
+            self.release_generated_natives();
+            self.generated_native_limit =
+                (self.generated_natives.len() * 2).max(MIN_GENERATED_NATIVE_LIMIT);
+        }
+        let native_fn = NativeFn::Dynamic(Rc::new(function));
+        let index = match self.native_functions.iter().position(Option::is_none) {
+            Some(index) => {
+                self.native_functions[index] = Some(native_fn);
+                index
+            }
+            None => {
+                self.native_functions.push(Some(native_fn));
+                self.native_functions.len() - 1
+            }
+        };
+        // 値ごとに別の引数名を割り当て、参照数を数えられるようにする
+        let params: Rc<[Utf16String]> = Rc::from([]);
+        self.generated_natives.push((index, Rc::clone(&params)));
+        return Value::Fn(Gc::new(GcCell::new(VFn {
+            index: FnIndex::Native(index),
+            defaults: Vec::new(),
+            capture: Vec::new(),
+            params,
+        })));
+    }
+
+    /// 値が残っていない生成したネイティブ関数を破棄します。
+    /// 到達できない値を回収した後に呼び出します。
+    pub(crate) fn release_generated_natives(&mut self) {
+        let native_functions = &mut self.native_functions;
+        self.generated_natives.retain(|(index, params)| {
+            if Rc::strong_count(params) > 1 {
+                return true;
+            }
+            native_functions[*index] = None;
+            return false;
+        });
+        while let Some(None) = self.native_functions.last() {
+            self.native_functions.pop();
+        }
+    }
+
+    /// 値を捕捉した関数の値を作成します。
+    /// 捕捉した値は呼び出し時に引数の先頭に渡されます。
+    pub(crate) fn bound_fn(&self, f: BoundFn, capture: Vec<Value>) -> Value {
+        return Value::Fn(Gc::new(GcCell::new(VFn {
+            index: FnIndex::Native(PRIMITIVE_METHOD_COUNT + f as usize),
+            defaults: Vec::new(),
+            capture: capture
+                .into_iter()
+                .map(|value| Gc::new(GcCell::new(value)))
+                .collect(),
+            params: Rc::from([]),
+        })));
+    }
+
+    /// `len`個のユーザー関数を連続して登録できる最初の位置を返します。
+    pub(crate) fn free_user_functions(&self, len: usize) -> usize {
+        let mut start = 0;
+        for (index, user_fn) in self.user_functions.iter().enumerate() {
+            if user_fn.is_some() {
+                start = index + 1;
+            } else if index + 1 - start == len {
+                return start;
+            }
+        }
+        return start;
+    }
+
+    /// ユーザー関数を`start`番目から順に登録します。
+    pub(crate) fn register_user_functions(&mut self, start: usize, user_functions: Vec<UserFn>) {
+        let end = start + user_functions.len();
+        if self.user_functions.len() < end {
+            self.user_functions.resize(end, None);
+        }
+        for (slot, user_fn) in self.user_functions[start..end]
+            .iter_mut()
+            .zip(user_functions)
+        {
+            *slot = Some(Rc::new(user_fn));
+        }
+    }
+
+    /// 範囲内のユーザー関数のいずれかの値が残っているかを返します。
+    pub(crate) fn user_functions_in_use(&self, range: Range<usize>) -> bool {
+        return self.user_functions[range]
+            .iter()
+            .flatten()
+            .any(|user_fn| Rc::strong_count(&user_fn.params) > 1);
+    }
+
+    /// 範囲内のユーザー関数を破棄します。
+    pub(crate) fn release_user_functions(&mut self, range: Range<usize>) {
+        self.user_functions[range].fill(None);
+        while let Some(None) = self.user_functions.last() {
+            self.user_functions.pop();
+        }
+    }
+
+    /// 番号のユーザー関数を返します。
+    /// 関数は値が残っている間は破棄されないため、関数の値が持つ番号は常に有効です。
+    fn user_fn(&self, index: UserFnIndex) -> &Rc<UserFn> {
+        return self.user_functions[index]
+            .as_ref()
+            .expect("user function already released");
+    }
+
+    pub(crate) fn native_function_count(&self) -> usize {
+        self.native_functions.len()
+    }
+
+    pub(crate) fn user_function_count(&self) -> usize {
+        self.user_functions.len()
+    }
+
+    pub(crate) fn global_count(&self) -> usize {
+        self.globals.len()
+    }
+
+    /// グローバル変数の領域を確保します。
+    pub(crate) fn reserve_globals(&mut self, len: usize) {
+        if self.globals.len() < len {
+            self.globals.resize(len, Value::Uninitialized);
+        }
+    }
+
+    /// 実行した命令数を0に戻します。
+    pub(crate) fn reset_step_count(&mut self) {
+        self.step_count = 0;
+    }
+
+    /// 関数の値を呼び出します。
+    /// ネイティブ関数からスクリプトのコールバックを呼び出すために使用できます。
+    pub fn exec_fn(&mut self, f: &Value, args: Vec<Value>) -> Result<Value> {
+        let f = require_function(f)?;
+        return self.call(&f, args);
+    }
+
+    /// エントリーポイントを実行し、レジスタ`result`の値を返します。
+    pub(crate) fn exec(&mut self, entry_point: &UserFn, result: Option<Register>) -> Result<Value> {
+        let mut registers = Registers::new(entry_point.register_length, entry_point.cell_length);
+        self.call_stack.push(StackFrame {
+            name: None,
+            pos: None,
+        });
+        let control = self.exec_instructions(&entry_point.body, &mut registers);
+        self.call_stack.pop();
+        let control = control?;
+        if let Some(control) = control {
+            return Err(Box::new(escaped_control_error(&control)));
+        }
+        let value = match result {
+            Some(result) => registers[result].clone(),
+            None => Value::Null,
+        };
+        return Ok(value);
     }
 
-    pub(crate) fn exec(&mut self, entry_point: &UserFn) -> Result<()> {
-        self.exec_instructions(
-            &entry_point.instructions,
-            &mut Registers::new(entry_point.register_length),
-        )
+    /// 関数を呼び出します。
+    pub(crate) fn call(&mut self, f: &Gc<GcCell<VFn>>, args: Vec<Value>) -> Result<Value> {
+        let f = f.borrow().clone();
+        match f.index {
+            FnIndex::Native(index) => {
+                // 捕捉した値は引数の先頭に渡す
+                let args = if f.capture.is_empty() {
+                    args
+                } else {
+                    f.capture
+                        .iter()
+                        .map(|value| value.borrow().clone())
+                        .chain(args)
+                        .collect()
+                };
+                let native_fn = self.native_functions[index]
+                    .as_ref()
+                    .expect("native function already released");
+                match native_fn {
+                    NativeFn::Static(function) => {
+                        let function = *function;
+                        function(args, self)
+                    }
+                    NativeFn::Dynamic(function) => {
+                        let function = Rc::clone(function);
+                        function(args, self)
+                    }
+                }
+            }
+            FnIndex::User(index) => {
+                let user_fn = Rc::clone(self.user_fn(index));
+                let mut registers = Registers::new(user_fn.register_length, user_fn.cell_length);
+                for (cell, captured) in user_fn.captures.iter().zip(&f.capture) {
+                    registers.cells[*cell] = Gc::clone(captured);
+                }
+                let mut args = args.into_iter();
+                for (param, default) in f.defaults.iter().enumerate() {
+                    let value = args
+                        .next()
+                        .or_else(|| default.clone())
+                        .unwrap_or(Value::Uninitialized);
+                    registers[param] = require_any(&value)?;
+                }
+                let stack_guard = StackGuard::new();
+                if self.call_depth >= MAX_CALL_DEPTH || stack_guard.exceeded() {
+                    return Err(Box::new(AiScriptBasicError::new(
+                        AiScriptBasicErrorKind::Runtime,
+                        "Maximum call stack size exceeded",
+                        None,
+                    )));
+                }
+                self.call_depth += 1;
+                self.call_stack.push(StackFrame {
+                    name: user_fn.name.as_ref().map(|name| name.to_string()),
+                    pos: None,
+                });
+                let result = self.exec_instructions(&user_fn.body, &mut registers);
+                self.call_stack.pop();
+                self.call_depth -= 1;
+                match result? {
+                    Some(Control::Return(value)) => Ok(value),
+                    Some(control) => Err(Box::new(escaped_control_error(&control))),
+                    None => Ok(Value::Null),
+                }
+            }
+        }
     }
 
+    /// 命令列を実行します。
+    /// 制御文によって命令列の実行が中断された場合は、その制御文を返します。
     fn exec_instructions(
         &mut self,
-        instructions: &[Instruction],
+        block: &Block,
         registers: &mut Registers,
-    ) -> Result<()> {
-        for instruction in instructions {
-            self.step(&instruction, registers)?;
+    ) -> Result<Option<Control>> {
+        for (instruction, pos) in block.instructions.iter().zip(&block.positions) {
+            if let Instruction::Call(..) = instruction {
+                if let Some(frame) = self.call_stack.last_mut() {
+                    frame.pos = pos.clone();
+                }
+            }
+            match self.step(instruction, registers) {
+                Ok(Some(control)) => return Ok(Some(control)),
+                Ok(None) => {}
+                Err(mut error) => {
+                    if error.stack().is_empty() {
+                        let pos = error.pos().or_else(|| pos.clone());
+                        error.record_trace(pos.clone(), self.trace(pos));
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// 現在の呼び出し履歴を内側の関数から順に並べて返します。
+    /// 最も内側の関数の位置は`pos`とします。
+    fn trace(&self, pos: Option<Position>) -> Vec<StackFrame> {
+        let mut stack: Vec<StackFrame> = self.call_stack.iter().rev().cloned().collect();
+        if let Some(frame) = stack.first_mut() {
+            frame.pos = pos;
         }
-        Ok(())
+        return stack;
     }
 
-    fn step(&mut self, instruction: &Instruction, registers: &mut Registers) -> Result<()> {
+    /// ループ本体を1回実行します。
+    /// ループを終了する場合は、外側に伝播させる制御文を`ControlFlow::Break`に格納して返します。
+    fn exec_loop_body(
+        &mut self,
+        block: &Block,
+        registers: &mut Registers,
+    ) -> Result<ControlFlow<Option<Control>>> {
+        // 本体が空のループでも停止できるよう、繰り返しごとに数える
+        self.count_step()?;
+        match self.exec_instructions(block, registers)? {
+            None | Some(Control::Continue(0)) => Ok(ControlFlow::Continue(())),
+            Some(Control::Break(0)) => Ok(ControlFlow::Break(None)),
+            // 外側のループを対象とする場合は、1つ外側に伝播させる
+            Some(Control::Break(depth)) => Ok(ControlFlow::Break(Some(Control::Break(depth - 1)))),
+            Some(Control::Continue(depth)) => {
+                Ok(ControlFlow::Break(Some(Control::Continue(depth - 1))))
+            }
+            Some(control) => Ok(ControlFlow::Break(Some(control))),
+        }
+    }
+
+    fn step(
+        &mut self,
+        instruction: &Instruction,
+        registers: &mut Registers,
+    ) -> Result<Option<Control>> {
+        self.count_step()?;
         match instruction {
             Instruction::Nop => {}
             Instruction::Panic(ai_script_basic_error) => {
@@ -82,11 +503,107 @@ impl Vm {
             Instruction::If(cond, then_code, else_code) => {
                 let cond = require_boolean(&registers[*cond])?;
                 if cond {
-                    self.exec_instructions(then_code, registers)?;
+                    return self.exec_instructions(then_code, registers);
                 } else {
-                    self.exec_instructions(else_code, registers)?;
+                    return self.exec_instructions(else_code, registers);
+                }
+            }
+            Instruction::Return(register) => {
+                return Ok(Some(Control::Return(registers[*register].clone())));
+            }
+            Instruction::Loop(code) => loop {
+                if let ControlFlow::Break(control) = self.exec_loop_body(code, registers)? {
+                    return Ok(control);
+                }
+            },
+            Instruction::Times(times, code) => {
+                let times = require_number(&registers[*times])?;
+                let mut i = 0.0;
+                while i < times {
+                    if let ControlFlow::Break(control) = self.exec_loop_body(code, registers)? {
+                        return Ok(control);
+                    }
+                    i += 1.0;
+                }
+            }
+            Instruction::Range(counter, from, to, code) => {
+                let from = require_number(&registers[*from])?;
+                let to = require_number(&registers[*to])?;
+                let mut i = from;
+                while i < from + to {
+                    registers[*counter] = Value::Num(i);
+                    if let ControlFlow::Break(control) = self.exec_loop_body(code, registers)? {
+                        return Ok(control);
+                    }
+                    i += 1.0;
                 }
             }
+            Instruction::Each(item, items, code) => {
+                let items = require_array(&registers[*items])?;
+                let mut i = 0;
+                loop {
+                    // ループ中の配列の変更を反映するため、毎回要素を取り出す
+                    let value = items.borrow().get(i).cloned();
+                    let Some(value) = value else {
+                        break;
+                    };
+                    registers[*item] = value;
+                    if let ControlFlow::Break(control) = self.exec_loop_body(code, registers)? {
+                        return Ok(control);
+                    }
+                    i += 1;
+                }
+            }
+            Instruction::Break(depth) => {
+                return Ok(Some(Control::Break(*depth)));
+            }
+            Instruction::Continue(depth) => {
+                return Ok(Some(Control::Continue(*depth)));
+            }
+            Instruction::Call(register, f, args) => {
+                let value = self.exec_call(&registers[*f], &registers[*args])?;
+                registers[*register] = value;
+            }
+            _ => self.exec_simple(instruction, registers)?,
+        }
+
+        return Ok(None);
+    }
+
+    /// 命令の実行回数を数え、停止が要求されていればエラーを返します。
+    /// 長時間かかるネイティブ関数からも、停止の要求に応じるために呼び出します。
+    pub(crate) fn count_step(&mut self) -> Result<()> {
+        self.check_aborted()?;
+        self.step_count += 1;
+        if let Some(max_step) = self.max_step {
+            if self.step_count > max_step {
+                return Err(Box::new(AiScriptBasicError::new(
+                    AiScriptBasicErrorKind::Aborted,
+                    "max step exceeded",
+                    None,
+                )));
+            }
+        }
+        return Ok(());
+    }
+
+    /// 停止が要求されていればエラーを返します。
+    /// 命令の実行回数は数えないため、待機中のネイティブ関数から呼び出せます。
+    pub(crate) fn check_aborted(&self) -> Result<()> {
+        if self.abort_handle.is_aborted() {
+            return Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Aborted,
+                "execution aborted",
+                None,
+            )));
+        }
+        return Ok(());
+    }
+
+    /// 制御フローを伴わない命令を実行します。
+    /// 再帰呼び出しの経路に乗る`step`のスタックフレームを小さく保つため分離しています。
+    fn exec_simple(&mut self, instruction: &Instruction, registers: &mut Registers) -> Result<()> {
+        match instruction {
             Instruction::Null(register) => {
                 registers[*register] = Value::Null;
             }
@@ -100,20 +617,71 @@ impl Vm {
                 registers[*register] = Value::Str(Rc::clone(value));
             }
             Instruction::Arr(register, value) => {
-                registers[*register] = Value::Arr(Gc::clone(value));
+                registers[*register] = Value::Arr(Gc::new(GcCell::new(value.borrow().clone())));
             }
             Instruction::Obj(register, value) => {
-                registers[*register] = Value::Obj(Gc::clone(value));
+                registers[*register] = Value::Obj(Gc::new(GcCell::new(value.borrow().clone())));
+            }
+            Instruction::Value(register, value) => {
+                registers[*register] = value.clone();
             }
             Instruction::NativeFn(register, index) => {
                 registers[*register] = Value::Fn(Gc::new(GcCell::new(VFn {
                     index: FnIndex::Native(*index),
+                    defaults: Vec::new(),
                     capture: Vec::new(),
+                    params: Rc::from([]),
+                })));
+            }
+            Instruction::Fn(register, index, defaults, captures) => {
+                let defaults = defaults
+                    .iter()
+                    .map(|default| default.map(|default| registers[default].clone()))
+                    .collect();
+                let capture = captures
+                    .iter()
+                    .map(|cell| Gc::clone(&registers.cells[*cell]))
+                    .collect();
+                registers[*register] = Value::Fn(Gc::new(GcCell::new(VFn {
+                    index: FnIndex::User(*index),
+                    defaults,
+                    capture,
+                    params: Rc::clone(&self.user_fn(*index).params),
                 })));
             }
             Instruction::Move(dest, src) => {
                 registers[*dest] = registers[*src].clone();
             }
+            Instruction::Cell(cell) => {
+                registers.cells[*cell] = Gc::new(GcCell::new(Value::Uninitialized));
+            }
+            Instruction::LoadCell(dest, src) => {
+                let value = registers.cells[*src].borrow().clone();
+                registers[*dest] = value;
+            }
+            Instruction::StoreCell(src, dest) => {
+                *registers.cells[*dest].borrow_mut() = registers[*src].clone();
+            }
+            Instruction::LoadGlobal(dest, src) => {
+                registers[*dest] = self
+                    .globals
+                    .get(*src)
+                    .cloned()
+                    .unwrap_or(Value::Uninitialized);
+            }
+            Instruction::StoreGlobal(src, dest) => {
+                if self.globals.len() <= *dest {
+                    self.globals.resize(*dest + 1, Value::Uninitialized);
+                }
+                self.globals[*dest] = registers[*src].clone();
+            }
+            Instruction::Tmpl(dest, parts) => {
+                let mut result = Utf16String::new();
+                for part in parts {
+                    result += repr_value(&registers[*part], false)?.as_utf16_str();
+                }
+                registers[*dest] = Value::Str(Rc::from(result.as_u16s()));
+            }
             Instruction::Add(dest, left, right) => {
                 let left = require_number(&registers[*left])?;
                 let right = require_number(&registers[*right])?;
@@ -124,93 +692,130 @@ impl Vm {
                 let right = require_number(&registers[*right])?;
                 registers[*dest] = Value::Num(left - right);
             }
+            Instruction::Mul(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Num(left * right);
+            }
+            Instruction::Pow(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Num(pow(left, right));
+            }
+            Instruction::Div(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Num(left / right);
+            }
+            Instruction::Rem(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Num(left % right);
+            }
+            Instruction::Lt(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Bool(left < right);
+            }
+            Instruction::Lteq(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Bool(left <= right);
+            }
+            Instruction::Gt(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Bool(left > right);
+            }
+            Instruction::Gteq(dest, left, right) => {
+                let left = require_number(&registers[*left])?;
+                let right = require_number(&registers[*right])?;
+                registers[*dest] = Value::Bool(left >= right);
+            }
+            Instruction::Eq(dest, left, right) => {
+                registers[*dest] = Value::Bool(registers[*left] == registers[*right]);
+            }
+            Instruction::Neq(dest, left, right) => {
+                registers[*dest] = Value::Bool(registers[*left] != registers[*right]);
+            }
             Instruction::Not(dest, src) => {
                 let src = require_boolean(&registers[*src])?;
                 registers[*dest] = Value::Bool(!src);
             }
             Instruction::Load(register, target, index) => {
-                let target = registers[*target].clone();
-                match target {
+                let value = match &registers[*target] {
                     Value::Arr(target) => {
-                        let index_float = require_number(&registers[*index])?;
-                        if let Some(value) = target.as_ref().borrow().get_by_f64(index_float) {
-                            let value = value.clone();
-                            registers[*register] = value;
-                        } else {
-                            return Err(Box::new(AiScriptBasicError::new(
-                                AiScriptBasicErrorKind::Runtime,
-                                format!(
-                                    "Index out of range. index: {} max: {}",
-                                    index_float,
-                                    target.as_ref().borrow().len() - 1
-                                ),
-                                None,
-                            )));
-                        }
+                        let index = require_number(&registers[*index])?;
+                        let target = target.borrow();
+                        let Some(value) = target.get_by_f64(index) else {
+                            return Err(Box::new(index_out_of_range_error(index, target.len())));
+                        };
+                        value.clone()
                     }
-                    Value::Obj(_) => todo!(),
-                    _ => todo!(),
-                }
+                    Value::Obj(target) => {
+                        let index = require_string(&registers[*index])?;
+                        let value = target.borrow().0.get(&index).cloned();
+                        value.unwrap_or(Value::Null)
+                    }
+                    target => {
+                        return Err(Box::new(invalid_index_target_error(
+                            target,
+                            &registers[*index],
+                        )?));
+                    }
+                };
+                registers[*register] = value;
             }
             Instruction::LoadIndex(register, target, index) => {
                 let target = require_array(&registers[*target])?;
-                if let Some(value) = target.borrow().get(*index) {
-                    registers[*register] = value.clone();
-                } else {
-                    return Err(Box::new(AiScriptBasicError::new(
-                        AiScriptBasicErrorKind::Runtime,
-                        format!(
-                            "Index out of range. index: {} max: {}",
-                            index,
-                            target.as_ref().borrow().len() - 1
-                        ),
-                        None,
+                let value = target.borrow().get(*index).cloned();
+                let Some(value) = value else {
+                    return Err(Box::new(index_out_of_range_error(
+                        index,
+                        target.borrow().len(),
                     )));
                 };
+                registers[*register] = value;
             }
             Instruction::LoadProp(register, target, name) => {
-                let target = require_object(&registers[*target])?;
-                let value = target.borrow().0.get(name).map(|value| value.clone());
+                let value = match &registers[*target] {
+                    Value::Obj(target) => target.borrow().0.get(name).cloned(),
+                    target => Some(get_prim_prop(target, name)?),
+                };
                 registers[*register] = value.unwrap_or(Value::Null);
             }
             Instruction::Store(register, target, index) => {
-                let target = registers[*target].clone();
-                match target {
+                let value = registers[*register].clone();
+                match &registers[*target] {
                     Value::Arr(target) => {
-                        let index_float = require_number(&registers[*index])?;
-                        if let Some(value) = target.borrow_mut().get_mut_by_f64(index_float) {
-                            *value = registers[*register].clone();
-                        } else {
-                            return Err(Box::new(AiScriptBasicError::new(
-                                AiScriptBasicErrorKind::Runtime,
-                                format!(
-                                    "Index out of range. index: {} max: {}",
-                                    index_float,
-                                    target.as_ref().borrow().len() - 1
-                                ),
-                                None,
-                            )));
-                        }
+                        let index = require_number(&registers[*index])?;
+                        let mut target = target.borrow_mut();
+                        let len = target.len();
+                        let Some(ptr) = target.get_mut_by_f64(index) else {
+                            return Err(Box::new(index_out_of_range_error(index, len)));
+                        };
+                        *ptr = value;
+                    }
+                    Value::Obj(target) => {
+                        let index = require_string(&registers[*index])?;
+                        target.borrow_mut().0.insert(index, value);
+                    }
+                    target => {
+                        return Err(Box::new(invalid_index_target_error(
+                            target,
+                            &registers[*index],
+                        )?));
                     }
-                    Value::Obj(_) => todo!(),
-                    _ => todo!(),
                 }
             }
             Instruction::StoreIndex(register, target, index) => {
                 let target = require_array(&registers[*target])?;
-                if let Some(ptr) = target.borrow_mut().get_mut(*index) {
-                    *ptr = registers[*register].clone();
-                } else {
-                    return Err(Box::new(AiScriptBasicError::new(
-                        AiScriptBasicErrorKind::Runtime,
-                        format!(
-                            "Index out of range. index: {} max: {}",
-                            index,
-                            target.as_ref().borrow().len() - 1
-                        ),
-                        None,
-                    )));
+                let mut target = target.borrow_mut();
+                let len = target.len();
+                let Some(ptr) = target.get_mut(*index) else {
+                    return Err(Box::new(index_out_of_range_error(index, len)));
                 };
+                *ptr = registers[*register].clone();
             }
             Instruction::StoreProp(register, target, name) => {
                 let target = require_object(&registers[*target])?;
@@ -219,36 +824,51 @@ impl Vm {
                     .0
                     .insert(Rc::clone(name), registers[*register].clone());
             }
-            Instruction::Call(register, f, args) => {
-                let closure = require_function(&registers[*f])?;
-                let args = require_array(&registers[*args])?;
-                let capture = closure.borrow().capture.clone();
-                match closure.borrow().index {
-                    FnIndex::Native(index) => {
-                        let function = &self.native_functions[index];
-                        match function {
-                            NativeFn::Static(function) => {
-                                registers[*register] = function(args.borrow().clone(), self)?;
-                            }
-                            NativeFn::Dynamic(function) => {
-                                registers[*register] =
-                                    Rc::clone(function)(args.borrow().clone(), self)?;
-                            }
-                        };
-                    }
-                    FnIndex::User(index) => todo!(),
-                };
-            }
+            // 制御フローを伴う命令は`step`で処理済み
+            _ => {}
         }
-
         return Ok(());
     }
-}
 
-impl Default for Vm {
-    fn default() -> Self {
-        Vm {
-            native_functions: Vec::new(),
-        }
+    /// 関数呼び出し命令を実行します。
+    fn exec_call(&mut self, f: &Value, args: &Value) -> Result<Value> {
+        let closure = require_function(f)?;
+        let args = require_array(args)?.borrow().clone();
+        return self.call(&closure, args);
     }
 }
+
+/// 関数やプログラムの外まで伝播した制御文のエラーを生成します。
+fn escaped_control_error(control: &Control) -> AiScriptBasicError {
+    let message = match control {
+        Control::Return(_) => "return must be inside function",
+        Control::Break(_) => "break must be inside for / each / while / do-while / loop",
+        Control::Continue(_) => "continue must be inside for / each / while / do-while / loop",
+    };
+    return AiScriptBasicError::new(AiScriptBasicErrorKind::Runtime, message, None);
+}
+
+fn index_out_of_range_error(index: impl Display, len: usize) -> AiScriptBasicError {
+    return AiScriptBasicError::new(
+        AiScriptBasicErrorKind::Runtime,
+        format!(
+            "Index out of range. index: {} max: {}",
+            index,
+            len as isize - 1
+        ),
+        None,
+    );
+}
+
+/// 配列とオブジェクト以外の値を添字で参照した場合のエラーを生成します。
+fn invalid_index_target_error(target: &Value, index: &Value) -> Result<AiScriptBasicError> {
+    return Ok(AiScriptBasicError::new(
+        AiScriptBasicErrorKind::Runtime,
+        format!(
+            "Cannot read prop ({}) of {}.",
+            repr_value(index, false)?,
+            target.type_name()
+        ),
+        None,
+    ));
+}
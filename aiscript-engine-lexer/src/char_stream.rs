@@ -84,26 +84,17 @@ impl CharStream<'_> {
         if self.eof() {
             self.char = None;
         } else {
-            self.char = self
-                .page
-                .as_u16s()
-                .get(self.address)
-                .map(|char| char.clone());
+            self.char = self.page.as_u16s().get(self.address).copied();
         }
     }
 }
 
+#[derive(Default)]
 pub struct CharStreamOpts {
     line: usize,
     column: usize,
 }
 
-impl Default for CharStreamOpts {
-    fn default() -> Self {
-        CharStreamOpts { line: 0, column: 0 }
-    }
-}
-
 impl<'a> From<&'a Utf16Str> for CharStream<'a> {
     fn from(value: &'a Utf16Str) -> Self {
         CharStream::new(value, CharStreamOpts::default())
@@ -151,20 +142,20 @@ mod tests {
     fn eof() {
         let source = Utf16String::from("abc");
         let mut stream = CharStream::new(&source, Default::default());
-        assert_eq!(false, stream.eof());
+        assert!(!stream.eof());
         stream.next();
-        assert_eq!(false, stream.eof());
+        assert!(!stream.eof());
         stream.next();
-        assert_eq!(false, stream.eof());
+        assert!(!stream.eof());
         stream.next();
-        assert_eq!(true, stream.eof());
+        assert!(stream.eof());
     }
 
     #[test]
     fn none_when_ref_char_at_eof() {
         let source = Utf16String::new();
         let stream = CharStream::new(&source, Default::default());
-        assert_eq!(true, stream.eof());
+        assert!(stream.eof());
         assert!(stream.char().is_none());
     }
 
@@ -178,6 +169,6 @@ mod tests {
         stream.next();
         assert_eq!(Some(utf16!('b')), stream.char());
         stream.next();
-        assert_eq!(true, stream.eof());
+        assert!(stream.eof());
     }
 }
@@ -2,7 +2,7 @@ use std::collections::VecDeque;
 
 use utf16_literal::utf16;
 
-use aiscript_engine_common::{AiScriptSyntaxError, Result, Utf16String};
+use aiscript_engine_common::{AiScriptSyntaxError, Result, StackGuard, Utf16String};
 
 use crate::{
     token::{Token, TokenKind},
@@ -18,13 +18,14 @@ fn is_line_break_char(char: u16) -> bool {
 }
 
 fn is_digit(char: u16) -> bool {
-    char >= utf16!('0') && char <= utf16!('9')
+    (utf16!('0')..=utf16!('9')).contains(&char)
 }
 
 fn is_word_char(char: u16) -> bool {
-    (char >= utf16!('A') && char <= utf16!('Z'))
-        || (char >= utf16!('a') && char <= utf16!('z'))
+    (utf16!('A')..=utf16!('Z')).contains(&char)
+        || (utf16!('a')..=utf16!('z')).contains(&char)
         || is_digit(char)
+        || char == utf16!('_')
 }
 
 /// 入力文字列からトークンを読み取る構造体
@@ -120,10 +121,11 @@ impl Scanner<'_> {
                             has_left_spacing,
                         })
                     } else {
-                        Err(Box::new(AiScriptSyntaxError::new(
-                            "invalid character: \"#\"",
+                        Ok(Token {
+                            kind: TokenKind::Sharp,
                             pos,
-                        )))
+                            has_left_spacing,
+                        })
                     }
                 }
                 utf16!('%') => {
@@ -572,6 +574,16 @@ impl Scanner<'_> {
 
         let pos = self.stream.get_pos();
         let mut element_pos = pos.clone();
+
+        // 埋め込み式の中のテンプレートは再帰的に読み込むため、入れ子の深さを制限する
+        let stack_guard = StackGuard::new();
+        if stack_guard.exceeded() {
+            return Err(Box::new(AiScriptSyntaxError::new(
+                "nesting is too deep",
+                pos,
+            )));
+        }
+
         self.stream.next();
 
         loop {
@@ -677,10 +689,7 @@ impl Scanner<'_> {
     }
 
     fn skip_comment_range(&mut self) {
-        loop {
-            let Some(ch) = self.stream.char() else {
-                break;
-            };
+        while let Some(ch) = self.stream.char() {
             if ch == utf16!('*') {
                 self.stream.next();
                 if self.stream.char().is_some_and(|ch| ch == utf16!('/')) {
@@ -696,17 +705,18 @@ impl Scanner<'_> {
 
 impl ITokenStream for Scanner<'_> {
     fn get_token(&self) -> &Token {
-        self.tokens.get(0).expect("no token found")
+        self.tokens.front().expect("no token found")
     }
 
     fn next(&mut self) -> Result<Token> {
-        let result = self.tokens.pop_front().expect("no token found");
-
-        if self.tokens.is_empty() {
+        // 読み取りに失敗してもトークンが空にならないよう、取り出す前に次のトークンを読む
+        if self.tokens.len() == 1 {
             let token = self.read_token();
             self.tokens.push_back(token?);
         }
 
+        let result = self.tokens.pop_front().expect("no token found");
+
         return Ok(result);
     }
 
@@ -728,7 +738,7 @@ mod tests {
 
     use super::*;
 
-    fn init(source: &Utf16Str) -> Scanner {
+    fn init(source: &Utf16Str) -> Scanner<'_> {
         Scanner::new(source).unwrap()
     }
 
@@ -807,6 +817,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn identifier_with_underscore() {
+        let source = Utf16String::from("to_str");
+        let mut stream = init(&source);
+        next(
+            &mut stream,
+            &Token {
+                kind: TokenKind::Identifier(source.clone()),
+                pos: Position::At { line: 1, column: 1 },
+                has_left_spacing: false,
+            },
+        );
+    }
+
     #[test]
     fn invalid_token() {
         let source = Utf16String::from("$");
@@ -1000,8 +1024,18 @@ mod tests {
             },
         );
 
+        let source = Utf16Str::new(&utf16!("#a"));
+        let mut stream = init(source);
+        next(
+            &mut stream,
+            Token {
+                kind: TokenKind::Sharp,
+                pos: Position::At { line: 1, column: 1 },
+                has_left_spacing: false,
+            },
+        );
+
         fails(Utf16Str::new(&utf16!("##")));
-        fails(Utf16Str::new(&utf16!("#")));
         fails(Utf16Str::new(&utf16!("&")));
         fails(Utf16Str::new(&utf16!("|")));
     }
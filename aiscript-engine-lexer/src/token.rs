@@ -60,6 +60,9 @@ pub enum TokenKind {
     /// "!="
     NotEq,
 
+    /// "#"
+    Sharp,
+
     /// "#["
     OpenSharpBracket,
 
@@ -165,28 +168,28 @@ pub enum TokenKind {
 
 impl TokenKind {
     pub fn for_word(word: &Utf16Str) -> TokenKind {
-        match word.as_u16s() {
-            &utf16!("null") => TokenKind::NullKeyword,
-            &utf16!("true") => TokenKind::TrueKeyword,
-            &utf16!("false") => TokenKind::FalseKeyword,
-            &utf16!("each") => TokenKind::EachKeyword,
-            &utf16!("for") => TokenKind::ForKeyword,
-            &utf16!("loop") => TokenKind::LoopKeyword,
-            &utf16!("do") => TokenKind::DoKeyword,
-            &utf16!("while") => TokenKind::WhileKeyword,
-            &utf16!("break") => TokenKind::BreakKeyword,
-            &utf16!("continue") => TokenKind::ContinueKeyword,
-            &utf16!("match") => TokenKind::MatchKeyword,
-            &utf16!("case") => TokenKind::CaseKeyword,
-            &utf16!("default") => TokenKind::DefaultKeyword,
-            &utf16!("if") => TokenKind::IfKeyword,
-            &utf16!("elif") => TokenKind::ElifKeyword,
-            &utf16!("else") => TokenKind::ElseKeyword,
-            &utf16!("return") => TokenKind::ReturnKeyword,
-            &utf16!("eval") => TokenKind::EvalKeyword,
-            &utf16!("var") => TokenKind::VarKeyword,
-            &utf16!("let") => TokenKind::LetKeyword,
-            &utf16!("exists") => TokenKind::ExistsKeyword,
+        match *word.as_u16s() {
+            utf16!("null") => TokenKind::NullKeyword,
+            utf16!("true") => TokenKind::TrueKeyword,
+            utf16!("false") => TokenKind::FalseKeyword,
+            utf16!("each") => TokenKind::EachKeyword,
+            utf16!("for") => TokenKind::ForKeyword,
+            utf16!("loop") => TokenKind::LoopKeyword,
+            utf16!("do") => TokenKind::DoKeyword,
+            utf16!("while") => TokenKind::WhileKeyword,
+            utf16!("break") => TokenKind::BreakKeyword,
+            utf16!("continue") => TokenKind::ContinueKeyword,
+            utf16!("match") => TokenKind::MatchKeyword,
+            utf16!("case") => TokenKind::CaseKeyword,
+            utf16!("default") => TokenKind::DefaultKeyword,
+            utf16!("if") => TokenKind::IfKeyword,
+            utf16!("elif") => TokenKind::ElifKeyword,
+            utf16!("else") => TokenKind::ElseKeyword,
+            utf16!("return") => TokenKind::ReturnKeyword,
+            utf16!("eval") => TokenKind::EvalKeyword,
+            utf16!("var") => TokenKind::VarKeyword,
+            utf16!("let") => TokenKind::LetKeyword,
+            utf16!("exists") => TokenKind::ExistsKeyword,
             _ => TokenKind::Identifier(Utf16String::from(word)),
         }
     }
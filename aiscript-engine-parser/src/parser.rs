@@ -1,5 +1,5 @@
 use crate::{
-    plugins::{validate_keyword, validate_type},
+    plugins::{validate_jump_statements, validate_keyword, validate_type},
     syntaxes::toplevel::parse_top_level,
 };
 use aiscript_engine_ast::{self as ast};
@@ -18,10 +18,20 @@ pub struct Parser {
     transform_plugins: Vec<Box<ParserPlugin>>,
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Parser {
     pub fn new() -> Self {
         return Parser {
-            validate_plugins: vec![Box::new(validate_keyword), Box::new(validate_type)],
+            validate_plugins: vec![
+                Box::new(validate_keyword),
+                Box::new(validate_type),
+                Box::new(validate_jump_statements),
+            ],
             transform_plugins: Vec::new(),
         };
     }
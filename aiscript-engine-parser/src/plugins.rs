@@ -1,6 +1,8 @@
+mod validate_jump_statements;
 mod validate_keyword;
 mod validate_type;
 mod visit;
 
+pub(super) use validate_jump_statements::validate_jump_statements;
 pub(super) use validate_keyword::validate_keyword;
 pub(super) use validate_type::validate_type;
@@ -0,0 +1,127 @@
+// break文とcontinue文の対象となるループがあるかを確認する。
+// 関数の外側のループは対象にならない。
+
+use aiscript_engine_ast as ast;
+use aiscript_engine_common::{AiScriptSyntaxError, Result, Utf16String};
+
+use super::visit::{RecursiveVisitor, Visitor};
+
+struct NodeValidator {
+    /// 関数ごとの、囲んでいるループのラベル。外側のループから順に並ぶ
+    loops: Vec<Vec<Option<Utf16String>>>,
+}
+
+impl NodeValidator {
+    fn enter_loop(&mut self, label: &Option<Utf16String>) {
+        if let Some(loops) = self.loops.last_mut() {
+            loops.push(label.clone());
+        }
+    }
+
+    fn leave_loop(&mut self) {
+        if let Some(loops) = self.loops.last_mut() {
+            loops.pop();
+        }
+    }
+
+    /// 対象のループがなければ`unlabeled`のメッセージで文法エラーを返します。
+    fn check_target(
+        &self,
+        label: &Option<Utf16String>,
+        loc: &ast::Loc,
+        unlabeled: &'static str,
+    ) -> Result<()> {
+        let loops = self.loops.last().map_or(&[][..], |loops| &loops[..]);
+        match label {
+            Some(label) => {
+                if !loops
+                    .iter()
+                    .any(|loop_label| loop_label.as_ref() == Some(label))
+                {
+                    return Err(Box::new(AiScriptSyntaxError::new(
+                        format!("label \"{}\" is not defined", label),
+                        loc.start.clone(),
+                    )));
+                }
+            }
+            None => {
+                if loops.is_empty() {
+                    return Err(Box::new(AiScriptSyntaxError::new(
+                        unlabeled,
+                        loc.start.clone(),
+                    )));
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Visitor for NodeValidator {
+    fn visit_each(&mut self, node: &mut ast::Each) -> Result<()> {
+        self.enter_loop(&node.label);
+        return Ok(());
+    }
+
+    fn leave_each(&mut self, _: &mut ast::Each) -> Result<()> {
+        self.leave_loop();
+        return Ok(());
+    }
+
+    fn visit_for(&mut self, node: &mut ast::For) -> Result<()> {
+        self.enter_loop(&node.label);
+        return Ok(());
+    }
+
+    fn leave_for(&mut self, _: &mut ast::For) -> Result<()> {
+        self.leave_loop();
+        return Ok(());
+    }
+
+    fn visit_loop(&mut self, node: &mut ast::Loop) -> Result<()> {
+        self.enter_loop(&node.label);
+        return Ok(());
+    }
+
+    fn leave_loop(&mut self, _: &mut ast::Loop) -> Result<()> {
+        NodeValidator::leave_loop(self);
+        return Ok(());
+    }
+
+    fn visit_fn(&mut self, _: &mut ast::Fn) -> Result<()> {
+        self.loops.push(Vec::new());
+        return Ok(());
+    }
+
+    fn leave_fn(&mut self, _: &mut ast::Fn) -> Result<()> {
+        self.loops.pop();
+        return Ok(());
+    }
+
+    fn visit_break(&mut self, node: &mut ast::Break) -> Result<()> {
+        return self.check_target(
+            &node.label,
+            &node.loc,
+            "unlabeled break must be inside for / each / while / do-while / loop",
+        );
+    }
+
+    fn visit_continue(&mut self, node: &mut ast::Continue) -> Result<()> {
+        return self.check_target(
+            &node.label,
+            &node.loc,
+            "continue must be inside for / each / while / do-while / loop",
+        );
+    }
+}
+
+pub(crate) fn validate_jump_statements(nodes: &mut Vec<ast::Node>) -> Result<()> {
+    let mut node_validator = NodeValidator {
+        loops: vec![Vec::new()],
+    };
+    let mut validator = RecursiveVisitor::new(&mut node_validator);
+    for node in nodes {
+        validator.visit(node)?;
+    }
+    return Ok(());
+}
@@ -9,7 +9,7 @@ struct NodeValidator;
 impl Visitor for NodeValidator {
     fn visit_def(&mut self, node: &mut ast::Definition) -> Result<()> {
         if let Some(var_type) = &node.var_type {
-            get_type_by_source(&var_type)?;
+            get_type_by_source(var_type)?;
         }
         return Ok(());
     }
@@ -17,11 +17,11 @@ impl Visitor for NodeValidator {
     fn visit_fn(&mut self, node: &mut ast::Fn) -> Result<()> {
         for arg in &node.args {
             if let Some(arg_type) = &arg.arg_type {
-                get_type_by_source(&arg_type)?;
+                get_type_by_source(arg_type)?;
             }
         }
         if let Some(ret_type) = &node.ret_type {
-            get_type_by_source(&ret_type)?;
+            get_type_by_source(ret_type)?;
         }
         return Ok(());
     }
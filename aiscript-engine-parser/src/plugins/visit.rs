@@ -1,5 +1,5 @@
 use aiscript_engine_ast::*;
-use aiscript_engine_common::Result;
+use aiscript_engine_common::{AiScriptSyntaxError, Result, StackGuard};
 
 /// [`Node`]にアクセスして処理を行うトレイト。
 pub(super) trait Visitor {
@@ -157,6 +157,30 @@ pub(super) trait Visitor {
         let _ = node;
         Ok(())
     }
+
+    /// each文の子ノードを訪問した後に呼び出されます。
+    fn leave_each(&mut self, node: &mut Each) -> Result<()> {
+        let _ = node;
+        Ok(())
+    }
+
+    /// for文の子ノードを訪問した後に呼び出されます。
+    fn leave_for(&mut self, node: &mut For) -> Result<()> {
+        let _ = node;
+        Ok(())
+    }
+
+    /// loop文の子ノードを訪問した後に呼び出されます。
+    fn leave_loop(&mut self, node: &mut Loop) -> Result<()> {
+        let _ = node;
+        Ok(())
+    }
+
+    /// 関数の子ノードを訪問した後に呼び出されます。
+    fn leave_fn(&mut self, node: &mut Fn) -> Result<()> {
+        let _ = node;
+        Ok(())
+    }
 }
 
 /// ASTの構造に対して再帰的に処理を行う構造体。
@@ -210,7 +234,8 @@ impl<'a> Visitor for RecursiveVisitor<'a> {
     fn visit_each(&mut self, node: &mut Each) -> Result<()> {
         self.visitor.visit_each(node)?;
         self.visit_expr(&mut node.items)?;
-        return self.visit_statement_or_expr(&mut node.for_statement);
+        self.visit_statement_or_expr(&mut node.for_statement)?;
+        return self.visitor.leave_each(node);
     }
 
     fn visit_for(&mut self, node: &mut For) -> Result<()> {
@@ -218,10 +243,12 @@ impl<'a> Visitor for RecursiveVisitor<'a> {
         match &mut node.iter {
             ForIterator::Range { var: _, from, to } => {
                 self.visit_expr(from)?;
-                self.visit_expr(to)
+                self.visit_expr(to)?;
             }
-            ForIterator::Times { times } => self.visit_expr(times),
+            ForIterator::Times { times } => self.visit_expr(times)?,
         }
+        self.visit_statement_or_expr(&mut node.for_statement)?;
+        return self.visitor.leave_for(node);
     }
 
     fn visit_loop(&mut self, node: &mut Loop) -> Result<()> {
@@ -229,7 +256,7 @@ impl<'a> Visitor for RecursiveVisitor<'a> {
         for statement in &mut node.statements {
             self.visit_statement_or_expr(statement)?;
         }
-        return Ok(());
+        return self.visitor.leave_loop(node);
     }
 
     fn visit_break(&mut self, node: &mut Break) -> Result<()> {
@@ -273,7 +300,7 @@ impl<'a> Visitor for RecursiveVisitor<'a> {
         for child in &mut node.children {
             self.visit_statement_or_expr(child)?;
         }
-        return Ok(());
+        return self.visitor.leave_fn(node);
     }
 
     fn visit_match(&mut self, node: &mut Match) -> Result<()> {
@@ -397,6 +424,7 @@ pub(super) trait VisitorExtra: Visitor {
     }
 
     fn visit_statement(&mut self, node: &mut Statement) -> Result<()> {
+        let _stack_guard = enter_nested(node)?;
         match node {
             Statement::Def(n) => self.visit_def(n),
             Statement::Return(n) => self.visit_return(n),
@@ -410,6 +438,7 @@ pub(super) trait VisitorExtra: Visitor {
     }
 
     fn visit_expr(&mut self, node: &mut Expression) -> Result<()> {
+        let _stack_guard = enter_nested(node)?;
         match node {
             Expression::If(n) => self.visit_if(n),
             Expression::Fn(n) => self.visit_fn(n),
@@ -432,3 +461,15 @@ pub(super) trait VisitorExtra: Visitor {
         }
     }
 }
+
+/// 入れ子のノードを訪問する前に呼び出し、入れ子が深すぎる場合は文法エラーを返します。
+fn enter_nested(node: &impl NodeBase) -> Result<StackGuard> {
+    let stack_guard = StackGuard::new();
+    if stack_guard.exceeded() {
+        return Err(Box::new(AiScriptSyntaxError::new(
+            "nesting is too deep",
+            node.loc().start.clone(),
+        )));
+    }
+    return Ok(stack_guard);
+}
@@ -1,4 +1,20 @@
+use aiscript_engine_common::{AiScriptSyntaxError, Result, StackGuard};
+use aiscript_engine_lexer::ITokenStream;
+
 mod common;
 mod expressions;
 mod statement;
 pub(super) mod toplevel;
+
+/// 入れ子の構文を読み込む前に呼び出し、入れ子が深すぎる場合は文法エラーを返します。
+/// 返されたガードは入れ子の読み込みが終わるまで保持します。
+fn enter_nested(s: &impl ITokenStream) -> Result<StackGuard> {
+    let stack_guard = StackGuard::new();
+    if stack_guard.exceeded() {
+        return Err(Box::new(AiScriptSyntaxError::new(
+            "nesting is too deep",
+            s.get_pos().to_owned(),
+        )));
+    }
+    return Ok(stack_guard);
+}
@@ -2,7 +2,7 @@ use aiscript_engine_ast::{self as ast, Loc};
 use aiscript_engine_common::{AiScriptSyntaxError, Result};
 use aiscript_engine_lexer::{ITokenStream, RawToken, TokenKind};
 
-use super::{expressions::parse_expr, statement::parse_statement};
+use super::{enter_nested, expressions::parse_expr, statement::parse_statement};
 
 /// ```abnf
 /// Dest = IDENT / Expr
@@ -134,6 +134,7 @@ pub(super) fn parse_block(s: &mut impl ITokenStream) -> Result<Vec<ast::Statemen
 }
 
 pub(super) fn parse_type(s: &mut impl ITokenStream) -> Result<ast::TypeSource> {
+    let _stack_guard = enter_nested(s)?;
     if matches!(s.get_token_kind(), TokenKind::At) {
         return parse_fn_type(s);
     } else {
@@ -6,12 +6,14 @@ use aiscript_engine_ast::{self as ast, IndexMap, Loc, NodeBase};
 
 use super::{
     common::{parse_block, parse_params, parse_type},
+    enter_nested,
     statement::parse_block_or_statement,
 };
 
 mod pratt;
 
 pub(super) fn parse_expr(s: &mut impl ITokenStream, is_static: bool) -> Result<ast::Expression> {
+    let _stack_guard = enter_nested(s)?;
     if is_static {
         return parse_atom(s, true);
     } else {
@@ -197,6 +199,7 @@ fn parse_postfix(s: &mut impl ITokenStream, expr: ast::Expression) -> Result<ast
     }
 }
 
+#[allow(clippy::collapsible_match)]
 fn parse_atom(s: &mut impl ITokenStream, is_static: bool) -> Result<ast::Expression> {
     fn map_into(res: Result<impl Into<ast::Expression>>) -> Result<ast::Expression> {
         res.map(|expr| expr.into())
@@ -239,9 +242,7 @@ fn parse_atom(s: &mut impl ITokenStream, is_static: bool) -> Result<ast::Express
                     match element.kind {
                         TokenKind::TemplateStringElement(value) => {
                             // トークンの終了位置を取得するために先読み
-                            let next_token = iter
-                                .peek()
-                                .map_or_else(|| s.lookahead(1), |token| Ok(token))?;
+                            let next_token = iter.peek().map_or_else(|| s.lookahead(1), Ok)?;
                             values.push(
                                 ast::Str {
                                     loc: Loc {
@@ -292,8 +293,12 @@ fn parse_atom(s: &mut impl ITokenStream, is_static: bool) -> Result<ast::Express
             .into());
         }
         TokenKind::NumberLiteral(value) => {
-            // TODO: validate number value
-            let value: f64 = value.parse().unwrap();
+            let Ok(value) = value.parse::<f64>() else {
+                return Err(Box::new(AiScriptSyntaxError::new(
+                    "invalid number literal",
+                    start_pos,
+                )));
+            };
             s.next()?;
             return Ok(ast::Num {
                 loc: Loc {
@@ -5,6 +5,7 @@ use aiscript_engine_ast as ast;
 use aiscript_engine_common::Result;
 use aiscript_engine_lexer::{ITokenStream, TokenKind};
 
+use super::super::enter_nested;
 use super::{parse_atom, parse_infix, parse_postfix, parse_prefix};
 
 pub(super) type BindingPower = i32;
@@ -42,6 +43,8 @@ pub(super) fn parse_pratt(
     s: &mut impl ITokenStream,
     min_bp: BindingPower,
 ) -> Result<ast::Expression> {
+    let _stack_guard = enter_nested(s)?;
+
     // pratt parsing
     // https://matklad.github.io/2020/04/13/simple-but-powerful-pratt-parsing.html
 
@@ -3,15 +3,18 @@ use std::vec;
 use aiscript_engine_ast::{
     self as ast, Expression, Identifier, Loc, NodeBase, Statement, StatementOrExpression,
 };
-use aiscript_engine_common::{AiScriptSyntaxError, NamePath, Result, Utf16Str};
+use aiscript_engine_common::{AiScriptSyntaxError, NamePath, Result, Utf16Str, Utf16String};
 use aiscript_engine_lexer::{ITokenStream, RawToken, TokenKind};
 use utf16_literal::utf16;
 
 use crate::syntaxes::expressions::parse_expr;
 
 use super::common::{parse_block, parse_dest, parse_params, parse_type};
+use super::enter_nested;
 
 pub(super) fn parse_statement(s: &mut impl ITokenStream) -> Result<ast::StatementOrExpression> {
+    let _stack_guard = enter_nested(s)?;
+
     fn statement(result: Result<impl Into<Statement>>) -> Result<StatementOrExpression> {
         return result.map(|value| StatementOrExpression::from_statement(value));
     }
@@ -20,8 +23,6 @@ pub(super) fn parse_statement(s: &mut impl ITokenStream) -> Result<ast::Statemen
         return result.map(|value| StatementOrExpression::from_expr(value));
     }
 
-    let start_pos = s.get_pos().clone();
-
     match s.get_token_kind() {
         TokenKind::VarKeyword | TokenKind::LetKeyword => return statement(parse_var_def(s)),
         TokenKind::At => {
@@ -32,29 +33,14 @@ pub(super) fn parse_statement(s: &mut impl ITokenStream) -> Result<ast::Statemen
         TokenKind::Out => return expr(parse_out(s)),
         TokenKind::ReturnKeyword => return statement(parse_return(s)),
         TokenKind::OpenSharpBracket => return statement(parse_statement_with_attr(s)),
+        TokenKind::Sharp => return statement(parse_statement_with_label(s)),
         TokenKind::EachKeyword => return statement(parse_each(s)),
         TokenKind::ForKeyword => return statement(parse_for(s)),
         TokenKind::LoopKeyword => return statement(parse_loop(s)),
         TokenKind::DoKeyword => return statement(parse_do_while(s)),
         TokenKind::WhileKeyword => return statement(parse_while(s)),
-        TokenKind::BreakKeyword => {
-            s.next()?;
-            return Ok(StatementOrExpression::from_statement(ast::Break {
-                loc: Loc {
-                    start: start_pos,
-                    end: s.get_pos().to_owned(),
-                },
-            }));
-        }
-        TokenKind::ContinueKeyword => {
-            s.next()?;
-            return Ok(StatementOrExpression::from_statement(ast::Continue {
-                loc: Loc {
-                    start: start_pos,
-                    end: s.get_pos().to_owned(),
-                },
-            }));
-        }
+        TokenKind::BreakKeyword => return statement(parse_break(s)),
+        TokenKind::ContinueKeyword => return statement(parse_continue(s)),
         _ => {}
     }
     let expr = parse_expr(s, false)?;
@@ -268,6 +254,7 @@ fn parse_each(s: &mut impl ITokenStream) -> Result<ast::Each> {
             start: start_pos,
             end: s.get_pos().to_owned(),
         },
+        label: None,
         var: dest,
         items,
         for_statement: Box::new(body),
@@ -337,12 +324,13 @@ fn parse_for(s: &mut impl ITokenStream) -> Result<ast::For> {
                 start: start_pos,
                 end: s.get_pos().to_owned(),
             },
+            label: None,
             iter: ast::ForIterator::Range {
                 var: name,
                 from,
                 to,
             },
-            for_statement: Box::new(body.into()),
+            for_statement: Box::new(body),
         });
     } else {
         // times syntax
@@ -360,8 +348,9 @@ fn parse_for(s: &mut impl ITokenStream) -> Result<ast::For> {
                 start: start_pos,
                 end: s.get_pos().to_owned(),
             },
+            label: None,
             iter: ast::ForIterator::Times { times },
-            for_statement: Box::new(body.into()),
+            for_statement: Box::new(body),
         });
     }
 }
@@ -387,6 +376,7 @@ fn parse_return(s: &mut impl ITokenStream) -> Result<ast::Return> {
 /// ```abnf
 /// StatementWithAttr = *Attr Statement
 /// ```
+#[allow(clippy::collapsible_match)]
 fn parse_statement_with_attr(s: &mut impl ITokenStream) -> Result<ast::Definition> {
     let mut attrs: Vec<ast::Attribute> = Vec::new();
     while matches!(s.get_token_kind(), TokenKind::OpenSharpBracket) {
@@ -410,6 +400,96 @@ fn parse_statement_with_attr(s: &mut impl ITokenStream) -> Result<ast::Definitio
     )));
 }
 
+/// ```abnf
+/// StatementWithLabel = Label ":" Statement
+/// ```
+fn parse_statement_with_label(s: &mut impl ITokenStream) -> Result<ast::Statement> {
+    let label = parse_label(s)?;
+    s.expect_and_next(|token| matches!(token.kind, TokenKind::Colon))?;
+
+    let statement = parse_statement(s)?;
+    let loc = statement.loc().start.to_owned();
+
+    match statement {
+        StatementOrExpression::Statement(Statement::Each(mut statement)) => {
+            statement.label = Some(label);
+            return Ok(Statement::Each(statement));
+        }
+        StatementOrExpression::Statement(Statement::For(mut statement)) => {
+            statement.label = Some(label);
+            return Ok(Statement::For(statement));
+        }
+        StatementOrExpression::Statement(Statement::Loop(mut statement)) => {
+            statement.label = Some(label);
+            return Ok(Statement::Loop(statement));
+        }
+        _ => {
+            return Err(Box::new(AiScriptSyntaxError::new(
+                "cannot use label for statement other than for / each / while / do-while / loop",
+                loc,
+            )));
+        }
+    }
+}
+
+/// ```abnf
+/// Label = "#" IDENT
+/// ```
+fn parse_label(s: &mut impl ITokenStream) -> Result<Utf16String> {
+    s.expect_and_next(|token| matches!(token.kind, TokenKind::Sharp))?;
+    if s.get_token().has_left_spacing {
+        return Err(Box::new(AiScriptSyntaxError::new(
+            "cannot add spaces after a sharp",
+            s.get_pos().to_owned(),
+        )));
+    }
+    return Ok(s.expect_identifier_and_next()?.raw);
+}
+
+/// ```abnf
+/// Break = "break" [Label]
+/// ```
+fn parse_break(s: &mut impl ITokenStream) -> Result<ast::Break> {
+    let start_pos = s
+        .expect_and_next(|token| matches!(token.kind, TokenKind::BreakKeyword))?
+        .pos;
+    let label = if matches!(s.get_token_kind(), TokenKind::Sharp) {
+        Some(parse_label(s)?)
+    } else {
+        None
+    };
+
+    return Ok(ast::Break {
+        loc: Loc {
+            start: start_pos,
+            end: s.get_pos().to_owned(),
+        },
+        label,
+    });
+}
+
+/// ```abnf
+/// Continue = "continue" [Label]
+/// ```
+fn parse_continue(s: &mut impl ITokenStream) -> Result<ast::Continue> {
+    let start_pos = s
+        .expect_and_next(|token| matches!(token.kind, TokenKind::ContinueKeyword))?
+        .pos;
+    let label = if matches!(s.get_token_kind(), TokenKind::Sharp) {
+        Some(parse_label(s)?)
+    } else {
+        None
+    };
+
+    return Ok(ast::Continue {
+        loc: Loc {
+            start: start_pos,
+            end: s.get_pos().to_owned(),
+        },
+        label,
+    });
+}
+
 /// ```abnf
 /// Attr = "#[" IDENT [StaticExpr] "]"
 /// ```
@@ -460,6 +540,7 @@ fn parse_loop(s: &mut impl ITokenStream) -> Result<ast::Loop> {
             start: start_pos,
             end: s.get_pos().to_owned(),
         },
+        label: None,
         statements,
     });
 }
@@ -483,6 +564,7 @@ fn parse_do_while(s: &mut impl ITokenStream) -> Result<ast::Loop> {
             start: do_start_pos,
             end: end_pos.clone(),
         },
+        label: None,
         statements: vec![
             body,
             StatementOrExpression::from_expr(ast::If {
@@ -505,6 +587,7 @@ fn parse_do_while(s: &mut impl ITokenStream) -> Result<ast::Loop> {
                         start: end_pos.clone(),
                         end: end_pos,
                     },
+                    label: None,
                 })),
                 elseif: Vec::new(),
                 else_statement: None,
@@ -529,6 +612,7 @@ fn parse_while(s: &mut impl ITokenStream) -> Result<ast::Loop> {
             start: start_pos.clone(),
             end: s.get_pos().to_owned(),
         },
+        label: None,
         statements: vec![
             StatementOrExpression::from_expr(ast::If {
                 loc: Loc {
@@ -550,6 +634,7 @@ fn parse_while(s: &mut impl ITokenStream) -> Result<ast::Loop> {
                         start: cond_end_pos.clone(),
                         end: cond_end_pos,
                     },
+                    label: None,
                 })),
                 elseif: Vec::new(),
                 else_statement: None,
@@ -3,6 +3,7 @@ use aiscript_engine_common::{AiScriptSyntaxError, Result};
 use aiscript_engine_lexer::{ITokenStream, TokenKind};
 
 use super::{
+    enter_nested,
     expressions::parse_expr,
     statement::{parse_def_statement, parse_statement},
 };
@@ -52,6 +53,7 @@ pub fn parse_top_level(s: &mut impl ITokenStream) -> Result<Vec<ast::Node>> {
 /// Namespace = "::" IDENT "{" *(VarDef / FnDef / Namespace) "}"
 /// ```
 pub(super) fn parse_namespace(s: &mut impl ITokenStream) -> Result<ast::Namespace> {
+    let _stack_guard = enter_nested(s)?;
     let start_pos = s
         .expect_and_next(|token| matches!(token.kind, TokenKind::Colon2))?
         .pos;
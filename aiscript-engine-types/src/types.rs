@@ -32,13 +32,13 @@ impl TSimple {
     }
 
     pub fn for_name(name: &Utf16Str) -> Option<Self> {
-        match name.as_u16s() {
-            &utf16!("null") => Some(TSimple::Null),
-            &utf16!("bool") => Some(TSimple::Bool),
-            &utf16!("num") => Some(TSimple::Num),
-            &utf16!("str") => Some(TSimple::Str),
-            &utf16!("any") => Some(TSimple::Any),
-            &utf16!("void") => Some(TSimple::Void),
+        match *name.as_u16s() {
+            utf16!("null") => Some(TSimple::Null),
+            utf16!("bool") => Some(TSimple::Bool),
+            utf16!("num") => Some(TSimple::Num),
+            utf16!("str") => Some(TSimple::Str),
+            utf16!("any") => Some(TSimple::Any),
+            utf16!("void") => Some(TSimple::Void),
             _ => None,
         }
     }
@@ -58,7 +58,7 @@ fn get_type_name_by_source(type_source: &ast::TypeSource) -> Utf16String {
     match type_source {
         ast::TypeSource::NamedTypeSource(type_source) => {
             if let Some(inner) = &type_source.inner {
-                let inner = get_type_name_by_source(&inner);
+                let inner = get_type_name_by_source(inner);
                 return type_source.name.to_owned()
                     + utf16!('<')
                     + inner.as_utf16_str()
@@ -68,15 +68,12 @@ fn get_type_name_by_source(type_source: &ast::TypeSource) -> Utf16String {
             }
         }
         ast::TypeSource::FnTypeSource(type_source) => {
-            let mut args = type_source
-                .args
-                .iter()
-                .map(|arg| get_type_name_by_source(arg));
+            let mut args = type_source.args.iter().map(get_type_name_by_source);
             let mut name = Utf16String::from_iter(&utf16!("@("));
             if let Some(first_arg) = args.next() {
                 name += first_arg.as_utf16_str();
             }
-            while let Some(arg) = args.next() {
+            for arg in args {
                 name += Utf16Str::new(&utf16!(", "));
                 name += arg.as_utf16_str();
             }
@@ -94,11 +91,11 @@ pub fn get_type_by_source(type_source: &ast::TypeSource) -> Result<Type> {
             if let Some(ty) = TSimple::for_name(name) {
                 return Ok(ty.into());
             }
-            match name.as_u16s() {
-                &utf16!("arr") => {
+            match *name.as_u16s() {
+                utf16!("arr") => {
                     return Ok(TGeneric::Arr(Box::new(get_inner_type(named_type_source)?)).into())
                 }
-                &utf16!("obj") => {
+                utf16!("obj") => {
                     return Ok(TGeneric::Obj(Box::new(get_inner_type(named_type_source)?)).into())
                 }
                 _ => {}
@@ -0,0 +1,372 @@
+//! 値とJSONの相互変換
+
+use std::{fmt::Display, rc::Rc};
+
+use aiscript_engine_common::{num_to_string, StackGuard, Utf16Str, Utf16String};
+use gc::{Gc, GcCell};
+use utf16_literal::utf16;
+
+use crate::{VObj, Value};
+
+/// 値をJSONに変換できない理由
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToJsonError {
+    /// 配列またはオブジェクトが自身を含んでいる
+    Circular,
+
+    /// JSONで表現できない型の値が含まれている
+    Unsupported(&'static Utf16Str),
+
+    /// 配列またはオブジェクトの入れ子が深すぎる
+    TooDeep,
+}
+
+impl Display for ToJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToJsonError::Circular => write!(f, "Cannot convert circular structure to JSON"),
+            ToJsonError::Unsupported(type_name) => {
+                write!(f, "Unrecognized value type: {}", type_name)
+            }
+            ToJsonError::TooDeep => write!(f, "Maximum call stack size exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for ToJsonError {}
+
+/// 値を`serde_json::Value`に変換します。
+///
+/// 関数は`"<function>"`、有限でない数値は`null`に変換されます。
+/// オブジェクトのキーはJavaScriptのオブジェクトと同じ順序で並びます。
+/// `serde_json`の文字列は不完全なサロゲートを表現できないため、U+FFFDに置き換えられます。
+pub fn value_to_json(value: &Value) -> Result<serde_json::Value, ToJsonError> {
+    let node = Node::new(value, &mut Vec::new())?;
+    return Ok(node.to_json());
+}
+
+/// `serde_json::Value`を値に変換します。
+/// オブジェクトのキーはJavaScriptの`JSON.parse`と同じ順序で並びます。
+pub fn json_to_value(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(value) => Value::Bool(*value),
+        serde_json::Value::Number(value) => Value::Num(value.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(value) => Value::Str(str_to_rc(value)),
+        serde_json::Value::Array(items) => {
+            let items = items.iter().map(json_to_value).collect();
+            Value::Arr(Gc::new(GcCell::new(items)))
+        }
+        serde_json::Value::Object(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| (str_to_rc(key), json_to_value(value)))
+                .collect();
+            let mut obj = VObj::new();
+            obj.0.extend(js_property_order(entries));
+            Value::Obj(Gc::new(GcCell::new(obj)))
+        }
+    }
+}
+
+/// JavaScriptの`JSON.stringify`と同じ形式で値をJSONの文字列に変換します。
+/// `value_to_json`と異なり、不完全なサロゲートはエスケープして保持されます。
+pub fn stringify(value: &Value) -> Result<Utf16String, ToJsonError> {
+    let node = Node::new(value, &mut Vec::new())?;
+    let mut result = Utf16String::new();
+    node.write(&mut result);
+    return Ok(result);
+}
+
+/// 循環参照がないことを確認し、キーをJavaScriptと同じ順序に並べ替えた値の木
+enum Node {
+    Scalar(Value),
+    Arr(Vec<Node>),
+    Obj(Vec<(Rc<[u16]>, Node)>),
+}
+
+impl Node {
+    /// `ancestors`は変換中の配列とオブジェクト
+    fn new(value: &Value, ancestors: &mut Vec<*const ()>) -> Result<Self, ToJsonError> {
+        let stack_guard = StackGuard::new();
+        if stack_guard.exceeded() {
+            return Err(ToJsonError::TooDeep);
+        }
+        match value {
+            Value::Arr(items) => {
+                enter(items, ancestors)?;
+                let items = items
+                    .borrow()
+                    .iter()
+                    .map(|item| Node::new(item, ancestors))
+                    .collect::<Result<Vec<_>, _>>();
+                ancestors.pop();
+                return Ok(Node::Arr(items?));
+            }
+            Value::Obj(obj) => {
+                enter(obj, ancestors)?;
+                let entries = obj
+                    .borrow()
+                    .0
+                    .iter()
+                    .map(|(key, value)| Ok((Rc::clone(key), Node::new(value, ancestors)?)))
+                    .collect::<Result<Vec<_>, _>>();
+                ancestors.pop();
+                return Ok(Node::Obj(js_property_order(entries?)));
+            }
+            Value::Null | Value::Bool(_) | Value::Num(_) | Value::Str(_) | Value::Fn(_) => {
+                return Ok(Node::Scalar(value.clone()));
+            }
+            _ => return Err(ToJsonError::Unsupported(value.type_name())),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Node::Scalar(value) => scalar_to_json(value),
+            Node::Arr(items) => serde_json::Value::Array(items.iter().map(Node::to_json).collect()),
+            Node::Obj(entries) => serde_json::Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (Utf16Str::new(key).to_string(), value.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn write(&self, result: &mut Utf16String) {
+        match self {
+            Node::Scalar(value) => write_scalar(result, value),
+            Node::Arr(items) => {
+                *result += utf16!('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        *result += utf16!(',');
+                    }
+                    item.write(result);
+                }
+                *result += utf16!(']');
+            }
+            Node::Obj(entries) => {
+                *result += utf16!('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        *result += utf16!(',');
+                    }
+                    write_quoted(result, key);
+                    *result += utf16!(':');
+                    value.write(result);
+                }
+                *result += utf16!('}');
+            }
+        }
+    }
+}
+
+/// 配列またはオブジェクトが変換中でなければ、変換中として記録します。
+fn enter<T: gc::Trace + ?Sized>(
+    value: &Gc<GcCell<T>>,
+    ancestors: &mut Vec<*const ()>,
+) -> Result<(), ToJsonError> {
+    let ptr = &**value as *const GcCell<T> as *const ();
+    if ancestors.contains(&ptr) {
+        return Err(ToJsonError::Circular);
+    }
+    ancestors.push(ptr);
+    return Ok(());
+}
+
+fn scalar_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(value) => serde_json::Value::Bool(*value),
+        Value::Num(value) => serde_json::Number::from_f64(*value)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Value::Str(value) => serde_json::Value::String(Utf16Str::new(value).to_string()),
+        Value::Fn(_) => serde_json::Value::String(String::from("<function>")),
+        _ => serde_json::Value::Null,
+    }
+}
+
+fn write_scalar(result: &mut Utf16String, value: &Value) {
+    match value {
+        Value::Bool(true) => *result += Utf16Str::new(&utf16!("true")),
+        Value::Bool(false) => *result += Utf16Str::new(&utf16!("false")),
+        Value::Num(value) if value.is_finite() => *result += num_to_string(*value).as_utf16_str(),
+        Value::Str(value) => write_quoted(result, value),
+        Value::Fn(_) => write_quoted(result, &utf16!("<function>")),
+        _ => *result += Utf16Str::new(&utf16!("null")),
+    }
+}
+
+/// `JSON.stringify`と同じ規則で文字列を引用符で囲みます。
+fn write_quoted(result: &mut Utf16String, value: &[u16]) {
+    *result += utf16!('"');
+    let mut chars = value.iter().copied().peekable();
+    let mut prev_high_surrogate = false;
+    while let Some(char) = chars.next() {
+        let escape = match char {
+            0x08 => Some(utf16!('b')),
+            0x09 => Some(utf16!('t')),
+            0x0A => Some(utf16!('n')),
+            0x0C => Some(utf16!('f')),
+            0x0D => Some(utf16!('r')),
+            0x22 | 0x5C => Some(char),
+            _ => None,
+        };
+        let is_high_surrogate = (0xD800..=0xDBFF).contains(&char);
+        let is_low_surrogate = (0xDC00..=0xDFFF).contains(&char);
+        let is_lone_surrogate = (is_high_surrogate
+            && !chars
+                .peek()
+                .is_some_and(|next| (0xDC00..=0xDFFF).contains(next)))
+            || (is_low_surrogate && !prev_high_surrogate);
+        prev_high_surrogate = is_high_surrogate && !is_lone_surrogate;
+        if let Some(escape) = escape {
+            *result += utf16!('\\');
+            *result += escape;
+        } else if char < 0x20 || is_lone_surrogate {
+            for char in format!("\\u{:04x}", char).encode_utf16() {
+                *result += char;
+            }
+        } else {
+            *result += char;
+        }
+    }
+    *result += utf16!('"');
+}
+
+fn str_to_rc(value: &str) -> Rc<[u16]> {
+    Rc::from(Utf16String::from(value).as_u16s())
+}
+
+/// JavaScriptのオブジェクトと同様に、配列の添字となるキーを昇順で先に並べます。
+/// それ以外のキーは元の順序を保ちます。
+fn js_property_order<T>(entries: Vec<(Rc<[u16]>, T)>) -> Vec<(Rc<[u16]>, T)> {
+    let (mut indices, mut others): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .map(|(key, value)| (array_index(&key), key, value))
+        .partition(|(index, _, _)| index.is_some());
+    indices.sort_by_key(|(index, _, _)| *index);
+    indices.append(&mut others);
+    return indices
+        .into_iter()
+        .map(|(_, key, value)| (key, value))
+        .collect();
+}
+
+/// キーが配列の添字 (0以上2^32-2以下の整数の正規の表記) であればその値を返します。
+fn array_index(key: &[u16]) -> Option<u32> {
+    if key.is_empty() || key.len() > 10 || (key.len() > 1 && key[0] == utf16!('0')) {
+        return None;
+    }
+    let mut value: u64 = 0;
+    for &char in key {
+        if !(utf16!('0')..=utf16!('9')).contains(&char) {
+            return None;
+        }
+        value = value * 10 + (char - utf16!('0')) as u64;
+    }
+    return u32::try_from(value).ok().filter(|value| *value != u32::MAX);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn str(s: &str) -> Value {
+        Value::Str(str_to_rc(s))
+    }
+
+    fn arr(items: Vec<Value>) -> Value {
+        Value::Arr(Gc::new(GcCell::new(items)))
+    }
+
+    fn obj(entries: Vec<(&str, Value)>) -> Value {
+        let mut obj = VObj::new();
+        for (key, value) in entries {
+            obj.0.insert(str_to_rc(key), value);
+        }
+        Value::Obj(Gc::new(GcCell::new(obj)))
+    }
+
+    #[test]
+    fn to_json() {
+        let value = obj(vec![
+            ("b", Value::Num(1.5)),
+            ("a", arr(vec![Value::Null, Value::Bool(true), str("x")])),
+            ("1", Value::Num(f64::NAN)),
+        ]);
+        let json = value_to_json(&value).unwrap();
+        assert_eq!(json, json!({ "1": null, "b": 1.5, "a": [null, true, "x"] }));
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["1", "b", "a"]);
+    }
+
+    #[test]
+    fn from_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(r#"{ "b": 1, "10": 2, "a": [true], "2": null, "01": 3 }"#)
+                .unwrap();
+        let Value::Obj(value) = json_to_value(&json) else {
+            panic!("expected obj");
+        };
+        let keys: Vec<String> = value
+            .borrow()
+            .0
+            .keys()
+            .map(|key| Utf16Str::new(key).to_string())
+            .collect();
+        assert_eq!(keys, ["2", "10", "b", "a", "01"]);
+    }
+
+    #[test]
+    fn stringify_values() {
+        let value = obj(vec![
+            ("b", Value::Num(1.0)),
+            ("a", arr(vec![Value::Null, Value::Num(f64::INFINITY)])),
+            ("s", str("\"\\\n\u{1}")),
+        ]);
+        assert_eq!(
+            stringify(&value).unwrap(),
+            Utf16String::from(r#"{"b":1,"a":[null,null],"s":"\"\\\n\u0001"}"#)
+        );
+    }
+
+    #[test]
+    fn stringify_lone_surrogates() {
+        let value = Value::Str(Rc::from([0xD800, 0xD83E, 0xDD70, 0xDC00]));
+        let mut expected = Utf16String::from(r#""\ud800"#);
+        expected.push(0xD83E);
+        expected.push(0xDD70);
+        expected += Utf16Str::new(&utf16!(r#"\udc00""#));
+        assert_eq!(stringify(&value).unwrap(), expected);
+    }
+
+    #[test]
+    fn circular() {
+        let value = arr(vec![]);
+        let Value::Arr(items) = &value else {
+            unreachable!();
+        };
+        items.borrow_mut().push(value.clone());
+        assert_eq!(stringify(&value), Err(ToJsonError::Circular));
+        assert_eq!(value_to_json(&value), Err(ToJsonError::Circular));
+    }
+
+    #[test]
+    fn shared_reference() {
+        let shared = arr(vec![Value::Num(1.0)]);
+        let value = arr(vec![shared.clone(), shared]);
+        assert_eq!(stringify(&value).unwrap(), Utf16String::from("[[1],[1]]"));
+    }
+
+    #[test]
+    fn unsupported() {
+        assert!(matches!(
+            stringify(&Value::Break),
+            Err(ToJsonError::Unsupported(_))
+        ));
+    }
+}
@@ -1,5 +1,9 @@
+mod json;
+mod repr;
 mod utils;
 mod values;
 
+pub use json::{json_to_value, stringify, value_to_json, ToJsonError};
+pub use repr::{repr_value, val_to_string};
 pub use utils::*;
 pub use values::*;
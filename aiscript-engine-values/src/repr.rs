@@ -0,0 +1,315 @@
+use aiscript_engine_common::{
+    num_to_string, AiScriptBasicError, AiScriptBasicErrorKind, Result, StackGuard, Utf16Str,
+    Utf16String,
+};
+use gc::{Gc, GcCell};
+use utf16_literal::utf16;
+
+use crate::{FnIndex, Value};
+
+/// 値を文字列で表現します。
+/// `literal_like`が真の場合、文字列はリテラルのように引用符で囲まれます。
+/// 配列やオブジェクトの入れ子が深すぎる場合はエラーを返します。
+pub fn repr_value(value: &Value, literal_like: bool) -> Result<Utf16String> {
+    let mut result = Utf16String::new();
+    Repr::default().write(&mut result, value, literal_like)?;
+    return Ok(result);
+}
+
+/// 値を型名とともに文字列で表現します。
+/// `simple`が真の場合、一部の型は型名を省略して表現されます。
+/// 配列の入れ子が深すぎる場合はエラーを返します。
+pub fn val_to_string(value: &Value, simple: bool) -> Result<Utf16String> {
+    if simple {
+        match value {
+            Value::Num(value) => return Ok(num_to_string(*value)),
+            Value::Bool(value) => return Ok(bool_to_string(*value)),
+            Value::Str(value) => {
+                let mut result = Utf16String::from(utf16!('"'));
+                result += Utf16Str::new(value);
+                result += utf16!('"');
+                return Ok(result);
+            }
+            Value::Arr(value) => {
+                let stack_guard = StackGuard::new();
+                if stack_guard.exceeded() {
+                    return Err(nesting_error());
+                }
+                let items = value
+                    .borrow()
+                    .iter()
+                    .map(|item| val_to_string(item, true))
+                    .collect::<Result<Vec<Utf16String>>>()?;
+                let mut result = Utf16String::from(utf16!('['));
+                result += Utf16String::join(&items, Utf16Str::new(&utf16!(", "))).as_utf16_str();
+                result += utf16!(']');
+                return Ok(result);
+            }
+            Value::Null => return Ok(Utf16String::from("(null)")),
+            _ => {}
+        }
+    }
+    let label = match value {
+        Value::Num(value) => Some(num_to_string(*value)),
+        Value::Bool(value) => Some(bool_to_string(*value)),
+        Value::Str(value) => {
+            let mut label = Utf16String::from(utf16!('"'));
+            label += Utf16Str::new(value);
+            label += utf16!('"');
+            Some(label)
+        }
+        Value::Fn(_) | Value::Obj(_) => Some(Utf16String::from("...")),
+        Value::Null => Some(Utf16String::new()),
+        _ => None,
+    };
+    let mut result = Utf16String::from(value.type_name());
+    result += utf16!('<');
+    result += label
+        .unwrap_or_else(|| Utf16String::from("null"))
+        .as_utf16_str();
+    result += utf16!('>');
+    return Ok(result);
+}
+
+/// 入れ子が深すぎて変換できない場合のエラー
+fn nesting_error() -> Box<AiScriptBasicError> {
+    return Box::new(AiScriptBasicError::new(
+        AiScriptBasicErrorKind::Runtime,
+        "Maximum call stack size exceeded",
+        None,
+    ));
+}
+
+fn bool_to_string(value: bool) -> Utf16String {
+    if value {
+        Utf16String::from("true")
+    } else {
+        Utf16String::from("false")
+    }
+}
+
+/// 循環参照を検出しながら値を文字列に変換する。
+#[derive(Default)]
+struct Repr {
+    /// 変換済みの配列とオブジェクト
+    processed: Vec<*const ()>,
+}
+
+impl Repr {
+    /// 配列またはオブジェクトが変換済みかを確認し、未変換なら変換済みとして記録します。
+    fn visit<T: gc::Trace + ?Sized>(&mut self, value: &Gc<GcCell<T>>) -> bool {
+        let ptr = &**value as *const GcCell<T> as *const ();
+        if self.processed.contains(&ptr) {
+            return false;
+        }
+        self.processed.push(ptr);
+        return true;
+    }
+
+    fn write(&mut self, result: &mut Utf16String, value: &Value, literal_like: bool) -> Result<()> {
+        let stack_guard = StackGuard::new();
+        if stack_guard.exceeded() {
+            return Err(nesting_error());
+        }
+        match value {
+            Value::Str(value) => {
+                if literal_like {
+                    *result += utf16!('"');
+                    for &ch in value.iter() {
+                        if matches!(ch, utf16!('"') | utf16!('\\') | utf16!('\r') | utf16!('\n')) {
+                            *result += utf16!('\\');
+                        }
+                        *result += ch;
+                    }
+                    *result += utf16!('"');
+                } else {
+                    *result += Utf16Str::new(value);
+                }
+            }
+            Value::Num(value) => *result += num_to_string(*value).as_utf16_str(),
+            Value::Arr(value) => {
+                if !self.visit(value) {
+                    *result += Utf16Str::new(&utf16!("..."));
+                    return Ok(());
+                }
+                *result += Utf16Str::new(&utf16!("[ "));
+                for (i, item) in value.borrow().iter().enumerate() {
+                    if i > 0 {
+                        *result += Utf16Str::new(&utf16!(", "));
+                    }
+                    self.write(result, item, true)?;
+                }
+                *result += Utf16Str::new(&utf16!(" ]"));
+            }
+            Value::Obj(value) => {
+                if !self.visit(value) {
+                    *result += Utf16Str::new(&utf16!("..."));
+                    return Ok(());
+                }
+                *result += Utf16Str::new(&utf16!("{ "));
+                for (i, (key, item)) in value.borrow().0.iter().enumerate() {
+                    if i > 0 {
+                        *result += Utf16Str::new(&utf16!(", "));
+                    }
+                    *result += Utf16Str::new(key);
+                    *result += Utf16Str::new(&utf16!(": "));
+                    self.write(result, item, true)?;
+                }
+                *result += Utf16Str::new(&utf16!(" }"));
+            }
+            Value::Bool(value) => *result += bool_to_string(*value).as_utf16_str(),
+            Value::Null => *result += Utf16Str::new(&utf16!("null")),
+            Value::Fn(value) => {
+                let value = value.borrow();
+                match value.index {
+                    FnIndex::Native(_) => {
+                        *result += Utf16Str::new(&utf16!("@( ?? ) { native code }"));
+                    }
+                    FnIndex::User(_) => {
+                        *result += Utf16Str::new(&utf16!("@( "));
+                        *result += Utf16String::join(&value.params, Utf16Str::new(&utf16!(", ")))
+                            .as_utf16_str();
+                        *result += Utf16Str::new(&utf16!(" ) { ... }"));
+                    }
+                }
+            }
+            _ => *result += utf16!('?'),
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::VObj;
+
+    use super::*;
+
+    fn str(s: &str) -> Value {
+        Value::Str(Rc::from(Utf16String::from(s).as_u16s()))
+    }
+
+    fn arr(items: Vec<Value>) -> Value {
+        Value::Arr(Gc::new(GcCell::new(items)))
+    }
+
+    #[test]
+    fn repr_primitives() {
+        assert_eq!(
+            repr_value(&str("ai"), false).unwrap(),
+            Utf16String::from("ai")
+        );
+        assert_eq!(
+            repr_value(&str("ai"), true).unwrap(),
+            Utf16String::from("\"ai\"")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(1.0), false).unwrap(),
+            Utf16String::from("1")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(0.5), false).unwrap(),
+            Utf16String::from("0.5")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(-0.0), false).unwrap(),
+            Utf16String::from("0")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(1e21), false).unwrap(),
+            Utf16String::from("1e+21")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(1.5e-7), false).unwrap(),
+            Utf16String::from("1.5e-7")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(f64::NAN), false).unwrap(),
+            Utf16String::from("NaN")
+        );
+        assert_eq!(
+            repr_value(&Value::Num(f64::NEG_INFINITY), false).unwrap(),
+            Utf16String::from("-Infinity")
+        );
+        assert_eq!(
+            repr_value(&Value::Bool(true), false).unwrap(),
+            Utf16String::from("true")
+        );
+        assert_eq!(
+            repr_value(&Value::Null, false).unwrap(),
+            Utf16String::from("null")
+        );
+    }
+
+    #[test]
+    fn repr_escape() {
+        assert_eq!(
+            repr_value(&str("\"a\\b\nc\""), true).unwrap(),
+            Utf16String::from("\"\\\"a\\\\b\\\nc\\\"\"")
+        );
+    }
+
+    #[test]
+    fn repr_collections() {
+        let value = arr(vec![Value::Num(1.0), str("a"), arr(vec![])]);
+        assert_eq!(
+            repr_value(&value, false).unwrap(),
+            Utf16String::from("[ 1, \"a\", [  ] ]")
+        );
+
+        let mut obj = VObj::new();
+        obj.0
+            .insert(Rc::from(Utf16String::from("a").as_u16s()), Value::Null);
+        obj.0
+            .insert(Rc::from(Utf16String::from("b").as_u16s()), str("c"));
+        let value = Value::Obj(Gc::new(GcCell::new(obj)));
+        assert_eq!(
+            repr_value(&value, false).unwrap(),
+            Utf16String::from("{ a: null, b: \"c\" }")
+        );
+    }
+
+    #[test]
+    fn repr_cycle() {
+        let inner = Gc::new(GcCell::new(vec![]));
+        let outer = arr(vec![Value::Arr(inner.clone())]);
+        inner.borrow_mut().push(outer.clone());
+        assert_eq!(
+            repr_value(&outer, false).unwrap(),
+            Utf16String::from("[ [ ... ] ]")
+        );
+
+        let shared = arr(vec![]);
+        let value = arr(vec![shared.clone(), shared]);
+        assert_eq!(
+            repr_value(&value, false).unwrap(),
+            Utf16String::from("[ [  ], ... ]")
+        );
+    }
+
+    #[test]
+    fn to_string() {
+        assert_eq!(
+            val_to_string(&Value::Num(1.0), false).unwrap(),
+            Utf16String::from("num<1>")
+        );
+        assert_eq!(
+            val_to_string(&str("ai"), false).unwrap(),
+            Utf16String::from("str<\"ai\">")
+        );
+        assert_eq!(
+            val_to_string(&Value::Null, false).unwrap(),
+            Utf16String::from("null<>")
+        );
+        assert_eq!(
+            val_to_string(&arr(vec![]), false).unwrap(),
+            Utf16String::from("arr<null>")
+        );
+        assert_eq!(
+            val_to_string(&arr(vec![Value::Num(1.0), Value::Null]), true).unwrap(),
+            Utf16String::from("[1, (null)]")
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use aiscript_engine_common::{AiScriptBasicError, AiScriptBasicErrorKind, Result};
 use gc::{Gc, GcCell};
 
@@ -17,7 +19,7 @@ pub fn require_any(val: &Value) -> Result<Value> {
 
 pub fn require_number(val: &Value) -> Result<f64> {
     if let Value::Num(val) = val {
-        Ok(val.clone())
+        Ok(*val)
     } else {
         Err(Box::new(AiScriptBasicError::new(
             AiScriptBasicErrorKind::Runtime,
@@ -27,9 +29,21 @@ pub fn require_number(val: &Value) -> Result<f64> {
     }
 }
 
+pub fn require_string(val: &Value) -> Result<Rc<[u16]>> {
+    if let Value::Str(val) = val {
+        Ok(Rc::clone(val))
+    } else {
+        Err(Box::new(AiScriptBasicError::new(
+            AiScriptBasicErrorKind::Runtime,
+            format!("Expect string, but got {}.", val.type_name()),
+            None,
+        )))
+    }
+}
+
 pub fn require_boolean(val: &Value) -> Result<bool> {
     if let Value::Bool(val) = val {
-        Ok(val.clone())
+        Ok(*val)
     } else {
         Err(Box::new(AiScriptBasicError::new(
             AiScriptBasicErrorKind::Runtime,
@@ -1,3 +1,6 @@
+// gcのderiveマクロが生成するimplに対する警告
+#![allow(non_local_definitions)]
+
 use std::rc::Rc;
 
 use aiscript_engine_common::{Utf16Str, Utf16String};
@@ -23,6 +26,7 @@ pub enum Value {
 
     Break,
     Continue,
+
     Error(Gc<VError>),
 }
 
@@ -52,33 +56,16 @@ impl PartialEq for Value {
             (Self::Bool(a), Self::Bool(b)) => a == b,
             (Self::Num(a), Self::Num(b)) => a == b,
             (Self::Str(a), Self::Str(b)) => a == b,
-            (Self::Obj(a), Self::Obj(b)) => std::ptr::eq(a, b),
-            (Self::Arr(a), Self::Arr(b)) => std::ptr::eq(a, b),
-            (Self::Fn(a), Self::Fn(b)) => std::ptr::eq(a, b),
-            (Self::Return(a), Self::Return(b)) => std::ptr::eq(a, b),
+            (Self::Obj(a), Self::Obj(b)) => Gc::ptr_eq(a, b),
+            (Self::Arr(a), Self::Arr(b)) => Gc::ptr_eq(a, b),
+            (Self::Fn(a), Self::Fn(b)) => Gc::ptr_eq(a, b),
+            (Self::Return(a), Self::Return(b)) => a == b,
             (Self::Break, Self::Break) => true,
             (Self::Continue, Self::Continue) => true,
-            (Self::Error(a), Self::Error(b)) => std::ptr::eq(a, b),
+            (Self::Error(a), Self::Error(b)) => **a == **b,
             _ => false,
         }
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Null, Self::Null) => false,
-            (Self::Bool(a), Self::Bool(b)) => a != b,
-            (Self::Num(a), Self::Num(b)) => a != b,
-            (Self::Str(a), Self::Str(b)) => a != b,
-            (Self::Obj(a), Self::Obj(b)) => !std::ptr::eq(a, b),
-            (Self::Arr(a), Self::Arr(b)) => !std::ptr::eq(a, b),
-            (Self::Fn(a), Self::Fn(b)) => !std::ptr::eq(a, b),
-            (Self::Return(a), Self::Return(b)) => !std::ptr::eq(a, b),
-            (Self::Break, Self::Break) => false,
-            (Self::Continue, Self::Continue) => false,
-            (Self::Error(a), Self::Error(b)) => !std::ptr::eq(a, b),
-            _ => true,
-        }
-    }
 }
 
 impl Value {
@@ -98,11 +85,26 @@ impl Value {
             Value::Error(_) => Utf16Str::new(&utf16!("error")),
         }
     }
+
+    /// スクリプトのエラー値を作成します。
+    /// ネイティブ関数から、実行を中断させずにエラーを返すために使用します。
+    pub fn error(value: impl Into<Utf16String>, info: Option<Value>) -> Self {
+        Value::Error(Gc::new(VError {
+            value: value.into(),
+            info,
+        }))
+    }
 }
 
 #[derive(Clone, Debug, Finalize)]
 pub struct VObj(pub IndexMap<Rc<[u16]>, Value>);
 
+impl Default for VObj {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VObj {
     pub fn new() -> Self {
         VObj(IndexMap::new())
@@ -122,7 +124,17 @@ pub type VArr = Vec<Value>;
 #[derive(Clone, Debug, Trace, Finalize)]
 pub struct VFn {
     pub index: FnIndex,
-    pub capture: Vec<Value>,
+
+    /// 引数の初期値
+    /// 省略できない引数は`None`
+    pub defaults: Vec<Option<Value>>,
+
+    /// 捕捉した変数
+    pub capture: Vec<Gc<GcCell<Value>>>,
+
+    /// 引数名
+    #[unsafe_ignore_trace]
+    pub params: Rc<[Utf16String]>,
 }
 
 #[derive(Clone, Debug, Trace, Finalize)]
@@ -1,3 +1,4 @@
+pub use aiscript_engine_ast as ast;
 pub use aiscript_engine_common::*;
 pub use aiscript_engine_interpreter::*;
 pub use aiscript_engine_parser::*;
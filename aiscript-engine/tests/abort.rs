@@ -0,0 +1,99 @@
+use std::{cell::RefCell, rc::Rc, thread, time::Duration};
+
+use aiscript_engine::{
+    AbortHandle, Interpreter, InterpreterOpts, Parser, Result, Utf16String, Value,
+};
+
+struct AbortOpts {
+    max_step: Option<usize>,
+    abort_on_out: RefCell<Option<AbortHandle>>,
+}
+
+impl InterpreterOpts for AbortOpts {
+    fn out(&self, _value: Value) {
+        if let Some(handle) = &*self.abort_on_out.borrow() {
+            handle.abort();
+        }
+    }
+
+    fn max_step(&self) -> Option<usize> {
+        self.max_step
+    }
+}
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    return interpreter.run(&ast);
+}
+
+fn opts(max_step: Option<usize>) -> Rc<AbortOpts> {
+    Rc::new(AbortOpts {
+        max_step,
+        abort_on_out: RefCell::new(None),
+    })
+}
+
+#[test]
+fn max_step_exceeded() {
+    let mut interpreter = Interpreter::new(opts(Some(1000)));
+    let err = run(&mut interpreter, "loop {}").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+}
+
+#[test]
+fn max_step_not_exceeded() {
+    let mut interpreter = Interpreter::new(opts(Some(1000)));
+    run(&mut interpreter, "for 10 { 1 + 1 }").unwrap();
+}
+
+#[test]
+fn max_step_exceeded_in_native_fn() {
+    let mut interpreter = Interpreter::new(opts(Some(1000)));
+    let err = run(&mut interpreter, "Core:range(0, 1000000)").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+    let err = run(&mut interpreter, "[1].repeat(1000000)").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+}
+
+#[test]
+fn abort_from_another_thread() {
+    let mut interpreter = Interpreter::new(opts(None));
+    let handle = interpreter.abort_handle();
+    let aborter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.abort();
+    });
+    let err = run(&mut interpreter, "loop {}").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+    aborter.join().unwrap();
+}
+
+#[test]
+fn abort_from_callback() {
+    let opts = opts(None);
+    let mut interpreter = Interpreter::new(Rc::clone(&opts) as Rc<dyn InterpreterOpts>);
+    *opts.abort_on_out.borrow_mut() = Some(interpreter.abort_handle());
+    let err = run(&mut interpreter, "var a = 0\n<: 1\na = 1").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+}
+
+#[test]
+fn stays_aborted() {
+    let mut interpreter = Interpreter::new(opts(None));
+    interpreter.abort();
+    let err = run(&mut interpreter, "1").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+}
+
+#[test]
+fn abort_during_sleep() {
+    let mut interpreter = Interpreter::new(opts(None));
+    let handle = interpreter.abort_handle();
+    let aborter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.abort();
+    });
+    let err = run(&mut interpreter, "Core:sleep(2147483647)").unwrap_err();
+    assert_eq!(err.name(), "Aborted");
+    aborter.join().unwrap();
+}
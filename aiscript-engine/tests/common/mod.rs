@@ -1,23 +1,67 @@
+#![allow(dead_code)]
+
 use std::{cell::RefCell, rc::Rc};
 
-use aiscript_engine::{Interpreter, InterpreterOpts, Parser, Result, Utf16String, Value};
+use aiscript_engine::{
+    Clock, Interpreter, InterpreterOpts, Parser, RandomSource, Result, Utf16String, Value,
+};
+use gc::{Gc, GcCell};
 
 struct TestOpts {
     result: Rc<RefCell<Value>>,
+    clock: Option<Rc<dyn Clock>>,
+    random_source: Option<Rc<dyn RandomSource>>,
 }
 
 impl InterpreterOpts for TestOpts {
     fn out(&self, value: Value) {
         *self.result.borrow_mut() = value;
     }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        match &self.clock {
+            Some(clock) => Rc::clone(clock),
+            None => Rc::new(aiscript_engine::SystemClock),
+        }
+    }
+
+    fn random_source(&self) -> Rc<dyn RandomSource> {
+        match &self.random_source {
+            Some(random_source) => Rc::clone(random_source),
+            None => Rc::new(aiscript_engine::DefaultRandomSource),
+        }
+    }
 }
 
 pub(crate) fn exe(source: &str) -> Result<Value> {
+    return exe_with(source, None, None);
+}
+
+/// 時計を差し替えて実行する。
+pub(crate) fn exe_with_clock(source: &str, clock: Option<Rc<dyn Clock>>) -> Result<Value> {
+    return exe_with(source, clock, None);
+}
+
+/// 乱数源を差し替えて実行する。
+pub(crate) fn exe_with_random_source(
+    source: &str,
+    random_source: Rc<dyn RandomSource>,
+) -> Result<Value> {
+    return exe_with(source, None, Some(random_source));
+}
+
+fn exe_with(
+    source: &str,
+    clock: Option<Rc<dyn Clock>>,
+    random_source: Option<Rc<dyn RandomSource>>,
+) -> Result<Value> {
     let mut parser = Parser::new();
     let ast = parser.parse(&Utf16String::from(source))?;
     let result = Rc::new(RefCell::new(Value::Uninitialized));
     let opts: Rc<dyn InterpreterOpts> = Rc::new(TestOpts {
         result: Rc::clone(&result),
+        clock,
+        random_source,
     });
     let mut interpreter = Interpreter::new(Rc::clone(&opts));
     interpreter.run(&ast)?;
@@ -35,3 +79,37 @@ pub(crate) fn num(value: f64) -> Value {
 pub(crate) fn str(s: &str) -> Value {
     Value::Str(Rc::from(Utf16String::from(s).as_u16s()))
 }
+
+pub(crate) fn arr(values: impl IntoIterator<Item = Value>) -> Value {
+    Value::Arr(Gc::new(GcCell::new(values.into_iter().collect())))
+}
+
+/// 配列やオブジェクトの中身を再帰的に比較する。
+pub(crate) fn deep_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Arr(left), Value::Arr(right)) => {
+            let left = left.borrow();
+            let right = right.borrow();
+            left.len() == right.len() && left.iter().zip(right.iter()).all(|(l, r)| deep_eq(l, r))
+        }
+        (Value::Obj(left), Value::Obj(right)) => {
+            let left = left.borrow();
+            let right = right.borrow();
+            left.0.len() == right.0.len()
+                && left
+                    .0
+                    .iter()
+                    .all(|(key, l)| right.0.get(key).is_some_and(|r| deep_eq(l, r)))
+        }
+        (left, right) => left == right,
+    }
+}
+
+pub(crate) fn assert_deep_eq(left: Value, right: Value) {
+    assert!(
+        deep_eq(&left, &right),
+        "assertion failed\n  left: {:?}\n right: {:?}",
+        left,
+        right
+    );
+}
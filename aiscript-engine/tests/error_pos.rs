@@ -0,0 +1,99 @@
+mod common;
+
+use aiscript_engine::{Position, StackFrame, STACK_TRACE_LIMIT};
+use common::exe;
+
+fn at(line: usize, column: usize) -> Option<Position> {
+    Some(Position::At { line, column })
+}
+
+fn frame(name: Option<&str>, pos: Option<Position>) -> StackFrame {
+    StackFrame {
+        name: name.map(str::to_string),
+        pos,
+    }
+}
+
+#[test]
+fn runtime_error() {
+    let err = exe("let a = 1\n<: a + \"x\"").unwrap_err();
+    assert_eq!(err.pos(), at(2, 6));
+}
+
+#[test]
+fn no_such_variable() {
+    let err = exe("let a = 1\n<: x").unwrap_err();
+    assert_eq!(err.message(), "No such variable 'x' in scope '<root>'");
+    assert_eq!(err.pos(), at(2, 4));
+}
+
+#[test]
+fn jump_statement() {
+    let err = exe("for 1 {}\nbreak").unwrap_err();
+    assert_eq!(err.name(), "Syntax");
+    assert_eq!(err.pos(), at(2, 1));
+
+    let err = exe("for 1 {\n  @f() { continue }\n}").unwrap_err();
+    assert_eq!(err.name(), "Syntax");
+    assert_eq!(err.pos(), at(2, 10));
+
+    let err = exe("#a: for 1 {\n  break #b\n}").unwrap_err();
+    assert_eq!(err.message(), "label \"b\" is not defined");
+    assert_eq!(err.pos(), at(2, 3));
+}
+
+#[test]
+fn stack() {
+    let err = exe(r#"
+        @f(x) {
+            x + "a"
+        }
+        @g() {
+            f(1)
+        }
+        <: g()
+        "#)
+    .unwrap_err();
+    assert_eq!(err.pos(), at(3, 15));
+    assert_eq!(
+        err.stack(),
+        [
+            frame(Some("f"), at(3, 15)),
+            frame(Some("g"), at(6, 14)),
+            frame(None, at(8, 13)),
+        ]
+    );
+}
+
+#[test]
+fn stack_through_native_fn() {
+    let err = exe("[1].map(@(x) { x.foo() })").unwrap_err();
+    assert_eq!(err.stack(), [frame(None, at(1, 17)), frame(None, at(1, 8))]);
+}
+
+#[test]
+fn long_stack() {
+    let err = exe("@f() {\n  f()\n}\nf()").unwrap_err();
+    assert_eq!(err.message(), "Maximum call stack size exceeded");
+    let stack = err.stack();
+    assert_eq!(stack.len(), STACK_TRACE_LIMIT * 2);
+    assert!(err.omitted_frames() > 0);
+    assert_eq!(stack[0], frame(Some("f"), at(2, 4)));
+    assert_eq!(stack[stack.len() - 1], frame(None, at(4, 2)));
+
+    let display = format!("{:?}", err);
+    let marker = format!(
+        "\n    at f (2:4)\n    ... {} more frames\n    at f (2:4)\n",
+        err.omitted_frames()
+    );
+    assert!(display.contains(&marker), "{}", display);
+}
+
+#[test]
+fn display() {
+    let err = exe("@f() {\n  1 + true\n}\nf()").unwrap_err();
+    assert_eq!(
+        format!("{:?}", err),
+        "Runtime: Expect number, but got bool. (2:5)\n    at f (2:5)\n    at <anonymous> (4:2)"
+    );
+}
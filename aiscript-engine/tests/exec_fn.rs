@@ -0,0 +1,107 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use aiscript_engine::{Interpreter, InterpreterOpts, Parser, Result, Utf16String, Value};
+use common::{num, str};
+
+struct NoOut;
+
+impl InterpreterOpts for NoOut {
+    fn out(&self, _value: Value) {}
+}
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    return interpreter.run(&ast);
+}
+
+/// `Mk:on`で登録されたコールバックを保持するインタプリタ
+fn interpreter_with_handler() -> (Interpreter, Rc<RefCell<Option<Value>>>) {
+    let handler = Rc::new(RefCell::new(None));
+    let on = Rc::clone(&handler);
+    let interpreter = Interpreter::builder(Rc::new(NoOut))
+        .function("Mk:on", move |args, _| {
+            *on.borrow_mut() = args.into_iter().next();
+            Ok(Value::Null)
+        })
+        .build();
+    return (interpreter, handler);
+}
+
+#[test]
+fn callback_after_run() {
+    let (mut interpreter, handler) = interpreter_with_handler();
+    run(&mut interpreter, "Mk:on(@(x) { x * 2 })").unwrap();
+    let handler = handler.borrow().clone().unwrap();
+    assert_eq!(
+        interpreter.exec_fn(&handler, vec![num(21.0)]).unwrap(),
+        num(42.0)
+    );
+}
+
+#[test]
+fn callback_keeps_state() {
+    let (mut interpreter, handler) = interpreter_with_handler();
+    run(
+        &mut interpreter,
+        r#"
+        var count = 0
+        Mk:on(@(x) {
+            count += x
+            count
+        })
+        "#,
+    )
+    .unwrap();
+    let handler = handler.borrow().clone().unwrap();
+    assert_eq!(
+        interpreter.exec_fn(&handler, vec![num(1.0)]).unwrap(),
+        num(1.0)
+    );
+    assert_eq!(
+        interpreter.exec_fn(&handler, vec![num(2.0)]).unwrap(),
+        num(3.0)
+    );
+}
+
+#[test]
+fn callback_after_another_run() {
+    let mut interpreter = Interpreter::new(Rc::new(NoOut));
+    let first = run(&mut interpreter, r#"let a = "first"; @() { a }"#).unwrap();
+    let second = run(&mut interpreter, r#"let a = "second"; @() { a }"#).unwrap();
+    assert_eq!(interpreter.exec_fn(&first, vec![]).unwrap(), str("first"));
+    assert_eq!(interpreter.exec_fn(&second, vec![]).unwrap(), str("second"));
+}
+
+#[test]
+fn registered_callback_after_another_run() {
+    let (mut interpreter, handler) = interpreter_with_handler();
+    run(&mut interpreter, r#"let a = "first"; Mk:on(@() { a })"#).unwrap();
+    for _ in 0..3 {
+        run(&mut interpreter, r#"let a = "second"; @() { a }"#).unwrap();
+    }
+    let handler = handler.borrow().clone().unwrap();
+    assert_eq!(interpreter.exec_fn(&handler, vec![]).unwrap(), str("first"));
+}
+
+#[test]
+fn callback_from_native_function() {
+    let mut interpreter = Interpreter::builder(Rc::new(NoOut))
+        .function("Mk:call", |args, vm| {
+            let mut args = args.into_iter();
+            let f = args.next().unwrap_or(Value::Null);
+            vm.exec_fn(&f, args.collect())
+        })
+        .build();
+    assert_eq!(
+        run(&mut interpreter, "Mk:call(@(a, b) { a + b }, 1, 2)").unwrap(),
+        num(3.0)
+    );
+}
+
+#[test]
+fn not_a_function() {
+    let mut interpreter = Interpreter::new(Rc::new(NoOut));
+    assert!(interpreter.exec_fn(&num(1.0), vec![]).is_err());
+}
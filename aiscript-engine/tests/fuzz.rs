@@ -0,0 +1,364 @@
+//! 任意の入力に対してホストがパニックしないことを確かめるテスト
+//!
+//! パースに成功したプログラムは実行し、実行結果かエラーのいずれかが返ることを確認する。
+//! 無限ループを避けるため、生成するプログラムに`loop`や`while`は含めない。
+
+mod common;
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use common::exe;
+
+/// 再現性のある疑似乱数生成器 (xorshift64)
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        return x;
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        return (self.next() % n as u64) as usize;
+    }
+
+    fn chance(&mut self, n: usize) -> bool {
+        return self.below(n) == 0;
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        return items[self.below(items.len())];
+    }
+}
+
+const VARS: &[&str] = &["a", "b", "c", "x"];
+
+const BINARY_OPS: &[&str] = &[
+    "+", "-", "*", "/", "%", "^", "==", "!=", "<", "<=", ">", ">=", "&&", "||",
+];
+
+const CORE_FNS: &[&str] = &[
+    "Core:not", "Core:eq", "Core:add", "Core:sub", "Core:mul", "Core:pow", "Core:div", "Core:mod",
+    "Core:gt", "Core:lt", "Core:and", "Core:or",
+];
+
+/// 文法に沿ったプログラムを生成する。
+/// ユーザー関数は先に定義されたものしか呼ばないため、無限再帰は起こらない。
+struct Generator {
+    rng: Rng,
+    fn_count: usize,
+    loop_depth: usize,
+}
+
+impl Generator {
+    fn expr(&mut self, depth: usize) -> String {
+        if depth == 0 {
+            return self.atom();
+        }
+        let d = depth - 1;
+        return match self.rng.below(17) {
+            0 | 1 => self.atom(),
+            2 => format!(
+                "({} {} {})",
+                self.expr(d),
+                self.rng.pick(BINARY_OPS),
+                self.expr(d)
+            ),
+            3 => format!("!{}", self.expr(d)),
+            4 => format!("[{}, {}]", self.expr(d), self.expr(d)),
+            5 => format!("{{ k: {}, l: {} }}", self.expr(d), self.expr(d)),
+            6 => format!("{}[{}]", self.atom(), self.expr(d)),
+            7 => format!("{}.k", self.atom()),
+            8 => format!(
+                "{}({}, {})",
+                self.rng.pick(CORE_FNS),
+                self.expr(d),
+                self.expr(d)
+            ),
+            9 if self.fn_count > 0 => {
+                let index = self.rng.below(self.fn_count);
+                format!("f{}({})", index, self.expr(d))
+            }
+            10 => format!("if {} {} else {}", self.expr(d), self.expr(d), self.expr(d)),
+            11 => format!("eval {{ {} }}", self.block(d)),
+            12 => format!(
+                "match {} {{ case {} => {} default => {} }}",
+                self.expr(d),
+                self.expr(d),
+                self.expr(d),
+                self.expr(d)
+            ),
+            13 => format!("`t{{{}}}t`", self.rng.pick(VARS)),
+            14 => format!("@(p) {{ {} }}", self.expr(d)),
+            15 => format!("{}(p)", self.rng.pick(VARS)),
+            _ => self.atom(),
+        };
+    }
+
+    fn atom(&mut self) -> String {
+        return match self.rng.below(9) {
+            0 => format!("{}", self.rng.below(4)),
+            1 => "0.5".to_string(),
+            2 => "\"s\"".to_string(),
+            3 => "true".to_string(),
+            4 => "false".to_string(),
+            5 => "null".to_string(),
+            6 => "[]".to_string(),
+            7 => "Core:v".to_string(),
+            _ => self.rng.pick(VARS).to_string(),
+        };
+    }
+
+    fn statement(&mut self, depth: usize) -> String {
+        let d = depth.saturating_sub(1);
+        return match self.rng.below(15) {
+            0 => format!("let {} = {}", self.rng.pick(VARS), self.expr(d)),
+            1 => format!("var {} = {}", self.rng.pick(VARS), self.expr(d)),
+            2 => format!("{} = {}", self.rng.pick(VARS), self.expr(d)),
+            3 => format!("{} += {}", self.rng.pick(VARS), self.expr(d)),
+            4 => format!(
+                "{}[{}] = {}",
+                self.rng.pick(VARS),
+                self.atom(),
+                self.expr(d)
+            ),
+            5 => format!("<: {}", self.expr(d)),
+            6 => format!("if {} {{ {} }}", self.expr(d), self.block(d)),
+            7 => format!(
+                "for let {}, {} {{ {} }}",
+                self.rng.pick(VARS),
+                self.rng.below(4),
+                self.loop_body(d)
+            ),
+            8 => format!("for {} {{ {} }}", self.rng.below(4), self.loop_body(d)),
+            9 => format!(
+                "each let {}, [{}, {}] {{ {} }}",
+                self.rng.pick(VARS),
+                self.atom(),
+                self.atom(),
+                self.loop_body(d)
+            ),
+            // ループ外のbreakやcontinueもエラーとして扱われることを確認するため、まれに生成する
+            10 if self.loop_depth > 0 || self.rng.chance(8) => "break".to_string(),
+            11 if self.loop_depth > 0 || self.rng.chance(8) => "continue".to_string(),
+            12 if self.rng.chance(4) => format!("return {}", self.expr(d)),
+            13 if depth > 0 && self.fn_count < 4 => {
+                let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                let body = self.block(d);
+                self.loop_depth = loop_depth;
+                let name = format!("f{}", self.fn_count);
+                self.fn_count += 1;
+                format!("@{}(p) {{ {} }}", name, body)
+            }
+            _ => self.expr(d),
+        };
+    }
+
+    fn loop_body(&mut self, depth: usize) -> String {
+        self.loop_depth += 1;
+        let body = self.block(depth);
+        self.loop_depth -= 1;
+        return body;
+    }
+
+    fn block(&mut self, depth: usize) -> String {
+        let len = self.rng.below(4);
+        return (0..len)
+            .map(|_| self.statement(depth))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    fn program(&mut self) -> String {
+        self.fn_count = 0;
+        let mut source: String = VARS
+            .iter()
+            .map(|name| format!("var {} = {}\n", name, self.rng.below(4)))
+            .collect();
+        for _ in 0..1 + self.rng.below(8) {
+            source += &self.statement(3);
+            source.push('\n');
+        }
+        return source;
+    }
+}
+
+const TOKENS: &[&str] = &[
+    "0", "1", "2", "0.5", "\"s\"", "'t'", "true", "false", "null", "a", "b", "f", "Core:add",
+    "Core:v", "let", "var", "if", "elif", "else", "eval", "match", "case", "default", "=>", "for",
+    "each", "exists", "@", "return", "break", "continue", "(", ")", "{", "}", "[", "]", ",", ":",
+    "::", ";", "=", "+=", "-=", "+", "-", "*", "/", "%", "^", "==", "!=", "<", "<=", ">", ">=",
+    "&&", "||", "!", ".", "#", "###", "<:", "\n", "`", "//", "/*", "*/", "\\",
+];
+
+/// ループを作らない文字の中からランダムに選んだ文字
+const MUTATION_CHARS: &[char] = &[
+    '(',
+    ')',
+    '{',
+    '}',
+    '[',
+    ']',
+    ':',
+    ';',
+    ',',
+    '.',
+    '#',
+    '@',
+    '"',
+    '\'',
+    '`',
+    '<',
+    '>',
+    '=',
+    '!',
+    '+',
+    '-',
+    '*',
+    '/',
+    '^',
+    '%',
+    '&',
+    '|',
+    '?',
+    '\\',
+    '0',
+    '1',
+    '9',
+    ' ',
+    '\n',
+    '\u{3042}',
+    '\u{1F600}',
+];
+
+fn token_soup(rng: &mut Rng) -> String {
+    let len = 1 + rng.below(20);
+    let mut source = String::new();
+    for _ in 0..len {
+        source += rng.pick(TOKENS);
+        if !rng.chance(4) {
+            source.push(' ');
+        }
+    }
+    return source;
+}
+
+fn mutate(rng: &mut Rng, source: &str) -> String {
+    let mut chars: Vec<char> = source.chars().collect();
+    for _ in 0..1 + rng.below(4) {
+        let pos = rng.below(chars.len() + 1);
+        match rng.below(3) {
+            0 if pos < chars.len() => {
+                chars.remove(pos);
+            }
+            1 if pos < chars.len() => {
+                chars[pos] = MUTATION_CHARS[rng.below(MUTATION_CHARS.len())];
+            }
+            _ => chars.insert(pos, MUTATION_CHARS[rng.below(MUTATION_CHARS.len())]),
+        }
+    }
+    return chars.into_iter().collect();
+}
+
+/// パースから実行までの間にパニックしないことを確認する。
+fn assert_no_panic(source: &str) {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = exe(source);
+    }));
+    if result.is_err() {
+        panic!("panicked on source:\n{}", source);
+    }
+}
+
+#[test]
+fn generated_programs() {
+    let mut generator = Generator {
+        rng: Rng(0x2545_f491_4f6c_dd1d),
+        fn_count: 0,
+        loop_depth: 0,
+    };
+    for _ in 0..2000 {
+        let source = generator.program();
+        assert_no_panic(&source);
+    }
+}
+
+#[test]
+fn mutated_programs() {
+    let mut generator = Generator {
+        rng: Rng(0x9e37_79b9_7f4a_7c15),
+        fn_count: 0,
+        loop_depth: 0,
+    };
+    let mut rng = Rng(0xdead_beef_cafe_babe);
+    for _ in 0..2000 {
+        let source = mutate(&mut rng, &generator.program());
+        assert_no_panic(&source);
+    }
+}
+
+#[test]
+fn token_soups() {
+    let mut rng = Rng(0x1234_5678_9abc_def0);
+    for _ in 0..5000 {
+        let source = token_soup(&mut rng);
+        assert_no_panic(&source);
+    }
+}
+
+/// テストスレッドの既定のスタックでも、深い再帰はスタックオーバーフローせずにエラーになる。
+#[test]
+fn deep_recursion() {
+    for source in [
+        "@f() { f() }\nf()",
+        "@f(n) { if true { if true { f(n + 1) } } }\nf(0)",
+        "@f() { [1].map(@(x) { f() }) }\nf()",
+    ] {
+        let err = exe(source).unwrap_err();
+        assert_eq!(err.name(), "Runtime");
+        assert_eq!(err.message(), "Maximum call stack size exceeded");
+    }
+}
+
+/// 深く入れ子になった式は、スタックオーバーフローせずにエラーになる。
+#[test]
+fn deep_nesting() {
+    let depth = 5000;
+    for source in [
+        format!("<: {}1{}", "(".repeat(depth), ")".repeat(depth)),
+        format!("<: {}1{}", "[".repeat(depth), "]".repeat(depth)),
+        format!("<: {}1", "-".repeat(depth)),
+        format!("<: {}1", "!".repeat(depth)),
+        format!("<: {}1{}", "`{".repeat(depth), "}`".repeat(depth)),
+        format!("<: {}1{}", "{a: ".repeat(depth), "}".repeat(depth)),
+        format!("{}1{}", "if true { ".repeat(depth), " }".repeat(depth)),
+        format!("{}{}", "@() { ".repeat(depth), " }".repeat(depth)),
+        format!("let x: {}num = 1", "arr<".repeat(depth)),
+        format!("{}{}", ":: a { ".repeat(depth), " }".repeat(depth)),
+    ] {
+        let err = exe(&source).unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+    }
+}
+
+/// 実行時に作られた深く入れ子の配列も、文字列への変換でスタックオーバーフローせずにエラーになる。
+/// GCによる走査は再帰的に行われるため、十分なスタックを持つスレッドで実行する。
+#[test]
+fn deeply_nested_values() {
+    let thread = std::thread::Builder::new().stack_size(64 * 1024 * 1024);
+    let handle = thread
+        .spawn(|| {
+            for output in ["`{a}`", "Core:to_str(a)", "Json:stringify(a)"] {
+                let source = format!("var a = []\nfor 100000 {{ a = [a] }}\n<: {output}");
+                let err = exe(&source).unwrap_err();
+                assert_eq!(err.name(), "Runtime");
+                assert_eq!(err.message(), "Maximum call stack size exceeded");
+            }
+        })
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use aiscript_engine::{
+    require_number, AiScriptBasicError, AiScriptBasicErrorKind, Interpreter, InterpreterOpts,
+    LibraryValue, Parser, Result, Utf16String, VObj, Value,
+};
+use common::{num, str};
+use gc::{Gc, GcCell};
+use indexmap::IndexMap;
+
+struct HostOpts {
+    result: Rc<RefCell<Value>>,
+}
+
+impl InterpreterOpts for HostOpts {
+    fn out(&self, value: Value) {
+        *self.result.borrow_mut() = value;
+    }
+}
+
+fn opts() -> (Rc<dyn InterpreterOpts>, Rc<RefCell<Value>>) {
+    let result = Rc::new(RefCell::new(Value::Uninitialized));
+    let opts = Rc::new(HostOpts {
+        result: Rc::clone(&result),
+    });
+    return (opts, result);
+}
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<()> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    interpreter.run(&ast)?;
+    return Ok(());
+}
+
+#[test]
+fn constants() {
+    let (opts, result) = opts();
+    let mut interpreter = Interpreter::builder(opts)
+        .value("Mk:num", LibraryValue::Num(42.0))
+        .value("Mk:str", LibraryValue::Str(Utf16String::from("hoge")))
+        .value("Mk:bool", LibraryValue::Bool(true))
+        .value("Mk:nothing", LibraryValue::Null)
+        .build();
+    run(
+        &mut interpreter,
+        r#"<: `{Mk:num} {Mk:str} {Mk:bool} {Mk:nothing}`"#,
+    )
+    .unwrap();
+    assert_eq!(*result.borrow(), str("42 hoge true null"));
+}
+
+#[test]
+fn function() {
+    let (opts, result) = opts();
+    let mut interpreter = Interpreter::builder(opts)
+        .function("Mk:double", |args, _| {
+            let value = require_number(args.first().unwrap_or(&Value::Uninitialized))?;
+            Ok(Value::Num(value * 2.0))
+        })
+        .build();
+    run(&mut interpreter, "<: Mk:double(21)").unwrap();
+    assert_eq!(*result.borrow(), num(42.0));
+}
+
+#[test]
+fn function_with_state() {
+    let (opts, result) = opts();
+    let count = Rc::new(RefCell::new(0.0));
+    let counter = Rc::clone(&count);
+    let mut interpreter = Interpreter::builder(opts)
+        .function("Mk:count", move |_, _| {
+            *counter.borrow_mut() += 1.0;
+            Ok(Value::Num(*counter.borrow()))
+        })
+        .build();
+    run(&mut interpreter, "Mk:count()\nMk:count()\n<: Mk:count()").unwrap();
+    assert_eq!(*result.borrow(), num(3.0));
+    assert_eq!(*count.borrow(), 3.0);
+}
+
+#[test]
+fn function_error() {
+    let (opts, _) = opts();
+    let mut interpreter = Interpreter::builder(opts)
+        .function("Mk:fail", |_, _| {
+            Err(Box::new(AiScriptBasicError::new(
+                AiScriptBasicErrorKind::Runtime,
+                "failed",
+                None,
+            )))
+        })
+        .build();
+    let err = run(&mut interpreter, "Mk:fail()").unwrap_err();
+    assert_eq!(err.message(), "failed");
+}
+
+#[test]
+fn obj() {
+    let (opts, result) = opts();
+    let obj = Gc::new(GcCell::new(VObj(IndexMap::from([(
+        Rc::from(Utf16String::from("a").as_u16s()),
+        Value::Num(1.0),
+    )]))));
+    let mut interpreter = Interpreter::builder(opts)
+        .value("Mk:obj", LibraryValue::Obj(Gc::clone(&obj)))
+        .build();
+    run(&mut interpreter, "Mk:obj.b = 2\n<: Mk:obj.a").unwrap();
+    assert_eq!(*result.borrow(), num(1.0));
+    // スクリプトからの変更がホストに反映される
+    let b = obj
+        .borrow()
+        .0
+        .get(Utf16String::from("b").as_u16s())
+        .cloned();
+    assert_eq!(b, Some(num(2.0)));
+}
+
+#[test]
+fn arr() {
+    let (opts, result) = opts();
+    let arr = Gc::new(GcCell::new(vec![Value::Num(1.0), Value::Num(2.0)]));
+    let mut interpreter = Interpreter::builder(opts)
+        .value("Mk:arr", LibraryValue::Arr(arr))
+        .build();
+    run(&mut interpreter, "<: Mk:arr[1]").unwrap();
+    assert_eq!(*result.borrow(), num(2.0));
+}
+
+#[test]
+fn function_returns_error_value() {
+    let (opts, result) = opts();
+    let mut interpreter = Interpreter::builder(opts)
+        .function("Mk:fail", |_, _| Ok(Value::error("failed", Some(num(1.0)))))
+        .build();
+    run(
+        &mut interpreter,
+        r#"
+        let e = Mk:fail()
+        <: `{Core:type(e)} {e.name} {e.info}`
+        "#,
+    )
+    .unwrap();
+    assert_eq!(*result.borrow(), str("error failed 1"));
+}
+
+#[test]
+fn error_value() {
+    let (opts, result) = opts();
+    let error = match Value::error("hoge", None) {
+        Value::Error(error) => error,
+        _ => unreachable!(),
+    };
+    let mut interpreter = Interpreter::builder(opts)
+        .value("Mk:error", LibraryValue::Error(error))
+        .build();
+    run(&mut interpreter, "<: Mk:error.name").unwrap();
+    assert_eq!(*result.borrow(), str("hoge"));
+}
+
+#[test]
+fn in_namespace_cannot_be_assigned() {
+    let (opts, _) = opts();
+    let mut interpreter = Interpreter::builder(opts)
+        .value("Mk:num", LibraryValue::Num(1.0))
+        .build();
+    assert!(run(&mut interpreter, "Mk:num = 2").is_err());
+}
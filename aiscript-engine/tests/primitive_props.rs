@@ -0,0 +1,473 @@
+mod common;
+
+mod str {
+    use aiscript_engine::Value;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn len() {
+        assert_eq!(exe(r#"<: "abc".len"#).unwrap(), num(3.0));
+        assert_eq!(exe(r#"<: "👍🏽🍆🌮".len"#).unwrap(), num(3.0));
+    }
+
+    #[test]
+    fn to_num() {
+        assert_eq!(exe(r#"<: "123".to_num()"#).unwrap(), num(123.0));
+        assert_eq!(exe(r#"<: " 12px".to_num()"#).unwrap(), num(12.0));
+        assert_eq!(exe(r#"<: "hoge".to_num()"#).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn arr_conversions() {
+        assert_deep_eq(
+            exe(r#"<: "👍🏽🍆🌮".to_arr()"#).unwrap(),
+            arr([str("👍🏽"), str("🍆"), str("🌮")]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "👍🏽🍆🌮".to_unicode_arr()"#).unwrap(),
+            arr([str("👍"), str("🏽"), str("🍆"), str("🌮")]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "👍🏽🍆🌮".to_unicode_codepoint_arr()"#).unwrap(),
+            arr([num(128077.0), num(127997.0), num(127814.0), num(127790.0)]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "abc".to_char_arr()"#).unwrap(),
+            arr([str("a"), str("b"), str("c")]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "abc".to_charcode_arr()"#).unwrap(),
+            arr([num(97.0), num(98.0), num(99.0)]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "aあ".to_utf8_byte_arr()"#).unwrap(),
+            arr([num(97.0), num(227.0), num(129.0), num(130.0)]),
+        );
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            exe(r#"<: "hello world".replace("o", "0")"#).unwrap(),
+            str("hell0 w0rld")
+        );
+    }
+
+    #[test]
+    fn index_of() {
+        assert_eq!(exe(r#"<: "abcabc".index_of("c")"#).unwrap(), num(2.0));
+        assert_eq!(exe(r#"<: "abcabc".index_of("c", 3)"#).unwrap(), num(5.0));
+        assert_eq!(
+            exe(r#"<: "abcabc".index_of("a", 0 - 2)"#).unwrap(),
+            num(-1.0)
+        );
+        assert_eq!(exe(r#"<: "abc".index_of("d")"#).unwrap(), num(-1.0));
+    }
+
+    #[test]
+    fn incl() {
+        assert_eq!(exe(r#"<: "abc".incl("b")"#).unwrap(), bool(true));
+        assert_eq!(exe(r#"<: "abc".incl("d")"#).unwrap(), bool(false));
+    }
+
+    #[test]
+    fn trim_and_case() {
+        assert_eq!(exe(r#"<: "  abc  ".trim()"#).unwrap(), str("abc"));
+        assert_eq!(exe(r#"<: "aBc".upper()"#).unwrap(), str("ABC"));
+        assert_eq!(exe(r#"<: "aBc".lower()"#).unwrap(), str("abc"));
+    }
+
+    #[test]
+    fn split() {
+        assert_deep_eq(
+            exe(r#"<: "a,b,c".split(",")"#).unwrap(),
+            arr([str("a"), str("b"), str("c")]),
+        );
+        assert_deep_eq(
+            exe(r#"<: "👍🏽🍆".split()"#).unwrap(),
+            arr([str("👍🏽"), str("🍆")]),
+        );
+    }
+
+    #[test]
+    fn slice() {
+        assert_eq!(exe(r#"<: "abcdef".slice(1, 3)"#).unwrap(), str("bc"));
+        assert_eq!(exe(r#"<: "👍🏽🍆🌮".slice(1, 2)"#).unwrap(), str("🍆"));
+        assert_eq!(exe(r#"<: "abc".slice(2, 1)"#).unwrap(), str(""));
+        assert_eq!(exe(r#"<: "abc".slice(0 - 1, 3)"#).unwrap(), str("c"));
+        assert_eq!(
+            exe(r#"<: "👍🏽🍆🌮".slice(0 - 2, 0 - 1)"#).unwrap(),
+            str("🍆")
+        );
+    }
+
+    #[test]
+    fn pick() {
+        assert_eq!(exe(r#"<: "👍🏽🍆🌮".pick(1)"#).unwrap(), str("🍆"));
+        assert_eq!(exe(r#"<: "abc".pick(3)"#).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn code_at() {
+        assert_eq!(exe(r#"<: "aあ".charcode_at(1)"#).unwrap(), num(12354.0));
+        assert_eq!(exe(r#"<: "a".charcode_at(1)"#).unwrap(), Value::Null);
+        assert_eq!(exe(r#"<: "🍆".codepoint_at(0)"#).unwrap(), num(127814.0));
+        assert_eq!(exe(r#"<: "🍆".codepoint_at(1)"#).unwrap(), num(57158.0));
+        assert_eq!(exe(r#"<: "a".codepoint_at(1)"#).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn starts_ends_with() {
+        assert_eq!(exe(r#"<: "abcdef".starts_with("ab")"#).unwrap(), bool(true));
+        assert_eq!(
+            exe(r#"<: "abcdef".starts_with("cd", 2)"#).unwrap(),
+            bool(true)
+        );
+        assert_eq!(
+            exe(r#"<: "abcdef".starts_with("ef", 0 - 2)"#).unwrap(),
+            bool(true)
+        );
+        assert_eq!(
+            exe(r#"<: "abcdef".starts_with("", 7)"#).unwrap(),
+            bool(false)
+        );
+        assert_eq!(exe(r#"<: "abcdef".ends_with("ef")"#).unwrap(), bool(true));
+        assert_eq!(
+            exe(r#"<: "abcdef".ends_with("cd", 4)"#).unwrap(),
+            bool(true)
+        );
+        assert_eq!(
+            exe(r#"<: "abcdef".ends_with("cd", 0 - 2)"#).unwrap(),
+            bool(true)
+        );
+    }
+
+    #[test]
+    fn pad() {
+        assert_eq!(exe(r#"<: "5".pad_start(3, "0")"#).unwrap(), str("005"));
+        assert_eq!(exe(r#"<: "5".pad_start(3)"#).unwrap(), str("  5"));
+        assert_eq!(exe(r#"<: "5".pad_end(4, "ab")"#).unwrap(), str("5aba"));
+        assert_eq!(exe(r#"<: "abc".pad_end(2, "x")"#).unwrap(), str("abc"));
+        assert!(exe(r#"<: "a".pad_start(Math:pow(10, 12))"#).is_err());
+        assert_eq!(
+            exe(r#"<: "a".pad_end(Math:pow(10, 12), "")"#).unwrap(),
+            str("a")
+        );
+    }
+
+    #[test]
+    fn method_as_value() {
+        assert_eq!(
+            exe(r#"
+                let f = "abc".upper
+                <: f()
+                "#)
+            .unwrap(),
+            str("ABC")
+        );
+    }
+
+    #[test]
+    fn no_such_prop() {
+        assert!(exe(r#"<: "abc".hoge"#).is_err());
+        assert!(exe("<: true.len").is_err());
+    }
+}
+
+mod arr {
+    use aiscript_engine::Value;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn len() {
+        assert_eq!(exe("<: [1, 2, 3].len").unwrap(), num(3.0));
+        assert_eq!(exe("<: [].len").unwrap(), num(0.0));
+    }
+
+    #[test]
+    fn push_pop() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2]
+                a.push(3)
+                <: a
+                "#)
+            .unwrap(),
+            arr([num(1.0), num(2.0), num(3.0)]),
+        );
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2]
+                <: [a.pop(), a]
+                "#)
+            .unwrap(),
+            arr([num(2.0), arr([num(1.0)])]),
+        );
+        assert_eq!(exe("<: [].pop()").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn unshift_shift() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2]
+                a.unshift(0)
+                <: [a.shift(), a.shift(), a]
+                "#)
+            .unwrap(),
+            arr([num(0.0), num(1.0), arr([num(2.0)])]),
+        );
+        assert_eq!(exe("<: [].shift()").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn concat_slice_join() {
+        assert_deep_eq(
+            exe("<: [1, 2].concat([3])").unwrap(),
+            arr([num(1.0), num(2.0), num(3.0)]),
+        );
+        assert_deep_eq(
+            exe("<: [1, 2, 3, 4].slice(1, 3)").unwrap(),
+            arr([num(2.0), num(3.0)]),
+        );
+        assert_deep_eq(
+            exe("<: [1, 2, 3, 4].slice(0 - 2, 4)").unwrap(),
+            arr([num(3.0), num(4.0)]),
+        );
+        assert_eq!(
+            exe(r#"<: ["a", "b", 1, "c"].join("-")"#).unwrap(),
+            str("a-b--c")
+        );
+        assert_eq!(exe(r#"<: ["a", "b"].join()"#).unwrap(), str("ab"));
+    }
+
+    #[test]
+    fn map_filter_reduce() {
+        assert_deep_eq(
+            exe("<: [1, 2, 3].map(@(x, i) { x * 10 + i })").unwrap(),
+            arr([num(10.0), num(21.0), num(32.0)]),
+        );
+        assert_deep_eq(
+            exe("<: [1, 2, 3, 4].filter(@(x) { x % 2 == 0 })").unwrap(),
+            arr([num(2.0), num(4.0)]),
+        );
+        assert_eq!(
+            exe("<: [1, 2, 3].reduce(@(acc, x) { acc + x })").unwrap(),
+            num(6.0)
+        );
+        assert_eq!(
+            exe("<: [1, 2, 3].reduce(@(acc, x, i) { acc + x * i }, 10)").unwrap(),
+            num(18.0)
+        );
+        assert!(exe("<: [].reduce(@(acc, x) { acc + x })").is_err());
+        assert!(exe("<: [1].filter(@(x) { 1 })").is_err());
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(exe("<: [1, 2, 3].find(@(x) { x > 1 })").unwrap(), num(2.0));
+        assert_eq!(
+            exe("<: [1, 2, 3].find(@(x) { x > 3 })").unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn incl_index_of() {
+        assert_eq!(exe(r#"<: [1, "a", null].incl("a")"#).unwrap(), bool(true));
+        assert_eq!(exe("<: [1, null].incl(null)").unwrap(), bool(true));
+        assert_eq!(exe(r#"<: [1].incl("1")"#).unwrap(), bool(false));
+        assert_eq!(exe("<: [[1]].incl([1])").unwrap(), bool(false));
+        assert_eq!(exe("<: [1, 2, 1].index_of(1)").unwrap(), num(0.0));
+        assert_eq!(exe("<: [1, 2, 1].index_of(1, 1)").unwrap(), num(2.0));
+        assert_eq!(exe("<: [1, 2, 1].index_of(3)").unwrap(), num(-1.0));
+    }
+
+    #[test]
+    fn reverse_copy() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2, 3]
+                let b = a.copy()
+                a.reverse()
+                <: [a, b]
+                "#)
+            .unwrap(),
+            arr([
+                arr([num(3.0), num(2.0), num(1.0)]),
+                arr([num(1.0), num(2.0), num(3.0)]),
+            ]),
+        );
+    }
+
+    #[test]
+    fn sort() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [3, 1, 2]
+                a.sort(@(a, b) { a - b })
+                <: a
+                "#)
+            .unwrap(),
+            arr([num(1.0), num(2.0), num(3.0)]),
+        );
+        assert_deep_eq(
+            exe(r#"<: ["b", "c", "a"].sort(Str:lt)"#).unwrap(),
+            arr([str("a"), str("b"), str("c")]),
+        );
+        assert!(exe("<: [2, 1].sort(@(a, b) { true })").is_err());
+    }
+
+    #[test]
+    fn fill_repeat() {
+        assert_deep_eq(
+            exe("<: [1, 2, 3].fill(0, 1)").unwrap(),
+            arr([num(1.0), num(0.0), num(0.0)]),
+        );
+        assert_deep_eq(
+            exe("<: [1, 2].repeat(2)").unwrap(),
+            arr([num(1.0), num(2.0), num(1.0), num(2.0)]),
+        );
+        assert!(exe("<: [1].repeat(0 - 1)").is_err());
+        assert!(exe("<: [1].repeat(1.5)").is_err());
+        assert_deep_eq(exe("<: [].repeat(Math:pow(10, 20))").unwrap(), arr([]));
+        assert!(exe("<: [1].repeat(Math:pow(2, 32))").is_err());
+        let err = exe("<: [1, 2].repeat(Math:pow(2, 23) + 1)").unwrap_err();
+        assert_eq!(err.message(), "Invalid array length");
+    }
+
+    #[test]
+    fn splice() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2, 3, 4]
+                let removed = a.splice(1, 2, [9])
+                <: [a, removed]
+                "#)
+            .unwrap(),
+            arr([
+                arr([num(1.0), num(9.0), num(4.0)]),
+                arr([num(2.0), num(3.0)]),
+            ]),
+        );
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2, 3]
+                <: [a.splice(0 - 1), a]
+                "#)
+            .unwrap(),
+            arr([arr([num(3.0)]), arr([num(1.0), num(2.0)])]),
+        );
+    }
+
+    #[test]
+    fn flat() {
+        assert_deep_eq(
+            exe("<: [1, [2, [3]]].flat()").unwrap(),
+            arr([num(1.0), num(2.0), arr([num(3.0)])]),
+        );
+        assert_deep_eq(
+            exe("<: [1, [2, [3]]].flat(2)").unwrap(),
+            arr([num(1.0), num(2.0), num(3.0)]),
+        );
+        assert!(exe("<: [1].flat(0.5)").is_err());
+        assert!(exe(r#"
+            let a = [1]
+            a.push(a)
+            <: a.flat(0 - 1)
+            "#)
+        .is_err());
+    }
+
+    #[test]
+    fn flat_map() {
+        assert_deep_eq(
+            exe("<: [1, 2].flat_map(@(x) { [x, x * 2] })").unwrap(),
+            arr([num(1.0), num(2.0), num(2.0), num(4.0)]),
+        );
+    }
+
+    #[test]
+    fn every_some() {
+        assert_eq!(exe("<: [1, 2].every(@(x) { x > 0 })").unwrap(), bool(true));
+        assert_eq!(exe("<: [1, 2].every(@(x) { x > 1 })").unwrap(), bool(false));
+        assert_eq!(exe("<: [1, 2].some(@(x) { x > 1 })").unwrap(), bool(true));
+        assert_eq!(exe("<: [].some(@(x) { true })").unwrap(), bool(false));
+    }
+
+    #[test]
+    fn at() {
+        assert_eq!(exe("<: [1, 2, 3].at(0)").unwrap(), num(1.0));
+        assert_eq!(exe("<: [1, 2, 3].at(0 - 1)").unwrap(), num(3.0));
+        assert_eq!(exe("<: [1, 2, 3].at(3)").unwrap(), Value::Null);
+        assert_eq!(exe("<: [1, 2, 3].at(3, 0)").unwrap(), num(0.0));
+    }
+
+    #[test]
+    fn mutation_in_callback() {
+        assert_deep_eq(
+            exe(r#"
+                let a = [1, 2]
+                <: a.map(@(x) { a.push(x); x })
+                "#)
+            .unwrap(),
+            arr([num(1.0), num(2.0)]),
+        );
+    }
+}
+
+mod error {
+    use crate::common::{num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn name() {
+        assert_eq!(exe(r#"<: Error:create("hoge").name"#).unwrap(), str("hoge"));
+    }
+
+    #[test]
+    fn info() {
+        assert_eq!(
+            exe(r#"<: Error:create("hoge", 42).info"#).unwrap(),
+            num(42.0)
+        );
+        assert_eq!(
+            exe(r#"<: Error:create("hoge").info"#).unwrap(),
+            aiscript_engine::Value::Null
+        );
+    }
+
+    #[test]
+    fn no_such_prop() {
+        assert!(exe(r#"<: Error:create("hoge").hoge"#).is_err());
+    }
+}
+
+mod num {
+    use crate::common::str;
+
+    use super::common::exe;
+
+    #[test]
+    fn to_str() {
+        assert_eq!(exe("let x = 42; <: x.to_str()").unwrap(), str("42"));
+        assert_eq!(exe("let x = -1.5; <: x.to_str()").unwrap(), str("-1.5"));
+    }
+
+    #[test]
+    fn to_hex() {
+        assert_eq!(exe("let x = 255; <: x.to_hex()").unwrap(), str("ff"));
+    }
+
+    #[test]
+    fn no_such_prop() {
+        assert!(exe("let x = 1; <: x.hoge").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::rc::Rc;
+
+use aiscript_engine::{ast, Interpreter, InterpreterOpts, Parser, Result, Utf16String, Value};
+use common::{num, str};
+
+struct NoOut;
+
+impl InterpreterOpts for NoOut {
+    fn out(&self, _value: Value) {}
+}
+
+fn run(source: &str) -> Result<Value> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    let mut interpreter = Interpreter::new(Rc::new(NoOut));
+    return interpreter.run(&ast);
+}
+
+fn eval_expression(source: &str) -> Result<Value> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    let [ast::Node::Expr(expr)] = ast.as_slice() else {
+        panic!("not a single expression");
+    };
+    let mut interpreter = Interpreter::new(Rc::new(NoOut));
+    return interpreter.eval_expression(expr);
+}
+
+#[test]
+fn last_expression() {
+    assert_eq!(run("1\n1 + 2").unwrap(), num(3.0));
+}
+
+#[test]
+fn last_statement() {
+    assert_eq!(run("let a = 1").unwrap(), Value::Null);
+}
+
+#[test]
+fn empty_program() {
+    assert_eq!(run("").unwrap(), Value::Null);
+}
+
+#[test]
+fn function_call() {
+    assert_eq!(run("@f(x) { x * 2 }\nf(21)").unwrap(), num(42.0));
+}
+
+#[test]
+fn expression() {
+    assert_eq!(eval_expression("1 + 2").unwrap(), num(3.0));
+}
+
+#[test]
+fn expression_with_block() {
+    assert_eq!(
+        eval_expression(
+            r#"
+            eval {
+                let a = "a"
+                `{a}b`
+            }
+            "#
+        )
+        .unwrap(),
+        str("ab")
+    );
+}
+
+#[test]
+fn expression_with_closure() {
+    assert_eq!(
+        eval_expression("eval { var a = 1\n@f() { a += 1 }\nf()\na }").unwrap(),
+        num(2.0)
+    );
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use aiscript_engine::{Interpreter, InterpreterOpts, Parser, Result, Utf16String, Value};
+use common::{num, str};
+
+struct TestOpts {
+    result: Rc<RefCell<Value>>,
+}
+
+impl InterpreterOpts for TestOpts {
+    fn out(&self, value: Value) {
+        *self.result.borrow_mut() = value;
+    }
+}
+
+fn session() -> (Interpreter, Rc<RefCell<Value>>) {
+    let result = Rc::new(RefCell::new(Value::Uninitialized));
+    let interpreter = Interpreter::builder(Rc::new(TestOpts {
+        result: Rc::clone(&result),
+    }))
+    .session()
+    .build();
+    return (interpreter, result);
+}
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value> {
+    let ast = Parser::new().parse(&Utf16String::from(source))?;
+    return interpreter.run(&ast);
+}
+
+#[test]
+fn keeps_variables() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "let a = 1\nvar b = 2").unwrap();
+    assert_eq!(run(&mut interpreter, "a + b").unwrap(), num(3.0));
+}
+
+#[test]
+fn mutates_variables() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "var a = 1").unwrap();
+    run(&mut interpreter, "a += 1").unwrap();
+    assert_eq!(run(&mut interpreter, "a").unwrap(), num(2.0));
+}
+
+#[test]
+fn immutable_variables() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "let a = 1").unwrap();
+    assert!(run(&mut interpreter, "a = 2").is_err());
+}
+
+#[test]
+fn keeps_functions() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "var a = 1\n@f() { a }").unwrap();
+    run(&mut interpreter, "a = 2").unwrap();
+    assert_eq!(run(&mut interpreter, "f()").unwrap(), num(2.0));
+}
+
+#[test]
+fn keeps_namespaces() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, ":: Ns { let a = \"ns\" }").unwrap();
+    assert_eq!(run(&mut interpreter, "Ns:a").unwrap(), str("ns"));
+}
+
+#[test]
+fn redefines_variables() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "let a = 1").unwrap();
+    run(&mut interpreter, "let a = \"a\"").unwrap();
+    assert_eq!(run(&mut interpreter, "a").unwrap(), str("a"));
+}
+
+#[test]
+fn keeps_library() {
+    let (mut interpreter, result) = session();
+    run(&mut interpreter, "let a = 1").unwrap();
+    run(&mut interpreter, "<: Core:add(a, 2)").unwrap();
+    assert_eq!(*result.borrow(), num(3.0));
+}
+
+#[test]
+fn continues_after_error() {
+    let (mut interpreter, _) = session();
+    run(&mut interpreter, "var a = 1").unwrap();
+    assert!(run(&mut interpreter, "a = 2\nCore:add(a, \"x\")").is_err());
+    assert_eq!(run(&mut interpreter, "a").unwrap(), num(2.0));
+}
+
+#[test]
+fn without_session() {
+    let mut interpreter = Interpreter::new(Rc::new(TestOpts {
+        result: Rc::new(RefCell::new(Value::Uninitialized)),
+    }));
+    run(&mut interpreter, "let a = 1").unwrap();
+    assert!(run(&mut interpreter, "a").is_err());
+}
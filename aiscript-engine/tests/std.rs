@@ -1,7 +1,9 @@
 mod common;
 
 mod core {
-    use crate::common::{bool, num, str};
+    use aiscript_engine::Value;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
 
     use super::common::exe;
 
@@ -107,4 +109,724 @@ mod core {
         assert!(exe("<: Core:mod(false, 1)").is_err());
         assert!(exe("<: Core:mod(1, false)").is_err());
     }
+
+    #[test]
+    fn gt() {
+        assert_eq!(exe("<: Core:gt(3, 2)").unwrap(), bool(true));
+        assert_eq!(exe("<: Core:gt(2, 2)").unwrap(), bool(false));
+        assert!(exe("<: Core:gt(false, 1)").is_err());
+    }
+
+    #[test]
+    fn lt() {
+        assert_eq!(exe("<: Core:lt(2, 3)").unwrap(), bool(true));
+        assert_eq!(exe("<: Core:lt(2, 2)").unwrap(), bool(false));
+        assert!(exe("<: Core:lt(false, 1)").is_err());
+    }
+
+    #[test]
+    fn gteq() {
+        assert_eq!(exe("<: Core:gteq(2, 2)").unwrap(), bool(true));
+        assert_eq!(exe("<: Core:gteq(1, 2)").unwrap(), bool(false));
+        assert!(exe("<: Core:gteq(false, 1)").is_err());
+    }
+
+    #[test]
+    fn lteq() {
+        assert_eq!(exe("<: Core:lteq(2, 2)").unwrap(), bool(true));
+        assert_eq!(exe("<: Core:lteq(3, 2)").unwrap(), bool(false));
+        assert!(exe("<: Core:lteq(false, 1)").is_err());
+    }
+
+    #[test]
+    fn type_of() {
+        assert_eq!(exe("<: Core:type(null)").unwrap(), str("null"));
+        assert_eq!(exe("<: Core:type(true)").unwrap(), str("bool"));
+        assert_eq!(exe("<: Core:type(1)").unwrap(), str("num"));
+        assert_eq!(exe("<: Core:type('a')").unwrap(), str("str"));
+        assert_eq!(exe("<: Core:type([])").unwrap(), str("arr"));
+        assert_eq!(exe("<: Core:type({})").unwrap(), str("obj"));
+        assert_eq!(exe("<: Core:type(@() {})").unwrap(), str("fn"));
+        assert_eq!(exe("<: Core:type(Core:add)").unwrap(), str("fn"));
+    }
+
+    #[test]
+    fn range() {
+        assert_deep_eq(
+            exe("<: Core:range(1, 10)").unwrap(),
+            arr((1..=10).map(|i| num(i as f64))),
+        );
+        assert_deep_eq(exe("<: Core:range(1, 1)").unwrap(), arr([num(1.0)]));
+        assert_deep_eq(
+            exe("<: Core:range(9, 7)").unwrap(),
+            arr([num(9.0), num(8.0), num(7.0)]),
+        );
+        assert_deep_eq(
+            exe("<: Core:range(0.5, 2)").unwrap(),
+            arr([num(0.5), num(1.5)]),
+        );
+    }
+
+    #[test]
+    fn range_invalid_length() {
+        let err = exe("<: Core:range(0, Math:Infinity)").unwrap_err();
+        assert_eq!(err.message(), "Invalid array length");
+        let err = exe("<: Core:range(0 - Math:Infinity, 0)").unwrap_err();
+        assert_eq!(err.message(), "Invalid array length");
+        let err = exe("<: Core:range(0, Math:pow(10, 12))").unwrap_err();
+        assert_eq!(err.message(), "Invalid array length");
+    }
+
+    #[test]
+    fn to_str() {
+        assert_eq!(exe(r#"<: Core:to_str("abc")"#).unwrap(), str("abc"));
+        assert_eq!(exe("<: Core:to_str(123)").unwrap(), str("123"));
+        assert_eq!(exe("<: Core:to_str(true)").unwrap(), str("true"));
+        assert_eq!(exe("<: Core:to_str(false)").unwrap(), str("false"));
+        assert_eq!(exe("<: Core:to_str(null)").unwrap(), str("null"));
+        assert_eq!(
+            exe(r#"<: Core:to_str({ a: "abc", b: 1234 })"#).unwrap(),
+            str(r#"{ a: "abc", b: 1234 }"#)
+        );
+        assert_eq!(
+            exe("<: Core:to_str([ true, 123, null ])").unwrap(),
+            str("[ true, 123, null ]")
+        );
+        assert_eq!(
+            exe("<: Core:to_str(@( a, b, c ) {})").unwrap(),
+            str("@( a, b, c ) { ... }")
+        );
+        assert_eq!(
+            exe(r#"
+                let arr = [0]
+                arr[0] = arr
+                <: Core:to_str(arr)
+                "#)
+            .unwrap(),
+            str("[ ... ]")
+        );
+        assert_eq!(
+            exe(r#"
+                let arr = [0]
+                arr[0] = { value: arr }
+                <: Core:to_str(arr)
+                "#)
+            .unwrap(),
+            str("[ { value: ... } ]")
+        );
+    }
+
+    #[test]
+    fn to_str_num() {
+        assert_eq!(
+            exe("<: Core:to_str(0.1 + 0.2)").unwrap(),
+            str("0.30000000000000004")
+        );
+        assert_eq!(
+            exe("<: Core:to_str(Math:pow(10, 21))").unwrap(),
+            str("1e+21")
+        );
+        assert_eq!(
+            exe("<: Core:to_str(Math:pow(10, 20))").unwrap(),
+            str("100000000000000000000")
+        );
+        assert_eq!(exe("<: Core:to_str(1 / 10000000)").unwrap(), str("1e-7"));
+        assert_eq!(exe("<: Core:to_str(-0)").unwrap(), str("0"));
+        assert_eq!(exe("<: Core:to_str(0 / 0)").unwrap(), str("NaN"));
+        assert_eq!(exe("<: Core:to_str(-1 / 0)").unwrap(), str("-Infinity"));
+        assert_eq!(exe("<: `{1 / 3}`").unwrap(), str("0.3333333333333333"));
+        assert_eq!(
+            exe("<: Json:stringify([1 / 10000000, Math:pow(2, 70)])").unwrap(),
+            str("[1e-7,1.1805916207174113e+21]")
+        );
+    }
+
+    #[test]
+    fn sleep() {
+        assert_eq!(exe("<: Core:sleep(1)").unwrap(), Value::Null);
+        assert_eq!(exe("<: Core:sleep(-1)").unwrap(), Value::Null);
+        assert!(exe("<: Core:sleep('a')").is_err());
+    }
+
+    #[test]
+    fn abort() {
+        let err = exe(r#"Core:abort("hoge")"#).unwrap_err();
+        assert_eq!(err.name(), "User");
+        assert!(err.message().contains("hoge"));
+        assert!(exe("Core:abort(1)").is_err());
+    }
+}
+
+mod math {
+    use aiscript_engine::Value;
+
+    use crate::common::{bool, num};
+
+    use super::common::exe;
+
+    #[test]
+    fn constants() {
+        assert_eq!(exe("<: Math:PI").unwrap(), num(std::f64::consts::PI));
+        assert_eq!(exe("<: Math:E").unwrap(), num(std::f64::consts::E));
+        assert_eq!(exe("<: Math:Infinity").unwrap(), num(f64::INFINITY));
+    }
+
+    #[test]
+    fn trig() {
+        assert_eq!(exe("<: Math:sin(0)").unwrap(), num(0.0));
+        assert_eq!(exe("<: Math:cos(0)").unwrap(), num(1.0));
+        assert_eq!(exe("<: Math:atan2(1, 1)").unwrap(), num(1f64.atan2(1.0)));
+    }
+
+    #[test]
+    fn log() {
+        assert_eq!(exe("<: Math:log(1)").unwrap(), num(0.0));
+        assert_eq!(exe("<: Math:log10(1000)").unwrap(), num(3.0));
+        assert_eq!(exe("<: Math:log2(8)").unwrap(), num(3.0));
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(exe("<: Math:floor(1.5)").unwrap(), num(1.0));
+        assert_eq!(exe("<: Math:ceil(1.5)").unwrap(), num(2.0));
+        assert_eq!(exe("<: Math:round(2.5)").unwrap(), num(3.0));
+        assert_eq!(exe("<: Math:round(0 - 2.5)").unwrap(), num(-2.0));
+        assert_eq!(exe("<: Math:trunc(0 - 1.5)").unwrap(), num(-1.0));
+    }
+
+    #[test]
+    fn others() {
+        assert_eq!(exe("<: Math:abs(0 - 3)").unwrap(), num(3.0));
+        assert_eq!(exe("<: Math:sqrt(4)").unwrap(), num(2.0));
+        assert_eq!(exe("<: Math:pow(2, 10)").unwrap(), num(1024.0));
+        assert_eq!(exe("<: Math:max(1, 2)").unwrap(), num(2.0));
+        assert_eq!(exe("<: Math:min(1, 2)").unwrap(), num(1.0));
+        assert_eq!(exe("<: Math:sign(0 - 5)").unwrap(), num(-1.0));
+        assert_eq!(exe("<: Math:hypot([3, 4])").unwrap(), num(5.0));
+        assert_eq!(exe("<: Math:imul(3, 4)").unwrap(), num(12.0));
+        assert_eq!(exe("<: Math:clz32(1)").unwrap(), num(31.0));
+        assert!(exe("<: Math:floor('a')").is_err());
+    }
+
+    #[test]
+    fn rnd() {
+        let value = exe("<: Math:rnd()").unwrap();
+        let Value::Num(value) = value else {
+            panic!("expected num, got {:?}", value);
+        };
+        assert!((0.0..1.0).contains(&value));
+        assert_eq!(
+            exe(r#"
+                var ok = true
+                for 100 {
+                    let r = Math:rnd(1, 3)
+                    if (r < 1 || r > 3 || Math:floor(r) != r) { ok = false }
+                }
+                <: ok
+                "#)
+            .unwrap(),
+            bool(true)
+        );
+    }
+
+    #[test]
+    fn gen_rng() {
+        assert_eq!(
+            exe(r#"<: Math:gen_rng("hello.", { algorithm: "rc4_legacy" })()"#).unwrap(),
+            num(0.9282578795792454)
+        );
+        for options in [
+            "",
+            r#", { algorithm: "chacha20" }"#,
+            r#", { algorithm: "rc4" }"#,
+        ] {
+            let source = format!(
+                r#"
+                let a = Math:gen_rng(1{options})
+                let b = Math:gen_rng(1{options})
+                var ok = true
+                for 100 {{
+                    let x = a(0, 100)
+                    if (x != b(0, 100) || x < 0 || x > 100 || x != Math:floor(x)) {{ ok = false }}
+                    let y = a()
+                    if (y != b() || y < 0 || y >= 1) {{ ok = false }}
+                }}
+                <: ok
+                "#
+            );
+            assert_eq!(exe(&source).unwrap(), bool(true));
+        }
+        assert_ne!(
+            exe(r#"<: Math:gen_rng("hello.")()"#).unwrap(),
+            num(0.9282578795792454)
+        );
+        assert_eq!(
+            exe("<: Math:gen_rng(1)(0, Math:pow(2, 53))").unwrap(),
+            Value::Null
+        );
+        assert_eq!(exe("<: Math:gen_rng(true)").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn gen_rng_invalid_options() {
+        let err = exe("<: Math:gen_rng(1, 1)").unwrap_err();
+        assert_eq!(err.message(), "`options` must be an object if specified.");
+        let err = exe("<: Math:gen_rng(1, {})").unwrap_err();
+        assert_eq!(err.message(), "`options.algorithm` must be string.");
+        let err = exe(r#"<: Math:gen_rng(1, { algorithm: "xorshift" })"#).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "`options.algorithm` must be one of these: `chacha20`, `rc4`, or `rc4_legacy`."
+        );
+    }
+}
+
+mod str {
+    use crate::common::{num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn lf() {
+        assert_eq!(exe("<: Str:lf").unwrap(), str("\n"));
+    }
+
+    #[test]
+    fn lt_gt() {
+        assert_eq!(exe(r#"<: Str:lt("a", "b")"#).unwrap(), num(-1.0));
+        assert_eq!(exe(r#"<: Str:lt("a", "a")"#).unwrap(), num(0.0));
+        assert_eq!(exe(r#"<: Str:lt("b", "a")"#).unwrap(), num(1.0));
+        assert_eq!(exe(r#"<: Str:gt("a", "b")"#).unwrap(), num(1.0));
+        assert_eq!(exe(r#"<: Str:gt("b", "a")"#).unwrap(), num(-1.0));
+    }
+
+    #[test]
+    fn from_codepoint() {
+        assert_eq!(exe("<: Str:from_codepoint(65)").unwrap(), str("A"));
+        assert_eq!(exe("<: Str:from_codepoint(127814)").unwrap(), str("🍆"));
+        assert!(exe("<: Str:from_codepoint(1114112)").is_err());
+        assert!(exe("<: Str:from_codepoint(0.5)").is_err());
+    }
+
+    #[test]
+    fn from_unicode_codepoints() {
+        assert_eq!(
+            exe("<: Str:from_unicode_codepoints([128077, 127997, 127814])").unwrap(),
+            str("👍🏽🍆")
+        );
+    }
+
+    #[test]
+    fn from_utf8_bytes() {
+        assert_eq!(
+            exe("<: Str:from_utf8_bytes([97, 227, 129, 130])").unwrap(),
+            str("aあ")
+        );
+        assert_eq!(
+            exe("<: Str:from_utf8_bytes([255])").unwrap(),
+            str("\u{FFFD}")
+        );
+    }
+}
+
+mod obj {
+    use aiscript_engine::Value;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn keys_vals_kvs() {
+        assert_deep_eq(
+            exe("<: Obj:keys({ b: 1, a: 2, c: 3 })").unwrap(),
+            arr([str("b"), str("a"), str("c")]),
+        );
+        assert_deep_eq(
+            exe("<: Obj:vals({ b: 1, a: 2 })").unwrap(),
+            arr([num(1.0), num(2.0)]),
+        );
+        assert_deep_eq(
+            exe("<: Obj:kvs({ b: 1, a: 2 })").unwrap(),
+            arr([arr([str("b"), num(1.0)]), arr([str("a"), num(2.0)])]),
+        );
+    }
+
+    #[test]
+    fn get_set_has() {
+        assert_eq!(exe(r#"<: Obj:get({ a: 1 }, "a")"#).unwrap(), num(1.0));
+        assert_eq!(exe(r#"<: Obj:get({ a: 1 }, "b")"#).unwrap(), Value::Null);
+        assert_deep_eq(
+            exe(r#"
+                let o = { a: 1, b: 2 }
+                Obj:set(o, "c", 3)
+                Obj:set(o, "a", 0)
+                <: Obj:kvs(o)
+                "#)
+            .unwrap(),
+            arr([
+                arr([str("a"), num(0.0)]),
+                arr([str("b"), num(2.0)]),
+                arr([str("c"), num(3.0)]),
+            ]),
+        );
+        assert_eq!(exe(r#"<: Obj:has({ a: 1 }, "a")"#).unwrap(), bool(true));
+        assert_eq!(exe(r#"<: Obj:has({ a: 1 }, "b")"#).unwrap(), bool(false));
+        assert!(exe(r#"<: Obj:get([], "a")"#).is_err());
+    }
+
+    #[test]
+    fn copy() {
+        assert_deep_eq(
+            exe(r#"
+                let a = { x: 1 }
+                let b = Obj:copy(a)
+                b.x = 2
+                <: [a.x, b.x]
+                "#)
+            .unwrap(),
+            arr([num(1.0), num(2.0)]),
+        );
+    }
+
+    #[test]
+    fn merge() {
+        assert_deep_eq(
+            exe("<: Obj:kvs(Obj:merge({ a: 1, b: 2 }, { c: 3, a: 4 }))").unwrap(),
+            arr([
+                arr([str("a"), num(4.0)]),
+                arr([str("b"), num(2.0)]),
+                arr([str("c"), num(3.0)]),
+            ]),
+        );
+    }
+
+    #[test]
+    fn pick() {
+        assert_deep_eq(
+            exe(r#"<: Obj:kvs(Obj:pick({ a: 1, b: 2, c: 3 }, ["c", "a", "d"]))"#).unwrap(),
+            arr([
+                arr([str("c"), num(3.0)]),
+                arr([str("a"), num(1.0)]),
+                arr([str("d"), Value::Null]),
+            ]),
+        );
+    }
+}
+
+mod json {
+    use aiscript_engine::Value;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn stringify() {
+        assert_eq!(
+            exe(r#"<: Json:stringify({ a: [1, "b", true, null], c: @() {} })"#).unwrap(),
+            str(r#"{"a":[1,"b",true,null],"c":"<function>"}"#)
+        );
+        assert_eq!(
+            exe("<: Json:stringify(Math:Infinity)").unwrap(),
+            str("null")
+        );
+        assert!(exe(r#"
+            let a = []
+            a.push(a)
+            <: Json:stringify(a)
+            "#)
+        .is_err());
+    }
+
+    #[test]
+    fn parse() {
+        assert_deep_eq(
+            exe(r#"<: Json:parse("[1, \"a\", true, null]")"#).unwrap(),
+            arr([num(1.0), str("a"), bool(true), Value::Null]),
+        );
+        assert_deep_eq(
+            exe(r#"<: Obj:keys(Json:parse("{\"b\": 1, \"1\": 2, \"a\": 3}"))"#).unwrap(),
+            arr([str("1"), str("b"), str("a")]),
+        );
+        assert_eq!(
+            exe(r#"<: Core:type(Json:parse("{"))"#).unwrap(),
+            str("error")
+        );
+    }
+
+    #[test]
+    fn parsable() {
+        assert_eq!(exe(r#"<: Json:parsable("[1]")"#).unwrap(), bool(true));
+        assert_eq!(exe(r#"<: Json:parsable("[1")"#).unwrap(), bool(false));
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(
+            exe(r#"<: Json:stringify(Json:parse("{\"b\":[0.5,\"\\u3042\"],\"a\":{}}"))"#).unwrap(),
+            str(r#"{"b":[0.5,"あ"],"a":{}}"#)
+        );
+    }
+}
+
+mod date {
+    use std::rc::Rc;
+
+    use aiscript_engine::{Clock, Value};
+
+    use super::common::*;
+
+    /// 時刻とタイムゾーンを固定した時計
+    struct FixedClock {
+        now: f64,
+        timezone_offset: f64,
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> f64 {
+            self.now
+        }
+
+        fn timezone_offset(&self, _time: f64) -> f64 {
+            self.timezone_offset
+        }
+    }
+
+    /// 2024-02-29T12:34:56.789Z
+    const NOW: f64 = 1709210096789.0;
+
+    fn exe_at(source: &str, timezone_offset: f64) -> Value {
+        let clock: Rc<dyn Clock> = Rc::new(FixedClock {
+            now: NOW,
+            timezone_offset,
+        });
+        return exe_with_clock(source, Some(clock)).unwrap();
+    }
+
+    #[test]
+    fn now() {
+        assert_eq!(exe_at("<: Date:now()", 0.0), num(NOW));
+    }
+
+    #[test]
+    fn fields() {
+        let source = r#"
+            <: [
+                Date:year(), Date:month(), Date:day(),
+                Date:hour(), Date:minute(), Date:second(), Date:millisecond(),
+            ]
+        "#;
+        assert_deep_eq(
+            exe_at(source, 0.0),
+            arr([
+                num(2024.0),
+                num(2.0),
+                num(29.0),
+                num(12.0),
+                num(34.0),
+                num(56.0),
+                num(789.0),
+            ]),
+        );
+        // JST
+        assert_deep_eq(
+            exe_at(source, -540.0),
+            arr([
+                num(2024.0),
+                num(2.0),
+                num(29.0),
+                num(21.0),
+                num(34.0),
+                num(56.0),
+                num(789.0),
+            ]),
+        );
+    }
+
+    #[test]
+    fn fields_of_time() {
+        let source = r#"
+            <: [Date:year(0), Date:month(0), Date:day(0), Date:hour(0)]
+        "#;
+        assert_deep_eq(
+            exe_at(source, 60.0),
+            arr([num(1969.0), num(12.0), num(31.0), num(23.0)]),
+        );
+        assert!(matches!(
+            exe_at("<: Date:year(Math:pow(10, 16))", 0.0),
+            Value::Num(year) if year.is_nan()
+        ));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            exe_at(r#"<: Date:parse("2024-02-29T12:34:56.789Z")"#, -540.0),
+            num(NOW)
+        );
+        assert_eq!(
+            exe_at(r#"<: Date:parse("2024-02-29T21:34:56.789+09:00")"#, 0.0),
+            num(NOW)
+        );
+        // 日付のみの形式はUTC
+        assert_eq!(
+            exe_at(r#"<: Date:parse("1970-01-02")"#, -540.0),
+            num(86400000.0)
+        );
+        // オフセットのない日時は地方時
+        assert_eq!(
+            exe_at(r#"<: Date:parse("1970-01-02T09:00")"#, -540.0),
+            num(86400000.0)
+        );
+        assert_eq!(
+            exe_at(r#"<: Core:type(Date:parse("hoge"))"#, 0.0),
+            str("error")
+        );
+    }
+
+    #[test]
+    fn to_iso_str() {
+        assert_eq!(
+            exe_at("<: Date:to_iso_str()", 0.0),
+            str("2024-02-29T12:34:56.789Z")
+        );
+        assert_eq!(
+            exe_at("<: Date:to_iso_str()", -540.0),
+            str("2024-02-29T21:34:56.789+09:00")
+        );
+        assert_eq!(
+            exe_at("<: Date:to_iso_str(86400000, -90)", 0.0),
+            str("1970-01-01T22:30:00.000-01:30")
+        );
+        // 0は省略と同じ扱い
+        assert_eq!(
+            exe_at("<: Date:to_iso_str(0, 0)", -540.0),
+            str("2024-02-29T21:34:56.789+09:00")
+        );
+    }
+}
+
+mod error {
+    use crate::common::{bool, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn create() {
+        assert_eq!(
+            exe(r#"<: Core:type(Error:create("hoge"))"#).unwrap(),
+            str("error")
+        );
+    }
+
+    #[test]
+    fn create_with_info() {
+        assert_eq!(
+            exe(r#"
+                let e = Error:create("hoge", { a: 1 })
+                <: e.info.a == 1
+                "#)
+            .unwrap(),
+            bool(true)
+        );
+    }
+
+    #[test]
+    fn create_requires_name() {
+        assert!(exe("Error:create(1)").is_err());
+    }
+
+    #[test]
+    fn eq() {
+        assert_eq!(
+            exe(r#"<: Error:create("hoge") == Error:create("hoge")"#).unwrap(),
+            bool(true)
+        );
+        assert_eq!(
+            exe(r#"<: Error:create("hoge") == Error:create("fuga")"#).unwrap(),
+            bool(false)
+        );
+        assert_eq!(
+            exe(r#"<: Error:create("hoge", 1) == Error:create("hoge", 1)"#).unwrap(),
+            bool(true)
+        );
+        assert_eq!(
+            exe(r#"<: Error:create("hoge", 1) == Error:create("hoge")"#).unwrap(),
+            bool(false)
+        );
+    }
+}
+
+mod num {
+    use crate::common::{num, str};
+
+    use super::common::exe;
+
+    #[test]
+    fn to_hex() {
+        assert_eq!(exe("<: Num:to_hex(255)").unwrap(), str("ff"));
+        assert_eq!(exe("<: Num:to_hex(-0.5)").unwrap(), str("-0.8"));
+        assert_eq!(exe("<: Num:to_hex(0.1)").unwrap(), str("0.1999999999999a"));
+    }
+
+    #[test]
+    fn from_hex() {
+        assert_eq!(exe(r#"<: Num:from_hex("ff")"#).unwrap(), num(255.0));
+        assert_eq!(exe(r#"<: Num:from_hex("0x1Fz")"#).unwrap(), num(31.0));
+        assert_eq!(
+            exe(r#"<: Num:from_hex("g") == Num:from_hex("g")"#).unwrap(),
+            crate::common::bool(false)
+        );
+    }
+}
+
+mod util {
+    use std::{cell::Cell, rc::Rc};
+
+    use aiscript_engine::RandomSource;
+
+    use crate::common::{arr, assert_deep_eq, bool, num, str};
+
+    use super::common::{exe, exe_with_random_source};
+
+    /// 同じ値を返し続ける乱数源
+    struct FixedRandom(Cell<u64>);
+
+    impl RandomSource for FixedRandom {
+        fn next_u64(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn uuid_format() {
+        let result = exe(r#"
+            let id = Util:uuid()
+            <: [id.len, id.split("-").map(@(p) { p.len }), id.pick(14)]
+            "#)
+        .unwrap();
+        assert_deep_eq(
+            result,
+            arr([
+                num(36.0),
+                arr([num(8.0), num(4.0), num(4.0), num(4.0), num(12.0)]),
+                str("4"),
+            ]),
+        );
+    }
+
+    #[test]
+    fn uuid_random_source() {
+        let random = Rc::new(FixedRandom(Cell::new(0x0123_4567_89ab_cdef)));
+        assert_eq!(
+            exe_with_random_source("<: Util:uuid()", random).unwrap(),
+            str("efcdab89-6745-4301-afcd-ab8967452301")
+        );
+    }
+
+    #[test]
+    fn uuid_unique() {
+        assert_eq!(exe("<: Util:uuid() != Util:uuid()").unwrap(), bool(true));
+    }
 }
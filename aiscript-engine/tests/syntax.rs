@@ -199,6 +199,96 @@ fn sub() {
     assert_eq!(res, num(-1.0));
 }
 
+#[test]
+fn mul() {
+    let res = exe("<: 2 * 3").unwrap();
+    assert_eq!(res, num(6.0));
+}
+
+#[test]
+fn pow() {
+    let res = exe("<: 2 ^ 3").unwrap();
+    assert_eq!(res, num(8.0));
+
+    let res = exe("<: 1 ^ (0 / 0)").unwrap();
+    assert!(matches!(res, aiscript_engine::Value::Num(value) if value.is_nan()));
+}
+
+#[test]
+fn div() {
+    let res = exe("<: 6 / 4").unwrap();
+    assert_eq!(res, num(1.5));
+}
+
+#[test]
+fn rem() {
+    let res = exe("<: 7 % 3").unwrap();
+    assert_eq!(res, num(1.0));
+
+    let res = exe("<: -7 % 3").unwrap();
+    assert_eq!(res, num(-1.0));
+}
+
+#[test]
+fn precedence() {
+    let res = exe("<: 1 + 2 * 3 ^ 2").unwrap();
+    assert_eq!(res, num(19.0));
+}
+
+#[test]
+fn comparison() {
+    assert_eq!(exe("<: 1 < 2").unwrap(), bool(true));
+    assert_eq!(exe("<: 2 < 2").unwrap(), bool(false));
+    assert_eq!(exe("<: 2 <= 2").unwrap(), bool(true));
+    assert_eq!(exe("<: 3 <= 2").unwrap(), bool(false));
+    assert_eq!(exe("<: 3 > 2").unwrap(), bool(true));
+    assert_eq!(exe("<: 2 > 2").unwrap(), bool(false));
+    assert_eq!(exe("<: 2 >= 2").unwrap(), bool(true));
+    assert_eq!(exe("<: 1 >= 2").unwrap(), bool(false));
+}
+
+#[test]
+fn arithmetic_type_error() {
+    assert!(exe("<: 1 * true").is_err());
+    assert!(exe("<: 'a' ^ 1").is_err());
+    assert!(exe("<: null / 1").is_err());
+    assert!(exe("<: 1 % []").is_err());
+    assert!(exe("<: 1 < 'a'").is_err());
+}
+
+#[test]
+fn eq() {
+    assert_eq!(exe("<: 1 == 1").unwrap(), bool(true));
+    assert_eq!(exe("<: 1 == 2").unwrap(), bool(false));
+    assert_eq!(exe("<: 'a' == 'a'").unwrap(), bool(true));
+    assert_eq!(exe("<: null == null").unwrap(), bool(true));
+    assert_eq!(exe("<: null == false").unwrap(), bool(false));
+    assert_eq!(exe("<: 1 == '1'").unwrap(), bool(false));
+    assert_eq!(exe("<: 1 != 2").unwrap(), bool(true));
+    assert_eq!(exe("<: 1 != 1").unwrap(), bool(false));
+}
+
+#[test]
+fn reference_eq() {
+    let res = exe(r#"
+    let a = []
+    let b = a
+    <: a == b
+    "#)
+    .unwrap();
+    assert_eq!(res, bool(true));
+
+    assert_eq!(exe("<: [] == []").unwrap(), bool(false));
+    assert_eq!(exe("<: {} != {}").unwrap(), bool(true));
+
+    let res = exe(r#"
+    @f() {}
+    <: f == f
+    "#)
+    .unwrap();
+    assert_eq!(res, bool(true));
+}
+
 #[test]
 fn and() {
     let res = exe("<: false && false").unwrap();
@@ -228,3 +318,1119 @@ fn or() {
     let res = exe("<: true || true").unwrap();
     assert_eq!(res, bool(true));
 }
+
+mod function {
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn call() {
+        let res = exe(r#"
+        @f(x) {
+            x
+        }
+        <: f(1)
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+
+    #[test]
+    fn empty() {
+        let res = exe(r#"
+        @f() {}
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, aiscript_engine::Value::Null);
+    }
+
+    #[test]
+    fn recursion() {
+        let res = exe(r#"
+        @fact(n) {
+            if Core:eq(n, 0) 1 else Core:mul(fact(n - 1), n)
+        }
+        <: fact(5)
+        "#)
+        .unwrap();
+        assert_eq!(res, num(120.0));
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let res = exe(r#"
+        @isEven(n) {
+            if Core:eq(n, 0) true else isOdd(n - 1)
+        }
+        @isOdd(n) {
+            if Core:eq(n, 0) false else isEven(n - 1)
+        }
+        <: isEven(10)
+        "#)
+        .unwrap();
+        assert_eq!(res, crate::common::bool(true));
+    }
+
+    #[test]
+    fn optional_arg() {
+        let res = exe(r#"
+        @f(x, y?) {
+            [x, y]
+        }
+        let a = f(1)
+        <: a[1]
+        "#)
+        .unwrap();
+        assert_eq!(res, aiscript_engine::Value::Null);
+    }
+
+    #[test]
+    fn default_arg() {
+        let res = exe(r#"
+        var x = "foo"
+        @f(a = x) {
+            a
+        }
+        x = "bar"
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("foo"));
+    }
+
+    #[test]
+    fn missing_arg() {
+        assert!(exe(r#"
+        @f(x) {
+            x
+        }
+        f()
+        "#)
+        .is_err());
+    }
+
+    #[test]
+    fn arg_is_mutable() {
+        let res = exe(r#"
+        @f(x) {
+            x += 1
+            x
+        }
+        <: f(1)
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+}
+
+mod closure {
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn basic() {
+        let res = exe(r#"
+        @store(v) {
+            let state = v
+            @() {
+                state
+            }
+        }
+        let s = store("ai")
+        <: s()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn counter() {
+        let res = exe(r#"
+        @createCounter() {
+            var count = 0
+            {
+                getCount: @() { count },
+                count: @() { count = (count + 1) },
+            }
+        }
+
+        let counter = createCounter()
+        let f = counter.count
+        f()
+        f()
+        f()
+
+        let g = counter.getCount
+
+        <: g()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn independent_instances() {
+        let res = exe(r#"
+        @createCounter() {
+            var count = 0
+            @() {
+                count += 1
+                count
+            }
+        }
+        let a = createCounter()
+        let b = createCounter()
+        a()
+        a()
+        b()
+        <: a()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn nested() {
+        let res = exe(r#"
+        @f(x) {
+            @(y) {
+                @(z) {
+                    x + y + z
+                }
+            }
+        }
+        <: f(1)(2)(3)
+        "#)
+        .unwrap();
+        assert_eq!(res, num(6.0));
+    }
+
+    #[test]
+    fn global_updated_after_definition() {
+        let res = exe(r#"
+        var x = 1
+        @f() { x }
+        x = 2
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn namespace() {
+        let res = exe(r#"
+        :: Ns {
+            @f() { g() }
+            @g() { "ai" }
+        }
+        <: Ns:f()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn shadowing() {
+        let res = exe(r#"
+        let x = "outer"
+        @f() {
+            let x = "inner"
+            @() { x }
+        }
+        <: f()()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("inner"));
+    }
+}
+
+mod test_return {
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn early_return() {
+        let res = exe(r#"
+        @f() {
+            if true {
+                return "ai"
+            }
+
+            "pope"
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn early_return_nested() {
+        let res = exe(r#"
+        @f() {
+            if true {
+                if true {
+                    return "ai"
+                }
+            }
+
+            "pope"
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn inside_eval() {
+        let res = exe(r#"
+        @f() {
+            let a = eval {
+                return 1
+            }
+            2
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+
+    #[test]
+    fn inner_function() {
+        let res = exe(r#"
+        @f() {
+            @g() {
+                return 1
+            }
+            g()
+            2
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn short_circuit() {
+        let res = exe(r#"
+        @f() {
+            true && eval { return "ai" }
+            "pope"
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn outside_function() {
+        assert!(exe("return 1").is_err());
+    }
+}
+
+mod jump {
+    use crate::common::{exe, num};
+
+    #[test]
+    fn break_outside_loop() {
+        let err = exe("break").unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+        assert_eq!(
+            err.message(),
+            "unlabeled break must be inside for / each / while / do-while / loop"
+        );
+
+        // 関数の外側のループは対象にならない
+        let err = exe(r#"
+        for 1 {
+            @f() {
+                break
+            }
+        }
+        "#)
+        .unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+    }
+
+    #[test]
+    fn continue_outside_loop() {
+        let err = exe("continue").unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+        assert_eq!(
+            err.message(),
+            "continue must be inside for / each / while / do-while / loop"
+        );
+
+        // 呼び出されない関数の中でも文法エラーになる
+        let err = exe(r#"
+        @f() {
+            if true continue
+        }
+        "#)
+        .unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+    }
+
+    #[test]
+    fn break_labeled_for() {
+        let res = exe(r#"
+        var count = 0
+        #outer: for (let i, 3) {
+            for (let j, 3) {
+                if Core:eq(j, 1) break #outer
+                count += 1
+            }
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+
+    #[test]
+    fn continue_labeled_each() {
+        let res = exe(r#"
+        var count = 0
+        #outer: each let a, [1, 2, 3] {
+            each let b, [1, 2, 3] {
+                if Core:eq(b, 2) continue #outer
+                count += 1
+            }
+            count += 10
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn break_labeled_loop() {
+        let res = exe(r#"
+        var count = 0
+        #outer: loop {
+            while true {
+                for 3 {
+                    count += 1
+                    if Core:eq(count, 5) break #outer
+                }
+            }
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(5.0));
+    }
+
+    #[test]
+    fn continue_labeled_while() {
+        let res = exe(r#"
+        var i = 0
+        var count = 0
+        #outer: while Core:lt(i, 3) {
+            i += 1
+            loop {
+                count += 1
+                continue #outer
+            }
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn break_innermost_with_same_label() {
+        let res = exe(r#"
+        var count = 0
+        #a: for 2 {
+            #a: for 3 {
+                break #a
+            }
+            count += 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn undefined_label() {
+        let err = exe(r#"
+        #a: for 1 {
+            break #b
+        }
+        "#)
+        .unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+        assert_eq!(err.message(), "label \"b\" is not defined");
+
+        // 関数の外側のループのラベルは参照できない
+        let err = exe(r#"
+        #a: for 1 {
+            @f() {
+                continue #a
+            }
+            f()
+        }
+        "#)
+        .unwrap_err();
+        assert_eq!(err.name(), "Syntax");
+    }
+
+    #[test]
+    fn label_for_non_loop_statement() {
+        assert!(exe("#a: var x = 1").is_err());
+        assert!(exe("#a: <: 1").is_err());
+        assert!(exe("for 1 { break # a }").is_err());
+    }
+}
+
+mod test_for {
+    use crate::common::{exe, num};
+
+    #[test]
+    fn range() {
+        let res = exe(r#"
+        var count = 0
+        for (let i, 10) {
+            count += i + 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(55.0));
+    }
+
+    #[test]
+    fn initial_value() {
+        let res = exe(r#"
+        var count = 0
+        for (let i = 2, 5) {
+            count += i
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(20.0));
+    }
+
+    #[test]
+    fn times() {
+        let res = exe(r#"
+        var count = 0
+        for (10) {
+            count += 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(10.0));
+    }
+
+    #[test]
+    fn single_statement() {
+        let res = exe(r#"
+        var count = 0
+        for 10 count += 1
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(10.0));
+    }
+
+    #[test]
+    fn test_break() {
+        let res = exe(r#"
+        var count = 0
+        for (let i, 20) {
+            if Core:eq(i, 11) break
+            count += i
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(55.0));
+    }
+
+    #[test]
+    fn test_continue() {
+        let res = exe(r#"
+        var count = 0
+        for (let i, 10) {
+            if Core:eq(i, 5) continue
+            count += 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(9.0));
+    }
+
+    #[test]
+    fn test_return() {
+        let res = exe(r#"
+        @f() {
+            for (let i, 10) {
+                if Core:eq(i, 3) return i
+            }
+            "unreachable"
+        }
+        <: f()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn immutable_counter() {
+        assert!(exe(r#"
+        for (let i, 10) {
+            i = 1
+        }
+        "#)
+        .is_err());
+    }
+
+    #[test]
+    fn scope() {
+        assert!(exe(r#"
+        for (let i, 10) {}
+        <: i
+        "#)
+        .is_err());
+    }
+
+    #[test]
+    fn capture_per_iteration() {
+        let res = exe(r#"
+        let fns = [null, null, null]
+        for (let i, 3) {
+            fns[i] = @() { i }
+        }
+        <: fns[1]()
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+}
+
+mod each {
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn basic() {
+        let res = exe(r#"
+        var count = 0
+        each let item, [1, 2, 3] {
+            count += item
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(6.0));
+    }
+
+    #[test]
+    fn destructuring() {
+        let res = exe(r#"
+        var result = ""
+        each let { a: x, b: y }, [{ a: "ai", b: "chan" }] {
+            result = y
+        }
+        <: result
+        "#)
+        .unwrap();
+        assert_eq!(res, str("chan"));
+    }
+
+    #[test]
+    fn test_break() {
+        let res = exe(r#"
+        var count = 0
+        each let item, [1, 2, 3, 4] {
+            if Core:eq(item, 3) break
+            count += item
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn live_array() {
+        let res = exe(r#"
+        let items = [1, 2, 3]
+        var count = 0
+        each let item, items {
+            items[2] = 10
+            count += item
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(13.0));
+    }
+
+    #[test]
+    fn not_array() {
+        assert!(exe("each let item, 1 {}").is_err());
+    }
+}
+
+mod test_loop {
+    use crate::common::{exe, num};
+
+    #[test]
+    fn basic() {
+        let res = exe(r#"
+        var count = 0
+        loop {
+            if Core:eq(count, 10) break
+            count = count + 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(10.0));
+    }
+
+    #[test]
+    fn test_continue() {
+        let res = exe(r#"
+        var a = 0
+        var b = 0
+        loop {
+            a += 1
+            if Core:gt(a, 10) break
+            if Core:eq(Core:mod(a, 2), 0) continue
+            b += 1
+        }
+        <: b
+        "#)
+        .unwrap();
+        assert_eq!(res, num(5.0));
+    }
+
+    #[test]
+    fn nested() {
+        let res = exe(r#"
+        var count = 0
+        for (let i, 3) {
+            loop {
+                count += 1
+                break
+            }
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn test_while() {
+        let res = exe(r#"
+        var count = 0
+        while Core:lt(count, 42) {
+            count += 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(42.0));
+    }
+
+    #[test]
+    fn while_false() {
+        let res = exe(r#"
+        var count = 0
+        while false {
+            count += 1
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(0.0));
+    }
+
+    #[test]
+    fn do_while() {
+        let res = exe(r#"
+        var count = 0
+        do {
+            count += 1
+        } while Core:lt(count, 42)
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(42.0));
+    }
+
+    #[test]
+    fn do_while_false() {
+        let res = exe(r#"
+        var count = 0
+        do {
+            count += 1
+        } while false
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+}
+
+mod block {
+    use aiscript_engine::Value;
+
+    use crate::common::{exe, num};
+
+    #[test]
+    fn empty() {
+        let res = exe("<: eval {}").unwrap();
+        assert_eq!(res, Value::Null);
+    }
+
+    #[test]
+    fn value() {
+        let res = exe(r#"
+        <: eval {
+            let a = 1
+            a + 1
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn scope() {
+        assert!(exe(r#"
+        eval {
+            let a = 1
+        }
+        <: a
+        "#)
+        .is_err());
+    }
+}
+
+mod test_match {
+    use aiscript_engine::Value;
+
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn basic() {
+        let res = exe(r#"
+        <: match 2 {
+            case 1 => "a"
+            case 2 => "b"
+            case 3 => "c"
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, str("b"));
+    }
+
+    #[test]
+    fn no_default() {
+        let res = exe(r#"
+        <: match 42 {
+            case 1 => "a"
+            case 2 => "b"
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, Value::Null);
+    }
+
+    #[test]
+    fn default() {
+        let res = exe(r#"
+        <: match 42 {
+            case 1 => "a"
+            case 2 => "b"
+            default => "d"
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, str("d"));
+    }
+
+    #[test]
+    fn block() {
+        let res = exe(r#"
+        <: match 2 {
+            case 1 => 1
+            case 2 => {
+                let a = 1
+                let b = 2
+                (a + b)
+            }
+            case 3 => 3
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn test_return() {
+        let res = exe(r#"
+        @f(x) {
+            match x {
+                case 1 => {
+                    return "ai"
+                }
+            }
+            "foo"
+        }
+        <: f(1)
+        "#)
+        .unwrap();
+        assert_eq!(res, str("ai"));
+    }
+
+    #[test]
+    fn evaluate_once() {
+        let res = exe(r#"
+        var count = 0
+        @f() {
+            count += 1
+            count
+        }
+        match f() {
+            case 2 => "a"
+            case 3 => "b"
+            default => "c"
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+
+    #[test]
+    fn lazy_arms() {
+        let res = exe(r#"
+        var count = 0
+        @f(x) {
+            count += 1
+            x
+        }
+        match 1 {
+            case f(1) => "a"
+            case f(2) => "b"
+        }
+        <: count
+        "#)
+        .unwrap();
+        assert_eq!(res, num(1.0));
+    }
+
+    #[test]
+    fn string() {
+        let res = exe(r#"
+        <: match "ai" {
+            case "chan" => 1
+            case "ai" => 2
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn same_reference() {
+        let res = exe(r#"
+        let a = [1]
+        <: match a {
+            case [1] => "other"
+            case a => "same"
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, str("same"));
+
+        let res = exe(r#"
+        let o = { x: 1 }
+        let p = { x: 1 }
+        <: match o {
+            case p => "other"
+            case o => "same"
+        }
+        "#)
+        .unwrap();
+        assert_eq!(res, str("same"));
+    }
+}
+
+mod template {
+    use crate::common::{exe, str};
+
+    #[test]
+    fn basic() {
+        let res = exe(r#"
+        let name = "kawaii"
+        <: `Ai is {name}!`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("Ai is kawaii!"));
+    }
+
+    #[test]
+    fn primitives() {
+        let res = exe(r#"
+        <: `{1} {0.5} {true} {null}`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("1 0.5 true null"));
+    }
+
+    #[test]
+    fn exponent() {
+        let res = exe(r#"
+        <: `{Math:pow(10, 21)} {Math:pow(10, 0 - 7)} {Math:pow(10, 20)}`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("1e+21 1e-7 100000000000000000000"));
+    }
+
+    #[test]
+    fn arr_and_obj() {
+        let res = exe(r#"
+        let a = [1, "a", null]
+        let o = { a: 1, b: [] }
+        <: `{a} {o}`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("[ 1, \"a\", null ] { a: 1, b: [  ] }"));
+    }
+
+    #[test]
+    fn function() {
+        let res = exe(r#"
+        @f(x, y) {}
+        <: `{f} {print}`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("@( x, y ) { ... } @( ?? ) { native code }"));
+    }
+
+    #[test]
+    fn expression() {
+        let res = exe(r#"
+        @f() { "ai" }
+        <: `{1 + 2}{f()}`
+        "#)
+        .unwrap();
+        assert_eq!(res, str("3ai"));
+    }
+}
+
+mod index {
+    use aiscript_engine::Value;
+
+    use crate::common::{exe, num, str};
+
+    #[test]
+    fn arr() {
+        let res = exe(r#"
+        let a = [1, 2, 3]
+        <: a[1]
+        "#)
+        .unwrap();
+        assert_eq!(res, num(2.0));
+    }
+
+    #[test]
+    fn arr_out_of_range() {
+        assert!(exe("<: [1, 2, 3][3]").is_err());
+        assert!(exe("<: [1, 2, 3][0.5]").is_err());
+        assert!(exe("<: [1, 2, 3][-1]").is_err());
+        assert!(exe("<: [][0]").is_err());
+        assert!(exe(r#"
+        let a = []
+        a[0] = 1
+        "#)
+        .is_err());
+    }
+
+    #[test]
+    fn arr_assign() {
+        let res = exe(r#"
+        let a = [1, 2, 3]
+        a[1] = 5
+        <: a[1]
+        "#)
+        .unwrap();
+        assert_eq!(res, num(5.0));
+    }
+
+    #[test]
+    fn arr_with_non_number() {
+        assert!(exe("<: [1, 2, 3]['0']").is_err());
+    }
+
+    #[test]
+    fn obj() {
+        let res = exe(r#"
+        let o = { a: "ai", b: "chan" }
+        <: o["b"]
+        "#)
+        .unwrap();
+        assert_eq!(res, str("chan"));
+    }
+
+    #[test]
+    fn obj_missing_key() {
+        let res = exe(r#"
+        let o = { a: 1 }
+        <: o["b"]
+        "#)
+        .unwrap();
+        assert_eq!(res, Value::Null);
+    }
+
+    #[test]
+    fn obj_assign() {
+        let res = exe(r#"
+        let o = {}
+        o["a"] = 1
+        o["a"] += 2
+        <: o.a
+        "#)
+        .unwrap();
+        assert_eq!(res, num(3.0));
+    }
+
+    #[test]
+    fn obj_with_non_string() {
+        assert!(exe("<: { a: 1 }[0]").is_err());
+        assert!(exe(r#"
+        let o = {}
+        o[0] = 1
+        "#)
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_target() {
+        assert!(exe("<: 'ai'[0]").is_err());
+        assert!(exe("<: 1[0]").is_err());
+        assert!(exe("<: null[0]").is_err());
+        assert!(exe(r#"
+        var s = "ai"
+        s[0] = "b"
+        "#)
+        .is_err());
+    }
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use aiscript_engine::{Clock, Interpreter, InterpreterOpts, Parser, Utf16String, Value};
+use common::{num, str};
+
+/// 手動で進める時計
+struct FakeClock {
+    now: Rc<Cell<f64>>,
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+
+    fn sleep(&self, ms: f64) {
+        self.now.set(self.now.get() + ms);
+    }
+}
+
+struct TestOpts {
+    now: Rc<Cell<f64>>,
+    out: Rc<RefCell<Vec<Value>>>,
+    max_step: Option<usize>,
+}
+
+impl InterpreterOpts for TestOpts {
+    fn out(&self, value: Value) {
+        self.out.borrow_mut().push(value);
+    }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        Rc::new(FakeClock {
+            now: Rc::clone(&self.now),
+        })
+    }
+
+    fn max_step(&self) -> Option<usize> {
+        self.max_step
+    }
+}
+
+struct Runner {
+    interpreter: Interpreter,
+    now: Rc<Cell<f64>>,
+    out: Rc<RefCell<Vec<Value>>>,
+}
+
+impl Runner {
+    fn new(source: &str) -> Self {
+        let now = Rc::new(Cell::new(0.0));
+        let out = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new(Rc::new(TestOpts {
+            now: Rc::clone(&now),
+            out: Rc::clone(&out),
+            max_step: None,
+        }));
+        let ast = Parser::new().parse(&Utf16String::from(source)).unwrap();
+        interpreter.run(&ast).unwrap();
+        return Runner {
+            interpreter,
+            now,
+            out,
+        };
+    }
+
+    /// 時計を`now`まで進め、タイマーを実行します。
+    fn advance(&mut self, now: f64) {
+        self.now.set(now);
+        self.interpreter.tick(now).unwrap();
+    }
+
+    /// これまでの出力を取り出します。
+    fn take_out(&self) -> Vec<Value> {
+        return self.out.borrow_mut().drain(..).collect();
+    }
+}
+
+#[test]
+fn timeout() {
+    let mut runner = Runner::new(r#"Async:timeout(100, @() { <: "fired" })"#);
+    assert_eq!(runner.interpreter.next_timer(), Some(100.0));
+    runner.advance(99.0);
+    assert_eq!(runner.take_out(), vec![]);
+    runner.advance(100.0);
+    assert_eq!(runner.take_out(), vec![str("fired")]);
+    runner.advance(1000.0);
+    assert_eq!(runner.take_out(), vec![]);
+    assert_eq!(runner.interpreter.next_timer(), None);
+}
+
+#[test]
+fn sleep_uses_clock() {
+    let runner = Runner::new("Core:sleep(1000)");
+    assert_eq!(runner.now.get(), 1000.0);
+}
+
+#[test]
+fn sleep_does_not_count_steps() {
+    let now = Rc::new(Cell::new(0.0));
+    let mut interpreter = Interpreter::new(Rc::new(TestOpts {
+        now: Rc::clone(&now),
+        out: Rc::new(RefCell::new(Vec::new())),
+        max_step: Some(1000),
+    }));
+    let ast = Parser::new()
+        .parse(&Utf16String::from("Core:sleep(1000000)"))
+        .unwrap();
+    interpreter.run(&ast).unwrap();
+    assert_eq!(now.get(), 1000000.0);
+}
+
+#[test]
+fn timeout_order() {
+    let mut runner = Runner::new(
+        r#"
+        Async:timeout(200, @() { <: 2 })
+        Async:timeout(100, @() { <: 1 })
+        Async:timeout(200, @() { <: 3 })
+        "#,
+    );
+    runner.advance(500.0);
+    assert_eq!(runner.take_out(), vec![num(1.0), num(2.0), num(3.0)]);
+}
+
+#[test]
+fn cancel_timeout() {
+    let mut runner = Runner::new(
+        r#"
+        let cancel = Async:timeout(100, @() { <: "fired" })
+        cancel()
+        "#,
+    );
+    runner.advance(100.0);
+    assert_eq!(runner.take_out(), vec![]);
+}
+
+#[test]
+fn interval() {
+    let mut runner = Runner::new(
+        r#"
+        var count = 0
+        Async:interval(100, @() {
+            count += 1
+            <: count
+        })
+        "#,
+    );
+    runner.advance(100.0);
+    runner.advance(150.0);
+    runner.advance(200.0);
+    runner.advance(300.0);
+    assert_eq!(runner.take_out(), vec![num(1.0), num(2.0), num(3.0)]);
+}
+
+#[test]
+fn interval_immediate() {
+    let mut runner = Runner::new(r#"Async:interval(100, @() { <: "tick" }, true)"#);
+    assert_eq!(runner.take_out(), vec![str("tick")]);
+    runner.advance(100.0);
+    assert_eq!(runner.take_out(), vec![str("tick")]);
+}
+
+#[test]
+fn cancel_interval_from_callback() {
+    let mut runner = Runner::new(
+        r#"
+        var count = 0
+        var cancel = null
+        cancel = Async:interval(100, @() {
+            count += 1
+            <: count
+            if count == 2 cancel()
+        })
+        "#,
+    );
+    for now in [100.0, 200.0, 300.0, 400.0] {
+        runner.advance(now);
+    }
+    assert_eq!(runner.take_out(), vec![num(1.0), num(2.0)]);
+    assert_eq!(runner.interpreter.next_timer(), None);
+}
+
+#[test]
+fn abort_clears_timers() {
+    let mut runner = Runner::new(r#"Async:interval(100, @() { <: "tick" })"#);
+    runner.interpreter.abort();
+    runner.advance(100.0);
+    assert_eq!(runner.take_out(), vec![]);
+    assert_eq!(runner.interpreter.next_timer(), None);
+}
+
+#[test]
+fn callback_error() {
+    let mut runner = Runner::new(r#"Async:timeout(100, @() { Core:abort("oops") })"#);
+    runner.now.set(100.0);
+    assert!(runner.interpreter.tick(100.0).is_err());
+}
@@ -1,5 +1,3 @@
-use std::u16;
-
 use proc_macro::TokenStream;
 use quote::TokenStreamExt;
 use syn::{parse_macro_input, Expr, Lit, LitChar, LitStr};